@@ -0,0 +1,296 @@
+//! `cargo wgsl-ln`, validate, dump and reflect the `wgsl_ln` shaders of a workspace.
+//!
+//! Runs `cargo check` on the workspace with `WGSL_LN_DUMP` set, which makes `wgsl!`
+//! write every validated shader, its reflection data and the crate's exports to
+//! `target/cargo-wgsl-ln/dump`. Nothing is code generated, so this is as fast
+//! as `cargo check`.
+//!
+//! ```text
+//! cargo install --path cargo-wgsl-ln
+//! cargo wgsl-ln check -- --features fog
+//! ```
+//!
+//! Since results are cached like `cargo check`, shaders removed from the workspace
+//! are still listed until `cargo wgsl-ln clean`.
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, ExitCode},
+};
+
+const USAGE: &str = "\
+Validate, dump and reflect the wgsl_ln shaders of a workspace.
+
+Usage: cargo wgsl-ln <COMMAND> [-- <CARGO CHECK ARGS>]
+
+Commands:
+    check              Validate every `wgsl!` in the workspace
+    dump [DIR]         Print the composed shaders, or write them to DIR
+    exports            List `#[wgsl_export]`s and their items
+    graph [--mermaid]  Print the dependencies between exports as Graphviz or mermaid
+    reflect [LABEL]    Print reflection data as JSON, one shader per line
+    clean              Remove cached results
+
+Arguments after `--` are passed to `cargo check`, which defaults to
+`--workspace --all-targets`.";
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // Invoked as `cargo wgsl-ln ..`.
+    if args.first().is_some_and(|x| x == "wgsl-ln") {
+        args.remove(0);
+    }
+    let (args, cargo_args) = match args.iter().position(|x| x == "--") {
+        Some(pos) => (args[..pos].to_vec(), args[pos + 1..].to_vec()),
+        None => (args, Vec::new()),
+    };
+    let Some(root) = root() else {
+        eprintln!("error: unable to run `cargo metadata`");
+        return ExitCode::FAILURE;
+    };
+    let dump = root.join("dump");
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["clean"] => {
+            if root.exists() {
+                if let Err(e) = std::fs::remove_dir_all(&root) {
+                    eprintln!("error: unable to remove \"{}\": {}", root.display(), e);
+                    return ExitCode::FAILURE;
+                }
+            }
+            ExitCode::SUCCESS
+        }
+        ["check"] => check(&root, &cargo_args),
+        ["dump", rest @ ..] if rest.len() <= 1 => {
+            if check(&root, &cargo_args) != ExitCode::SUCCESS {
+                return ExitCode::FAILURE;
+            }
+            for file in files(&dump, "wgsl") {
+                let Ok(source) = std::fs::read_to_string(&file) else {
+                    continue;
+                };
+                match rest.first() {
+                    Some(dir) => {
+                        let out = Path::new(dir).join(file.strip_prefix(&dump).unwrap());
+                        let written = std::fs::create_dir_all(out.parent().unwrap())
+                            .and_then(|_| std::fs::write(&out, source));
+                        if let Err(e) = written {
+                            eprintln!("error: unable to write \"{}\": {}", out.display(), e);
+                            return ExitCode::FAILURE;
+                        }
+                    }
+                    None => {
+                        println!("// {}", name(&dump, &file));
+                        println!("{}", source);
+                    }
+                }
+            }
+            ExitCode::SUCCESS
+        }
+        ["exports"] => {
+            if check(&root, &cargo_args) != ExitCode::SUCCESS {
+                return ExitCode::FAILURE;
+            }
+            for (krate, exports) in exports(&dump) {
+                for (name, item, _) in exports {
+                    println!("${} {}::{}", name, krate, item);
+                }
+            }
+            ExitCode::SUCCESS
+        }
+        ["graph", rest @ ..] if rest.is_empty() || rest == ["--mermaid"] => {
+            if check(&root, &cargo_args) != ExitCode::SUCCESS {
+                return ExitCode::FAILURE;
+            }
+            print!("{}", graph(&exports(&dump), !rest.is_empty()));
+            ExitCode::SUCCESS
+        }
+        ["reflect", rest @ ..] if rest.len() <= 1 => {
+            if check(&root, &cargo_args) != ExitCode::SUCCESS {
+                return ExitCode::FAILURE;
+            }
+            for file in files(&dump, "json") {
+                let stem = file.file_stem().unwrap_or_default();
+                if rest.first().is_some_and(|label| stem != *label) {
+                    continue;
+                }
+                if let Ok(json) = std::fs::read_to_string(&file) {
+                    println!("{}", json);
+                }
+            }
+            ExitCode::SUCCESS
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// `target/cargo-wgsl-ln` of the workspace.
+fn root() -> Option<PathBuf> {
+    let output = cargo()
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .output()
+        .ok()?;
+    let metadata = String::from_utf8(output.stdout).ok()?;
+    let (_, rest) = metadata.split_once(r#""target_directory":""#)?;
+    // Unescape the JSON string.
+    let mut dir = String::new();
+    let mut chars = rest.chars();
+    loop {
+        match chars.next()? {
+            '"' => break,
+            '\\' => dir.push(chars.next()?),
+            c => dir.push(c),
+        }
+    }
+    Some(PathBuf::from(dir).join("cargo-wgsl-ln"))
+}
+
+fn cargo() -> Command {
+    Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+}
+
+/// Run `cargo check` in a separate target directory, so results of a normal build are not invalidated.
+fn check(root: &Path, args: &[String]) -> ExitCode {
+    let mut command = cargo();
+    command
+        .arg("check")
+        .arg("--target-dir")
+        .arg(root.join("build"))
+        .env("WGSL_LN_DUMP", root.join("dump"));
+    match args.is_empty() {
+        true => command.args(["--workspace", "--all-targets"]),
+        false => command.args(args),
+    };
+    match command.status() {
+        Ok(status) if status.success() => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: unable to run `cargo check`: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Files with an extension in `<dump>/<package>/<crate>/`, sorted.
+fn files(dump: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut result = Vec::new();
+    for package in read_dir(dump) {
+        for krate in read_dir(&package) {
+            result.extend(
+                read_dir(&krate)
+                    .into_iter()
+                    .filter(|x| x.extension().is_some_and(|x| x == extension)),
+            );
+        }
+    }
+    result.sort();
+    result
+}
+
+fn read_dir(dir: &Path) -> Vec<PathBuf> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|x| Some(x.ok()?.path())).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Exports of each crate as `(name, item, dependencies)`, sorted by crate.
+#[allow(clippy::type_complexity)]
+fn exports(dump: &Path) -> Vec<(String, Vec<(String, String, Vec<String>)>)> {
+    let mut result = Vec::new();
+    for file in files(dump, "txt") {
+        let krate = file.parent().and_then(Path::file_name).unwrap_or_default();
+        let Ok(exports) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let exports = exports
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.split_whitespace().map(str::to_owned);
+                Some((words.next()?, words.next()?, words.collect()))
+            })
+            .collect();
+        result.push((krate.to_string_lossy().into_owned(), exports));
+    }
+    result
+}
+
+/// Graphviz or mermaid graph of exports grouped by crate,
+/// with an edge from each export to the exports pasted into it.
+///
+/// Dependencies are resolved to an export of the same crate if possible, then any crate.
+#[allow(clippy::type_complexity)]
+fn graph(exports: &[(String, Vec<(String, String, Vec<String>)>)], mermaid: bool) -> String {
+    let resolve = |krate: &str, name: &str| {
+        let exported = |krate: &str| {
+            exports
+                .iter()
+                .any(|(k, items)| k == krate && items.iter().any(|x| x.0 == name))
+        };
+        match exports.iter().map(|x| x.0.as_str()).find(|k| exported(k)) {
+            Some(_) if exported(krate) => format!("{}__{}", krate, name),
+            Some(other) => format!("{}__{}", other, name),
+            None => name.to_owned(),
+        }
+    };
+    let mut result = String::new();
+    let mut edges = Vec::new();
+    match mermaid {
+        true => result.push_str("flowchart LR\n"),
+        false => result.push_str("digraph wgsl_ln {\n    rankdir=LR;\n"),
+    }
+    for (krate, exports) in exports {
+        match mermaid {
+            true => result.push_str(&format!("    subgraph {}\n", krate)),
+            false => result.push_str(&format!(
+                "    subgraph \"cluster_{}\" {{\n        label=\"{}\";\n",
+                krate, krate
+            )),
+        }
+        for (name, item, dependencies) in exports {
+            match mermaid {
+                true => result.push_str(&format!(
+                    "        {}__{}[\"${} {}\"]\n",
+                    krate, name, name, item
+                )),
+                false => result.push_str(&format!(
+                    "        \"{}__{}\" [label=\"${}\\n{}\"];\n",
+                    krate, name, name, item
+                )),
+            }
+            let from = format!("{}__{}", krate, name);
+            edges.extend(
+                dependencies
+                    .iter()
+                    .map(|x| (from.clone(), resolve(krate, x))),
+            );
+        }
+        match mermaid {
+            true => result.push_str("    end\n"),
+            false => result.push_str("    }\n"),
+        }
+    }
+    for (from, to) in edges {
+        match mermaid {
+            true => result.push_str(&format!("    {} --> {}\n", from, to)),
+            false => result.push_str(&format!("    \"{}\" -> \"{}\";\n", from, to)),
+        }
+    }
+    if !mermaid {
+        result.push_str("}\n");
+    }
+    result
+}
+
+/// `package/crate/label` of a dumped file.
+fn name(dump: &Path, file: &Path) -> String {
+    let path = file.strip_prefix(dump).unwrap_or(file).with_extension("");
+    path.to_string_lossy().replace('\\', "/")
+}
@@ -0,0 +1,15 @@
+use wgsl_ln::wgsl;
+
+pub static LIT: &str = wgsl!(
+    $extern fn engine_lighting(p: vec3<f32>) -> vec3<f32>;
+    $extern @group(0) @binding(0) var<uniform> light_count: u32;
+
+    @fragment
+    fn fragment(@location(0) position: vec3<f32>) -> @location(0) vec4<f32> {
+        return vec4(engine_lighting(position) * f32(light_count), 1.0);
+    }
+);
+
+pub fn main() {
+    println!("{}", LIT);
+}
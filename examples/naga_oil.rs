@@ -1,16 +1,20 @@
+#[cfg(feature = "naga_oil")]
+use wgsl_ln::wgsl_oil;
 #[allow(unused)]
 use wgsl_ln::{wgsl, wgsl_export};
 
 #[cfg(feature = "naga_oil")]
 #[wgsl_export(Vertex)]
-pub static VERTEX: &str = wgsl!(
+pub static VERTEX: &str = wgsl_oil!(
+    #![modules("examples/shaders/awesome_game_engine.wgsl")]
     #import awesome_game_engine::Vertex;
 );
 
 #[cfg(feature = "naga_oil")]
 #[wgsl_export(VertexOutput)]
-pub static VERTEX_OUT: &str = wgsl!(
-    #import awesome_game_engine::{VertexOut}
+pub static VERTEX_OUT: &str = wgsl_oil!(
+    #![modules("examples/shaders/awesome_game_engine.wgsl")]
+    #import awesome_game_engine::{VertexOutput}
 );
 
 #[cfg(feature = "naga_oil")]
@@ -23,9 +27,84 @@ pub static VERTEX_SHADER: &str = wgsl!(
     }
 );
 
+#[cfg(feature = "naga_oil")]
+pub static FRAGMENT_SHADER: &str = wgsl_oil!(
+    #![stub(
+        bevy_pbr::pbr_types::PbrInput as struct { flags: u32, color: vec4<f32> },
+        bevy_pbr::pbr_functions::pbr_input_new as fn() -> PbrInput,
+        bevy_pbr::pbr_functions::apply_pbr_lighting as fn(input: PbrInput) -> vec4<f32>,
+    )]
+    #import bevy_pbr::pbr_functions::{pbr_input_new, apply_pbr_lighting};
+
+    @fragment
+    fn fragment() -> @location(0) vec4<f32> {
+        var input = pbr_input_new();
+        return apply_pbr_lighting(input);
+    }
+);
+
+#[cfg(feature = "naga_oil")]
+#[wgsl_export(lighting)]
+pub static LIGHTING: &str = wgsl!(
+    #![oil]
+    #define_import_path my_game::lighting
+
+    fn half_lambert(n: vec3<f32>, l: vec3<f32>) -> f32 {
+        return dot(n, l) * 0.5 + 0.5;
+    }
+);
+
+#[cfg(feature = "naga_oil")]
+pub static LIT_SHADER: &str = wgsl_oil!(
+    #![modules(my_game::lighting)]
+    #import my_game::lighting::half_lambert;
+
+    fn lit(n: vec3<f32>) -> f32 {
+        return half_lambert(n, vec3(0.0, 1.0, 0.0));
+    }
+);
+
+#[cfg(feature = "naga_oil")]
+pub static BRANCHES: &str = wgsl_oil!(
+    #![defs(SHADOWS, FOG, MSAA_SAMPLES = 4u)]
+    #![permutations]
+    fn branches() -> f32 {
+        #ifdef SHADOWS
+        return 1.0;
+        #else ifdef FOG
+        return 2.0;
+        #else if MSAA_SAMPLES == 4
+        return 3.0;
+        #else
+        return 0.0;
+        #endif
+    }
+);
+
+#[wgsl_export(saturate_half)]
+pub static SATURATE_HALF: &str = wgsl!(
+    fn saturate_half(x: f32) -> f32 {
+        return clamp(x * 0.5, 0.0, 1.0);
+    }
+);
+
+#[cfg(feature = "naga_oil")]
+pub static MIXED: &str = wgsl_oil!(
+    #import bevy_pbr::forward_io::VertexOutput
+
+    @fragment
+    fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+        return vec4($saturate_half(in.position.x));
+    }
+);
+
 #[cfg(feature = "naga_oil")]
 pub fn main() {
     println!("{}", VERTEX_SHADER);
+    println!("{}", FRAGMENT_SHADER);
+    println!("{}", LIT_SHADER);
+    println!("{}", BRANCHES);
+    println!("{}", MIXED);
 }
 
 #[cfg(not(feature = "naga_oil"))]
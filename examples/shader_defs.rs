@@ -0,0 +1,23 @@
+#[cfg(feature = "naga_oil")]
+use wgsl_ln::shader_defs;
+
+pub const MSAA_SAMPLES: u32 = 4;
+
+#[cfg(feature = "naga_oil")]
+pub fn main() {
+    let defs = shader_defs! {
+        MSAA_SAMPLES: u32 = MSAA_SAMPLES,
+        LIGHT_BIAS: i32 = -2,
+        SHADOWS: bool = cfg!(debug_assertions),
+        #[cfg(feature = "naga_oil")]
+        NAGA_OIL,
+    };
+    let mut defs: Vec<_> = defs.into_iter().collect();
+    defs.sort_by(|a, b| a.0.cmp(&b.0));
+    println!("{:?}", defs);
+}
+
+#[cfg(not(feature = "naga_oil"))]
+pub fn main() {
+    println!("Enable feature `naga_oil` or this will fail to compile.");
+}
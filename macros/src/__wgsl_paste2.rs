@@ -0,0 +1,118 @@
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+/// State of the paste chain, `__wgsl_chain [$($defined)*] {$($pasted)*} {$($body)*} [$($pending)*]`.
+///
+/// The leading `__wgsl_chain` tells it apart from a shader that starts with a bracket.
+/// Pasted items are accumulated separately, so the body is passed through `macro_rules!`
+/// as a single token tree and only sanitized by the first `wgsl!`.
+pub struct Chain {
+    /// Items pasted in so far.
+    pub defined: Vec<Ident>,
+    /// Items pasted in, newest first. Also contains other tokens,
+    /// like `#![module_source(..)]` inserted by `__wgsl_module_*`.
+    pub pasted: TokenStream,
+    pub body: TokenStream,
+    /// Imports found but not yet pasted, in order.
+    pub pending: Vec<Ident>,
+}
+
+/// Leading identifier of the paste chain.
+pub const MARKER: &str = "__wgsl_chain";
+
+fn idents(stream: TokenStream) -> Vec<Ident> {
+    stream
+        .into_iter()
+        .filter_map(|x| match x {
+            TokenTree::Ident(i) => Some(i),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Chain {
+    /// Returns `None` before the first paste.
+    pub fn parse(stream: TokenStream) -> Option<Chain> {
+        let mut iter = stream.into_iter();
+        let defined = match (iter.next(), iter.next()) {
+            (Some(TokenTree::Ident(marker)), Some(TokenTree::Group(g)))
+                if marker == MARKER && g.delimiter() == Delimiter::Bracket =>
+            {
+                idents(g.stream())
+            }
+            _ => return None,
+        };
+        let mut groups = Vec::new();
+        let mut pending = Vec::new();
+        let mut other = Vec::new();
+        for tt in iter {
+            match tt {
+                TokenTree::Group(g) if g.delimiter() == Delimiter::Brace && groups.len() < 2 => {
+                    groups.push(g.stream())
+                }
+                TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket && groups.len() == 2 => {
+                    pending = idents(g.stream())
+                }
+                tt => other.push(tt),
+            }
+        }
+        let [pasted, body] = <[TokenStream; 2]>::try_from(groups).ok()?;
+        let other = TokenStream::from_iter(other);
+        Some(Chain {
+            defined,
+            pasted: quote! {#other #pasted},
+            body,
+            pending,
+        })
+    }
+
+    pub fn to_tokens(&self) -> TokenStream {
+        let Chain {
+            defined,
+            pasted,
+            body,
+            pending,
+        } = self;
+        let marker = format_ident!("{}", MARKER);
+        quote! {#marker [#(#defined)*] {#pasted} {#body} [#(#pending)*]}
+    }
+}
+
+/// Braces let the paste chain expand in item position as well, see `#![doc_item(..)]`.
+pub fn __wgsl_paste2(stream: TokenStream) -> TokenStream {
+    let mut iter = stream.into_iter();
+    let Some(TokenTree::Ident(definition)) = iter.next() else {
+        abort!(
+            Span::call_site(),
+            "Expected `__wgsl_paste!($definition {to_be_pasted} $($tt)*)`!"
+        )
+    };
+    let Some(TokenTree::Group(pasted)) = iter.next() else {
+        abort!(
+            Span::call_site(),
+            "Expected `__wgsl_paste!($definition {to_be_pasted} $($tt)*)`!"
+        )
+    };
+    let pasted = pasted.stream();
+    let tokens: TokenStream = iter.collect();
+    let chain = match Chain::parse(tokens.clone()) {
+        // If defined, skip, if not defined, paste and define this item.
+        Some(chain) if chain.defined.contains(&definition) => chain,
+        Some(mut chain) => {
+            let accumulated = chain.pasted;
+            chain.pasted = quote! {#pasted #accumulated};
+            chain.defined.push(definition);
+            chain
+        }
+        // If no values defined, paste and define this item.
+        None => Chain {
+            defined: vec![definition],
+            pasted,
+            body: tokens,
+            pending: Vec::new(),
+        },
+    };
+    let chain = chain.to_tokens();
+    quote!(::wgsl_ln::wgsl! {#chain})
+}
@@ -0,0 +1,44 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use crate::{cache::target_dir, dump::write, json::file_stem, options::Options, wgsl2::fnv1a};
+
+/// File names and call sites of the shaders written for each crate, by directory,
+/// identical shaders share a file.
+///
+/// See `CATALOG` in `wgsl_catalog2.rs` for what state kept across expansions relies on.
+static MAPPING: Mutex<BTreeMap<PathBuf, BTreeSet<(String, String)>>> = Mutex::new(BTreeMap::new());
+
+/// `target/wgsl_ln/<package>/<crate>`.
+fn analyzer_dir() -> Option<PathBuf> {
+    let target = target_dir()?;
+    let package = std::env::var("CARGO_PKG_NAME").unwrap_or_default();
+    let krate = std::env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    Some(target.join("wgsl_ln").join(package).join(krate))
+}
+
+/// Write the output of a shader to `target/wgsl_ln/<package>/<crate>/<label>.wgsl`
+/// for wgsl-analyzer and `--cfg wgsl_ln_dump`, and `<label>.wgsl <file>:<line>:<column>`
+/// to `mapping.txt` in the same directory if the call site is known.
+pub fn write_shader(options: &Options, output: &str) {
+    let Some(dir) = analyzer_dir() else {
+        return;
+    };
+    let hash = format!("{:032x}", fnv1a(output.as_bytes()));
+    let name = format!("{}.wgsl", file_stem(options.label.as_deref(), &hash));
+    write(&dir.join(&name), output);
+    let Some(call_site) = &options.call_site else {
+        return;
+    };
+    let mut mapping = MAPPING.lock().unwrap();
+    let mapping = mapping.entry(dir.clone()).or_default();
+    mapping.insert((name, call_site.clone()));
+    let content: String = mapping
+        .iter()
+        .map(|(name, call_site)| format!("{} {}\n", name, call_site))
+        .collect();
+    write(&dir.join("mapping.txt"), &content);
+}
@@ -0,0 +1,82 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use crate::{
+    material::material_items, options::extract_options, wgsl2::fnv1a, wgsl_export2::item_ident,
+};
+
+/// Generate a `Handle<Shader>` constant and a function registering the shader
+/// for an exported `static`, like `load_internal_asset!`.
+///
+/// The handle is derived from the crate name and the exported name,
+/// so it is stable across builds.
+///
+/// With `#![embedded_asset("path.wgsl")]`, generate an asset path constant and a function
+/// registering the file written to `OUT_DIR` as an embedded asset instead, like `embedded_asset!`.
+///
+/// With `#![material(Name)]`, also generate an `AsBindGroup` struct for the material bindings.
+pub fn bevy_items(name: &Ident, item: &TokenStream, source: TokenStream) -> TokenStream {
+    let (vis, ident) = item_ident(name, item);
+    let crate_name = std::env::var("CARGO_PKG_NAME").unwrap_or_default();
+    let (source, options) = extract_options(source);
+    // `wgpu` descriptors and runtime shaders are not bevy shaders.
+    if options.wgpu || options.shader {
+        return TokenStream::new();
+    }
+    let material = match &options.material {
+        Some(material) => material_items(&vis, material, source),
+        None => TokenStream::new(),
+    };
+    if let Some((file, _)) = options.embedded_asset {
+        let path_const = format_ident!("{}_PATH", ident);
+        let embed = format_ident!("embed_{}", name);
+        let asset_path = format!("{}/{}", crate_name, file);
+        let embedded = format!("embedded://{}", asset_path);
+        let file = format!("/{}", file);
+        let path_doc = format!("Asset path of [`{}`].", ident);
+        let embed_doc = format!(
+            "Add [`{}`] to the `embedded` asset source as [`{}`].",
+            ident, path_const
+        );
+        return quote! {
+            #[doc = #path_doc]
+            #(#vis)* const #path_const: &str = #embedded;
+
+            #[doc = #embed_doc]
+            #(#vis)* fn #embed(app: &mut ::bevy::app::App) {
+                app.world_mut()
+                    .resource_mut::<::bevy::asset::io::embedded::EmbeddedAssetRegistry>()
+                    .insert_asset(
+                        ::std::path::PathBuf::from(concat!(env!("OUT_DIR"), #file)),
+                        ::std::path::Path::new(#asset_path),
+                        #ident.as_bytes(),
+                    );
+            }
+
+            #material
+        };
+    }
+    let handle = format_ident!("{}_HANDLE", ident);
+    let load = format_ident!("load_{}", name);
+    let uuid = fnv1a(format!("wgsl_ln::{}::{}", crate_name, name).as_bytes());
+    let path = format!("{}::{}.wgsl", crate_name, name);
+    let handle_doc = format!("Stable handle of [`{}`].", ident);
+    let load_doc = format!("Add [`{}`] to `Assets<Shader>` as [`{}`].", ident, handle);
+    quote! {
+        #[doc = #handle_doc]
+        #(#vis)* const #handle: ::bevy::asset::Handle<::bevy::render::render_resource::Shader> =
+            ::bevy::asset::Handle::weak_from_u128(#uuid);
+
+        #[doc = #load_doc]
+        #(#vis)* fn #load(app: &mut ::bevy::app::App) {
+            app.world_mut()
+                .resource_mut::<::bevy::asset::Assets<::bevy::render::render_resource::Shader>>()
+                .insert(
+                    #handle.id(),
+                    ::bevy::render::render_resource::Shader::from_wgsl(#ident, #path),
+                );
+        }
+
+        #material
+    }
+}
@@ -0,0 +1,46 @@
+// `target_dir` is shared with the `analyzer` feature and `--cfg wgsl_ln_dump`.
+#![cfg_attr(not(feature = "cache"), allow(dead_code))]
+
+use std::path::PathBuf;
+
+use crate::{dump::write, wgsl2::fnv1a};
+
+/// Changes to naga or validation flags invalidate the cache.
+const KEY: &str = "naga 0.20, ValidationFlags::all, Capabilities::all";
+
+/// The target directory, `CARGO_TARGET_DIR` if set, or `target`
+/// next to the `Cargo.lock` of the workspace.
+pub fn target_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("CARGO_TARGET_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let manifest_dir = PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR")?);
+    let root = manifest_dir
+        .ancestors()
+        .find(|dir| dir.join("Cargo.lock").exists())
+        .unwrap_or(&manifest_dir);
+    Some(root.join("target"))
+}
+
+/// `target/wgsl_ln/cache/<hash>`, an empty file marking a source that passed validation.
+fn marker(source: &str) -> Option<PathBuf> {
+    let hash = fnv1a(format!("{}\n{}", KEY, source).as_bytes());
+    Some(
+        target_dir()?
+            .join("wgsl_ln")
+            .join("cache")
+            .join(format!("{:032x}", hash)),
+    )
+}
+
+/// Returns true if this source passed validation in a previous build.
+pub fn is_validated(source: &str) -> bool {
+    marker(source).is_some_and(|x| x.exists())
+}
+
+/// Mark a source as validated.
+pub fn set_validated(source: &str) {
+    if let Some(marker) = marker(source) {
+        write(&marker, "");
+    }
+}
@@ -0,0 +1,91 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Names of the exports of the color library.
+pub const EXPORTS: &[&str] = &[
+    "srgb_to_linear",
+    "linear_to_srgb",
+    "linear_to_oklab",
+    "oklab_to_linear",
+    "oklab_to_oklch",
+    "oklch_to_oklab",
+    "rgb_to_hsv",
+    "hsv_to_rgb",
+];
+
+/// Source of an export of the color library, every color is a `vec3<f32>`,
+/// OKLCH hue and HSV hue are in `0..1`.
+pub fn source(name: &str) -> Option<TokenStream> {
+    let source = match name {
+        "srgb_to_linear" => quote! {
+            fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+                return select(pow((c + 0.055) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+            }
+        },
+        "linear_to_srgb" => quote! {
+            fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+                return select(1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055, c * 12.92, c <= vec3<f32>(0.0031308));
+            }
+        },
+        "linear_to_oklab" => quote! {
+            fn linear_to_oklab(c: vec3<f32>) -> vec3<f32> {
+                let lms = mat3x3<f32>(
+                    0.4122214708, 0.2119034982, 0.0883024619,
+                    0.5363325363, 0.6806995451, 0.2817188376,
+                    0.0514459929, 0.1073969566, 0.6299787005,
+                ) * c;
+                let t = pow(max(lms, vec3<f32>(0.0)), vec3<f32>(1.0 / 3.0));
+                return mat3x3<f32>(
+                    0.2104542553, 1.9779984951, 0.0259040371,
+                    0.7936177850, -2.4285922050, 0.7827717662,
+                    -0.0040720468, 0.4505937099, -0.8086757660,
+                ) * t;
+            }
+        },
+        "oklab_to_linear" => quote! {
+            fn oklab_to_linear(c: vec3<f32>) -> vec3<f32> {
+                let t = mat3x3<f32>(
+                    1.0, 1.0, 1.0,
+                    0.3963377774, -0.1055613458, -0.0894841775,
+                    0.2158037573, -0.0638541728, -1.2914855480,
+                ) * c;
+                return mat3x3<f32>(
+                    4.0767416621, -1.2684380046, -0.0041960863,
+                    -3.3077115913, 2.6097574011, -0.7034186147,
+                    0.2309699292, -0.3413193965, 1.7076147010,
+                ) * (t * t * t);
+            }
+        },
+        "oklab_to_oklch" => quote! {
+            fn oklab_to_oklch(c: vec3<f32>) -> vec3<f32> {
+                let hue = atan2(c.z, c.y) / 6.283185307179586;
+                return vec3<f32>(c.x, length(c.yz), fract(hue));
+            }
+        },
+        "oklch_to_oklab" => quote! {
+            fn oklch_to_oklab(c: vec3<f32>) -> vec3<f32> {
+                let hue = c.z * 6.283185307179586;
+                return vec3<f32>(c.x, c.y * cos(hue), c.y * sin(hue));
+            }
+        },
+        "rgb_to_hsv" => quote! {
+            fn rgb_to_hsv(c: vec3<f32>) -> vec3<f32> {
+                let K = vec4<f32>(0.0, -1.0 / 3.0, 2.0 / 3.0, -1.0);
+                let p = select(vec4<f32>(c.gb, K.xy), vec4<f32>(c.bg, K.wz), c.g < c.b);
+                let q = select(vec4<f32>(c.r, p.yzx), vec4<f32>(p.xyw, c.r), c.r < p.x);
+                let d = q.x - min(q.w, q.y);
+                let e = 1.0e-10;
+                return vec3<f32>(abs(q.z + (q.w - q.y) / (6.0 * d + e)), d / (q.x + e), q.x);
+            }
+        },
+        "hsv_to_rgb" => quote! {
+            fn hsv_to_rgb(c: vec3<f32>) -> vec3<f32> {
+                let K = vec4<f32>(1.0, 2.0 / 3.0, 1.0 / 3.0, 3.0);
+                let p = abs(fract(c.xxx + K.xyz) * 6.0 - K.www);
+                return c.z * mix(K.xxx, clamp(p - K.xxx, vec3<f32>(0.0), vec3<f32>(1.0)), c.y);
+            }
+        },
+        _ => return None,
+    };
+    Some(source)
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+    time::SystemTime,
+};
+
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use toml::Table;
+
+use crate::{__wgsl_paste2::MARKER, level::Level, options::is_oil};
+
+/// Name of the configuration file, next to `Cargo.toml`.
+const FILE: &str = "wgsl_ln.toml";
+
+/// Defaults for every shader of a crate, read from `wgsl_ln.toml`.
+///
+/// Environment variables take precedence over the file.
+#[derive(Debug, Default)]
+pub struct Config {
+    /// `validate = "off" | "parse" | "full" | "strict"`, like `WGSL_LN_VALIDATE`.
+    pub validate: Option<String>,
+    /// `oil = true`, `wgsl!` in `naga_oil` mode like `wgsl_oil!`.
+    pub oil: bool,
+    /// `round_trip = true`, like `WGSL_LN_ROUND_TRIP`.
+    pub round_trip: bool,
+    /// `prelude = ["a", "b"]`, imported in every shader like `wgsl_prelude!`.
+    pub prelude: Vec<String>,
+    /// `obfuscate = true`, like `#![obfuscate]` on every shader.
+    pub obfuscate: bool,
+    /// `canonical_order = true`, like `#![canonical_order]` on every shader.
+    pub canonical_order: bool,
+    /// `prefer = ["a::noise"]`, crates of exports without one, like `$a::noise`.
+    pub prefer: Vec<(String, String)>,
+    /// `passes = ["target/release/lints"]`, programs run on every validated shader.
+    pub passes: Vec<String>,
+    /// `substitute = ["@unroll => "]`, text replaced before naga sees a shader.
+    pub substitute: Vec<(String, String)>,
+    /// `preprocess = ["tools/dialect"]`, programs rewriting a shader before naga sees it.
+    pub preprocess: Vec<String>,
+    /// `limits = "limits/mobile.toml"`, `wgpu::Limits` of a target adapter checked on every shader.
+    pub limits: Option<String>,
+    /// `portability = "browser"`, like `#![portability(browser)]` on every shader.
+    pub portability: Option<String>,
+}
+
+/// Parsed files by manifest directory, parsed again when the file is modified.
+type Cache = HashMap<PathBuf, (Option<SystemTime>, Arc<Config>)>;
+
+impl Config {
+    /// Read `wgsl_ln.toml` in `CARGO_MANIFEST_DIR`, or the defaults if there is none.
+    ///
+    /// The file is parsed once per crate, and again if it was modified since.
+    pub fn get() -> Arc<Config> {
+        static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+        let Some(dir) = std::env::var_os("CARGO_MANIFEST_DIR") else {
+            return Arc::default();
+        };
+        let dir = PathBuf::from(dir);
+        let file = dir.join(FILE);
+        let modified = std::fs::metadata(&file).and_then(|x| x.modified()).ok();
+        // Not poisoned by errors in the file, which abort while parsing outside of the lock.
+        let cache = || {
+            let cache = CACHE.get_or_init(Default::default);
+            cache.lock().unwrap_or_else(PoisonError::into_inner)
+        };
+        if let Some((_, config)) = cache().get(&dir).filter(|(x, _)| *x == modified) {
+            return config.clone();
+        }
+        let config = match std::fs::read_to_string(&file) {
+            Ok(content) => Arc::new(Config::parse(&content, &file.display().to_string())),
+            Err(_) => Arc::default(),
+        };
+        cache().insert(dir, (modified, config.clone()));
+        config
+    }
+
+    /// Parse the file, values are strings, booleans or arrays of strings.
+    fn parse(content: &str, file: &str) -> Config {
+        let table = parse_toml(content, file);
+        let mut config = Config::default();
+        for (key, value) in &table {
+            let error = |message: &str| -> ! {
+                abort!(
+                    Span::call_site(),
+                    "{} for `{}` in \"{}\".",
+                    message,
+                    key,
+                    file
+                )
+            };
+            let boolean = || value.as_bool().unwrap_or_else(|| error("Expected a bool"));
+            let string = || value.as_str().map(str::to_owned);
+            let array = || -> Option<Vec<String>> {
+                value
+                    .as_array()?
+                    .iter()
+                    .map(|x| x.as_str().map(str::to_owned))
+                    .collect()
+            };
+            match key.as_str() {
+                "validate" => match string().filter(|x| Level::parse(x).is_some()) {
+                    Some(level) => config.validate = Some(level),
+                    None => error("Expected `\"off\"`, `\"parse\"`, `\"full\"` or `\"strict\"`"),
+                },
+                "oil" => config.oil = boolean(),
+                "round_trip" => config.round_trip = boolean(),
+                "obfuscate" => config.obfuscate = boolean(),
+                "canonical_order" => config.canonical_order = boolean(),
+                "prefer" => {
+                    config.prefer = array()
+                        .and_then(|x| {
+                            x.iter()
+                                .map(|x| {
+                                    let (krate, name) = x.split_once("::")?;
+                                    (is_ident(krate) && is_ident(name))
+                                        .then(|| (name.to_owned(), krate.to_owned()))
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_else(|| error("Expected an array of `crate::item` paths"))
+                }
+                "passes" => {
+                    config.passes = array().unwrap_or_else(|| error("Expected an array of paths"))
+                }
+                "substitute" => {
+                    config.substitute = array()
+                        .and_then(|x| {
+                            x.iter()
+                                .map(|x| {
+                                    let (from, to) = x.split_once("=>")?;
+                                    let from = from.trim();
+                                    (!from.is_empty())
+                                        .then(|| (from.to_owned(), to.trim().to_owned()))
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_else(|| error("Expected an array of `\"from => to\"` strings"))
+                }
+                "preprocess" => {
+                    config.preprocess =
+                        array().unwrap_or_else(|| error("Expected an array of paths"))
+                }
+                "limits" => {
+                    config.limits = Some(string().unwrap_or_else(|| error("Expected a path")))
+                }
+                "portability" => match string().filter(|x| x == "browser") {
+                    Some(target) => config.portability = Some(target),
+                    None => error("Expected `\"browser\"`"),
+                },
+                "prelude" => {
+                    config.prelude = array()
+                        .filter(|x| x.iter().all(|x| is_ident(x)))
+                        .unwrap_or_else(|| error("Expected an array of item names"))
+                }
+                _ => error("Unknown key"),
+            }
+        }
+        config
+    }
+}
+
+fn is_ident(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Apply `oil` and `prelude` to the first `wgsl!` of a paste chain.
+pub fn apply(config: &Config, stream: TokenStream) -> TokenStream {
+    if matches!(stream.clone().into_iter().next(), Some(TokenTree::Ident(i)) if i == MARKER) {
+        return stream;
+    }
+    let oil = match config.oil && !is_oil(&stream) {
+        true => quote! {#![oil]},
+        false => TokenStream::new(),
+    };
+    let prelude = config.prelude.iter().map(|x| format_ident!("{}", x));
+    let prelude: Vec<Ident> = prelude.collect();
+    quote! {#oil #(import package::#prelude;)* #stream}
+}
+
+/// Parse a TOML file, `wgsl_ln.toml` or a file it refers to.
+pub fn parse_toml(content: &str, file: &str) -> Table {
+    match content.parse::<Table>() {
+        Ok(table) => table,
+        Err(e) => abort!(
+            Span::call_site(),
+            "Invalid TOML in \"{}\": {}",
+            file,
+            e.to_string().trim_end()
+        ),
+    }
+}
+
+/// Crate preferred for an export in `wgsl_ln.toml`.
+pub fn preferred(config: &Config, name: &Ident) -> Option<Ident> {
+    let (_, krate) = config.prefer.iter().find(|(x, _)| name == x)?;
+    Some(format_ident!("{}", krate))
+}
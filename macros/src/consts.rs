@@ -0,0 +1,256 @@
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+/// Stands in for the `n`th `${..}`, declared by a stub for validation.
+pub const PLACEHOLDER: &str = "wgsl_ln_const_";
+
+/// Rust constants in `@workgroup_size` and `override` defaults,
+/// i.e. `@workgroup_size(${WG_X})` or `override samples: u32 = ${SAMPLES};`.
+#[derive(Debug, Default)]
+pub struct RustConsts {
+    /// Rust expressions of the placeholders and the variant of `wgsl_ln::interpolate::Value`
+    /// they are written as, in order.
+    pub exprs: Vec<(Ident, TokenStream)>,
+    /// Entry points with a `${..}` in `@workgroup_size` and the placeholder of each dimension.
+    pub entry_points: Vec<(String, [Option<usize>; 3])>,
+}
+
+/// Replace `${..}` with placeholders and add their stubs to `externs`.
+pub fn extract_consts(stream: TokenStream, externs: &mut TokenStream) -> (TokenStream, RustConsts) {
+    let mut consts = RustConsts::default();
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let mut result = Vec::new();
+    // Dimensions of the last `@workgroup_size`, until the `fn` it belongs to.
+    let mut pending = None;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match &tokens[idx..] {
+            [TokenTree::Punct(at), TokenTree::Ident(name), TokenTree::Group(args), ..]
+                if at.as_char() == '@'
+                    && name == "workgroup_size"
+                    && args.delimiter() == Delimiter::Parenthesis =>
+            {
+                let (args, dims) = workgroup_size(args, &mut consts, externs);
+                if dims.iter().any(Option::is_some) {
+                    pending = Some(dims);
+                }
+                result.extend([tokens[idx].clone(), tokens[idx + 1].clone()]);
+                result.push(TokenTree::Group(args));
+                idx += 3;
+            }
+            [TokenTree::Ident(keyword), TokenTree::Ident(name), ..] if keyword == "fn" => {
+                if let Some(dims) = pending.take() {
+                    consts.entry_points.push((name.to_string(), dims));
+                }
+                result.extend([tokens[idx].clone(), tokens[idx + 1].clone()]);
+                idx += 2;
+            }
+            [TokenTree::Ident(keyword), ..] if keyword == "override" => {
+                let end = tokens[idx..]
+                    .iter()
+                    .position(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ';'))
+                    .map_or(tokens.len(), |x| idx + x);
+                override_default(&tokens[idx..end], &mut consts, externs, &mut result);
+                idx = end;
+            }
+            [tt, ..] => {
+                no_consts(std::slice::from_ref(tt));
+                result.push(tt.clone());
+                idx += 1;
+            }
+            [] => unreachable!(),
+        }
+    }
+    (TokenStream::from_iter(result), consts)
+}
+
+/// Replace `${..}` arguments with placeholders, returns the placeholder of each dimension.
+fn workgroup_size(
+    args: &Group,
+    consts: &mut RustConsts,
+    externs: &mut TokenStream,
+) -> (Group, [Option<usize>; 3]) {
+    let mut dims = [None; 3];
+    let mut result = Vec::new();
+    let tokens: Vec<_> = args.stream().into_iter().collect();
+    for (dim, arg) in tokens
+        .split(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ','))
+        .enumerate()
+    {
+        if dim > 0 {
+            result.push(TokenTree::Punct(Punct::new(',', Spacing::Alone)));
+        }
+        match rust_const(arg) {
+            Some((dollar, expr)) if dim < 3 => {
+                dims[dim] = Some(consts.exprs.len());
+                let kind = Ident::new("WorkgroupSize", Span::call_site());
+                let stub = quote! {1u};
+                result.push(placeholder(dollar, kind, expr, stub, consts, externs));
+            }
+            _ => {
+                no_consts(arg);
+                result.extend(arg.iter().cloned());
+            }
+        }
+    }
+    let mut group = Group::new(Delimiter::Parenthesis, TokenStream::from_iter(result));
+    group.set_span(args.span());
+    (group, dims)
+}
+
+/// Replace `${..}` as the default of `override name: type = ${..}` with a placeholder.
+fn override_default(
+    item: &[TokenTree],
+    consts: &mut RustConsts,
+    externs: &mut TokenStream,
+    result: &mut Vec<TokenTree>,
+) {
+    let Some(eq) = item
+        .iter()
+        .position(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == '='))
+    else {
+        result.extend(item.iter().cloned());
+        return;
+    };
+    let Some((dollar, expr)) = rust_const(&item[eq + 1..]) else {
+        no_consts(item);
+        result.extend(item.iter().cloned());
+        return;
+    };
+    let ty = match &item[..eq] {
+        [_, _, TokenTree::Punct(colon), TokenTree::Ident(ty)] if colon.as_char() == ':' => ty,
+        _ => abort!(
+            dollar.span(),
+            "`${..}` requires the type of the `override`, i.e. `override samples: u32 = ${..};`."
+        ),
+    };
+    let kind = match ty.to_string().as_str() {
+        "bool" => "Bool",
+        "i32" => "I32",
+        "u32" => "U32",
+        "f32" => "F32",
+        _ => abort!(
+            ty.span(),
+            "`${..}` requires an `override` of type `bool`, `i32`, `u32` or `f32`."
+        ),
+    };
+    let kind = Ident::new(kind, Span::call_site());
+    result.extend(item[..=eq].iter().cloned());
+    result.push(placeholder(
+        dollar,
+        kind,
+        expr,
+        quote! {#ty()},
+        consts,
+        externs,
+    ));
+}
+
+/// Returns `$` and the expression if the tokens are `${..}`.
+fn rust_const(tokens: &[TokenTree]) -> Option<(&Punct, TokenStream)> {
+    match tokens {
+        [TokenTree::Punct(dollar), TokenTree::Group(expr)]
+            if dollar.as_char() == '$' && expr.delimiter() == Delimiter::Brace =>
+        {
+            Some((dollar, expr.stream()))
+        }
+        _ => None,
+    }
+}
+
+/// Record the expression and declare its placeholder as `stub` for validation.
+fn placeholder(
+    dollar: &Punct,
+    kind: Ident,
+    expr: TokenStream,
+    stub: TokenStream,
+    consts: &mut RustConsts,
+    externs: &mut TokenStream,
+) -> TokenTree {
+    let placeholder = format_ident!(
+        "{}{}",
+        PLACEHOLDER,
+        consts.exprs.len(),
+        span = dollar.span()
+    );
+    externs.extend(quote! {const #placeholder = #stub;});
+    consts.exprs.push((kind, expr));
+    TokenTree::Ident(placeholder)
+}
+
+/// Abort on `${..}` outside of `@workgroup_size` and `override` defaults.
+fn no_consts(tokens: &[TokenTree]) {
+    for pair in tokens.windows(2) {
+        if let [TokenTree::Punct(p), TokenTree::Group(g)] = pair {
+            if p.as_char() == '$' && g.delimiter() == Delimiter::Brace {
+                abort!(
+                    p.span(),
+                    "`${..}` is only supported in `@workgroup_size` and as the default of an `override`."
+                )
+            }
+        }
+    }
+    for tt in tokens {
+        if let TokenTree::Group(g) = tt {
+            no_consts(&g.stream().into_iter().collect::<Vec<_>>());
+        }
+    }
+}
+
+/// The output as a `&'static str` built at compile time, placeholders replaced by the values of their constants.
+pub fn interpolate(consts: &RustConsts, output: &str) -> TokenStream {
+    let mut parts = vec![String::new()];
+    let mut values = Vec::new();
+    let mut rest = output;
+    while let Some(start) = rest.find(PLACEHOLDER) {
+        let after = &rest[start + PLACEHOLDER.len()..];
+        let len = after
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after.len());
+        let part = parts.last_mut().unwrap();
+        match after[..len]
+            .parse()
+            .ok()
+            .and_then(|i: usize| consts.exprs.get(i))
+        {
+            Some((kind, expr)) => {
+                part.push_str(&rest[..start]);
+                values.push(quote! {::wgsl_ln::interpolate::Value::#kind(#expr)});
+                parts.push(String::new());
+            }
+            None => part.push_str(&rest[..start + PLACEHOLDER.len() + len]),
+        }
+        rest = &after[len..];
+    }
+    parts.last_mut().unwrap().push_str(rest);
+    quote! {{
+        const PARTS: &[&str] = &[#(#parts),*];
+        const VALUES: &[::wgsl_ln::interpolate::Value] = &[#(#values),*];
+        const LEN: usize = ::wgsl_ln::interpolate::len(PARTS, VALUES);
+        const BYTES: [u8; LEN] = ::wgsl_ln::interpolate::join(PARTS, VALUES);
+        const SOURCE: &str = ::wgsl_ln::interpolate::as_str(&BYTES);
+        SOURCE
+    }}
+}
+
+/// `@workgroup_size` of an entry point, with the expressions of `${..}`.
+#[cfg(feature = "runtime")]
+pub fn workgroup_size_of(consts: &RustConsts, name: &str, size: [u32; 3]) -> [TokenStream; 3] {
+    let dims = consts
+        .entry_points
+        .iter()
+        .find(|(x, _)| x == name)
+        .map(|(_, dims)| *dims);
+    let dim = |i: usize| match dims.and_then(|x| x[i]) {
+        Some(expr) => {
+            let (_, expr) = &consts.exprs[expr];
+            quote! {#expr}
+        }
+        None => {
+            let value = size[i];
+            quote! {#value}
+        }
+    };
+    [dim(0), dim(1), dim(2)]
+}
@@ -0,0 +1,86 @@
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
+
+use naga::Module;
+use proc_macro2::{Ident, TokenStream};
+
+use crate::{
+    json::{file_stem, reflect},
+    options::{is_oil, Options},
+    sanitize::sanitize,
+    wesl::package_imports,
+    wgsl2::fnv1a,
+};
+
+/// Exported items and their dependencies of each crate, by directory and name.
+///
+/// See `CATALOG` in `wgsl_catalog2.rs` for what state kept across expansions relies on.
+#[allow(clippy::type_complexity)]
+static EXPORTS: Mutex<BTreeMap<PathBuf, BTreeMap<String, (String, Vec<String>)>>> =
+    Mutex::new(BTreeMap::new());
+
+/// `$WGSL_LN_DUMP/<package>/<crate>`, `WGSL_LN_DUMP` is set by `cargo wgsl-ln`.
+fn dump_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("WGSL_LN_DUMP")?;
+    let package = std::env::var("CARGO_PKG_NAME").unwrap_or_default();
+    let krate = std::env::var("CARGO_CRATE_NAME").unwrap_or_default();
+    Some(PathBuf::from(dir).join(package).join(krate))
+}
+
+/// Write the output of a validated shader to `<label>.wgsl`
+/// and its reflection data to `<label>.json` for `cargo wgsl-ln`.
+pub fn dump(options: &Options, output: &str, module: Option<&Module>) {
+    let Some(dir) = dump_dir() else {
+        return;
+    };
+    let hash = format!("{:032x}", fnv1a(output.as_bytes()));
+    let name = file_stem(options.label.as_deref(), &hash);
+    write(&dir.join(format!("{}.wgsl", name)), output);
+    if let Some(module) = module {
+        let json = reflect(options.label.as_deref(), &hash, module);
+        write(&dir.join(format!("{}.json", name)), &json);
+    }
+}
+
+/// Write `<name> <item> <dependencies>..` for each `#[wgsl_export(name)]`
+/// to `exports.txt` for `cargo wgsl-ln`.
+pub fn dump_export(name: &Ident, item: &Ident, source: TokenStream) {
+    let Some(dir) = dump_dir() else {
+        return;
+    };
+    let oil = is_oil(&source);
+    let dependencies = dependencies(source, oil);
+    let mut exports = EXPORTS.lock().unwrap();
+    let exports = exports.entry(dir.clone()).or_default();
+    exports.insert(name.to_string(), (item.to_string(), dependencies));
+    let content: String = exports
+        .iter()
+        .map(|(name, (item, dependencies))| {
+            let mut line = format!("{} {}", name, item);
+            for dependency in dependencies {
+                line.push(' ');
+                line.push_str(dependency);
+            }
+            line.push('\n');
+            line
+        })
+        .collect();
+    write(&dir.join("exports.txt"), &content);
+}
+
+/// Items pasted in with `$item` or `import package::item;`, resolved the same way as `wgsl!`.
+fn dependencies(stream: TokenStream, oil: bool) -> Vec<String> {
+    let (stream, mut pastes) = sanitize(stream, oil);
+    pastes.extend(package_imports(stream).1);
+    pastes.iter().map(|x| x.to_string()).collect()
+}
+
+/// Write a file unless unchanged, ignoring errors.
+pub fn write(file: &std::path::Path, content: &str) {
+    if std::fs::read_to_string(file).is_ok_and(|x| x == content) {
+        return;
+    }
+    if let Some(parent) = file.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let _ = std::fs::write(file, content);
+}
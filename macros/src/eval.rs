@@ -0,0 +1,111 @@
+use naga::{ArraySize, Expression, Handle, Literal, Module, Scalar, ScalarKind, Type, TypeInner};
+use proc_macro2::{Span, TokenStream};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Name of the constant `wgsl_eval!` declares for its expression.
+pub const EVAL: &str = "wgsl_ln_eval";
+
+/// The value naga's constant evaluator computed for `wgsl_ln_eval`, as a Rust expression.
+///
+/// Scalars are suffixed literals, vectors, matrices and arrays are Rust arrays,
+/// i.e. `[f32; 3]` for `vec3<f32>` and `[[f32; 2]; 4]` for `mat4x2<f32>`.
+pub fn eval(module: &Module) -> TokenStream {
+    let Some((_, constant)) = module
+        .constants
+        .iter()
+        .find(|(_, c)| c.name.as_deref() == Some(EVAL))
+    else {
+        abort!(Span::call_site(), "Expected an expression.")
+    };
+    value(module, constant.init)
+}
+
+fn value(module: &Module, handle: Handle<Expression>) -> TokenStream {
+    match module.global_expressions[handle] {
+        Expression::Literal(literal) => literal_tokens(literal),
+        Expression::Compose { ty, .. }
+            if matches!(module.types[ty].inner, TypeInner::Struct { .. }) =>
+        {
+            unsupported()
+        }
+        Expression::Compose { ref components, .. } => {
+            let components = components.iter().map(|x| value(module, *x));
+            quote! {[#(#components),*]}
+        }
+        Expression::Splat {
+            size,
+            value: scalar,
+        } => {
+            let scalar = value(module, scalar);
+            let components = (0..size as usize).map(|_| &scalar);
+            quote! {[#(#components),*]}
+        }
+        Expression::ZeroValue(ty) => zero(module, &module.types[ty]),
+        ref expression => abort!(
+            Span::call_site(),
+            "`wgsl_eval!` cannot convert `{:?}` to a Rust value.",
+            expression
+        ),
+    }
+}
+
+fn literal_tokens(literal: Literal) -> TokenStream {
+    match literal {
+        Literal::F64(x) => quote! {#x},
+        Literal::F32(x) => quote! {#x},
+        Literal::U32(x) => quote! {#x},
+        Literal::I32(x) => quote! {#x},
+        Literal::U64(x) => quote! {#x},
+        Literal::I64(x) => quote! {#x},
+        Literal::Bool(x) => quote! {#x},
+        Literal::AbstractInt(x) => quote! {#x},
+        Literal::AbstractFloat(x) => quote! {#x},
+    }
+}
+
+fn zero(module: &Module, ty: &Type) -> TokenStream {
+    let scalar = |scalar: Scalar| match (scalar.kind, scalar.width) {
+        (ScalarKind::Bool, _) => quote! {false},
+        (ScalarKind::Float, 8) | (ScalarKind::AbstractFloat, _) => quote! {0f64},
+        (ScalarKind::Float, _) => quote! {0f32},
+        (ScalarKind::Sint, 8) | (ScalarKind::AbstractInt, _) => quote! {0i64},
+        (ScalarKind::Sint, _) => quote! {0i32},
+        (ScalarKind::Uint, 8) => quote! {0u64},
+        (ScalarKind::Uint, _) => quote! {0u32},
+    };
+    match ty.inner {
+        TypeInner::Scalar(x) => scalar(x),
+        TypeInner::Vector { size, scalar: x } => {
+            let x = scalar(x);
+            let size = size as usize;
+            quote! {[#x; #size]}
+        }
+        TypeInner::Matrix {
+            columns,
+            rows,
+            scalar: x,
+        } => {
+            let x = scalar(x);
+            let (columns, rows) = (columns as usize, rows as usize);
+            quote! {[[#x; #rows]; #columns]}
+        }
+        TypeInner::Array {
+            base,
+            size: ArraySize::Constant(size),
+            ..
+        } => {
+            let base = zero(module, &module.types[base]);
+            let size = size.get() as usize;
+            quote! {[#base; #size]}
+        }
+        _ => unsupported(),
+    }
+}
+
+fn unsupported() -> ! {
+    abort!(
+        Span::call_site(),
+        "`wgsl_eval!` supports scalars, vectors, matrices and arrays."
+    )
+}
@@ -0,0 +1,123 @@
+use proc_macro2::{Delimiter, Group, Ident, Spacing, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote_spanned;
+
+use crate::items::{item_at, item_name};
+
+/// Check for `extern` after `$`, `macro_rules!` turns `$extern` in pasted items into `$r#extern`.
+pub fn is_extern(ident: &Ident) -> bool {
+    ident == "extern" || ident == "r#extern"
+}
+
+/// Split `$extern` declarations out of the stream.
+///
+/// Returns the stream without externs and the stubs used for validation.
+/// `$extern fn` signatures get a body returning the zero value of their return type,
+/// other declarations (`var`, `struct`, etc) are used as is.
+/// An extern declared again, i.e. by a pasted export and the shader using it, is stubbed once.
+pub fn extract_externs(stream: TokenStream) -> (TokenStream, TokenStream) {
+    let mut result = Vec::new();
+    let mut stubs = Vec::new();
+    let mut names = Vec::new();
+    let mut iter = stream.into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        match &tt {
+            TokenTree::Punct(p)
+                if p.as_char() == '$'
+                    && matches!(iter.peek(), Some(TokenTree::Ident(i)) if is_extern(i)) =>
+            {
+                let extern_ident = iter.next().unwrap();
+                let mut item = Vec::new();
+                let mut stubbed = Vec::new();
+                loop {
+                    match iter.next() {
+                        Some(TokenTree::Punct(p)) if p.as_char() == ';' => {
+                            stub(item, p.span(), &mut stubbed);
+                            break;
+                        }
+                        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                            item.push(TokenTree::Group(g));
+                            stubbed.extend(item);
+                            break;
+                        }
+                        Some(tt) => item.push(tt),
+                        None => abort!(
+                            extern_ident.span(),
+                            "Expected `;` or `{{}}` after `$extern` declaration."
+                        ),
+                    }
+                }
+                match extern_names(&TokenStream::from_iter(stubbed.clone())).pop() {
+                    Some(name) if names.contains(&name) => (),
+                    name => {
+                        names.extend(name);
+                        stubs.extend(stubbed);
+                    }
+                }
+            }
+            _ => result.push(tt),
+        }
+    }
+    (
+        TokenStream::from_iter(result),
+        TokenStream::from_iter(stubs),
+    )
+}
+
+/// Names of the declarations in the stubs returned by [`extract_externs`].
+pub fn extern_names(stubs: &TokenStream) -> Vec<Ident> {
+    let tokens = Vec::from_iter(stubs.clone());
+    let mut names = Vec::new();
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match item_at(&tokens, idx) {
+            Some((_, end)) => {
+                names.extend(item_name(&tokens[idx + 1..end]));
+                idx = end;
+            }
+            None => idx += 1,
+        }
+    }
+    names
+}
+
+/// Give function signatures a body, or terminate other declarations with `;`.
+fn stub(item: Vec<TokenTree>, span: Span, stubs: &mut Vec<TokenTree>) {
+    let is_fn = item
+        .iter()
+        .any(|tt| matches!(tt, TokenTree::Ident(i) if i == "fn"));
+    if !is_fn {
+        stubs.extend(item);
+        stubs.extend(quote_spanned!(span=> ;));
+        return;
+    }
+    let mut return_type = None;
+    for (idx, tt) in item.iter().enumerate() {
+        if let TokenTree::Punct(p) = tt {
+            if p.as_char() == '-' && p.spacing() == Spacing::Joint {
+                return_type = Some(strip_attributes(&item[idx + 2..]));
+                break;
+            }
+        }
+    }
+    stubs.extend(item);
+    let body = match return_type {
+        Some(ty) => quote_spanned!(span=> return #ty ();),
+        None => TokenStream::new(),
+    };
+    stubs.push(TokenTree::Group(Group::new(Delimiter::Brace, body)));
+}
+
+/// Remove attributes like `@location(0)` from a return type.
+fn strip_attributes(mut tokens: &[TokenTree]) -> TokenStream {
+    while let [TokenTree::Punct(p), TokenTree::Ident(_), rest @ ..] = tokens {
+        if p.as_char() != '@' {
+            break;
+        }
+        tokens = match rest {
+            [TokenTree::Group(g), rest @ ..] if g.delimiter() == Delimiter::Parenthesis => rest,
+            rest => rest,
+        };
+    }
+    TokenStream::from_iter(tokens.iter().cloned())
+}
@@ -0,0 +1,132 @@
+use std::error::Error;
+
+use naga::{
+    back::wgsl::WriterFlags,
+    front::glsl::{Frontend, Options},
+    ShaderStage,
+};
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote;
+
+use crate::{
+    level::{validate_strict, Level},
+    options::string_value,
+    to_wgsl_string::to_wgsl_string,
+    wgsl2::{span_at, validate},
+};
+
+/// Validate GLSL with naga's GLSL frontend, emitting it or the translated WGSL.
+///
+/// `#![stage(vertex|fragment|compute)]` is required, `#![wgsl]` emits WGSL.
+pub fn glsl2(stream: TokenStream) -> TokenStream {
+    let mut stage = None;
+    let mut wgsl = false;
+    let mut tokens = stream.into_iter().peekable();
+    while matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '#') {
+        let hash = tokens.next().unwrap();
+        let Some(TokenTree::Punct(bang)) = tokens.next().filter(is_bang) else {
+            abort!(hash.span(), "Directives require GLSL in a string literal.")
+        };
+        let Some(TokenTree::Group(group)) = tokens.next() else {
+            abort!(bang.span(), "Expected `#![..]`.")
+        };
+        let mut option = group.stream().into_iter();
+        match (option.next(), option.next()) {
+            (Some(TokenTree::Ident(name)), None) if name == "wgsl" => wgsl = true,
+            (Some(TokenTree::Ident(name)), Some(TokenTree::Group(args)))
+                if name == "stage" && args.delimiter() == Delimiter::Parenthesis =>
+            {
+                stage = Some(match args.stream().to_string().as_str() {
+                    "vertex" => ShaderStage::Vertex,
+                    "fragment" => ShaderStage::Fragment,
+                    "compute" => ShaderStage::Compute,
+                    _ => abort!(args.span(), "Expected `vertex`, `fragment` or `compute`."),
+                });
+            }
+            _ => abort!(group.span(), "Expected `#![stage(..)]` or `#![wgsl]`."),
+        }
+    }
+    let Some(stage) = stage else {
+        abort!(
+            Span::call_site(),
+            "Expected `#![stage(vertex)]`, `#![stage(fragment)]` or `#![stage(compute)]`."
+        )
+    };
+    let stream: TokenStream = tokens.collect();
+    // A string literal is used as is, tokens are written like `wgsl!`.
+    let mut spans = Vec::new();
+    let source = match stream.clone().into_iter().collect::<Vec<_>>().as_slice() {
+        [TokenTree::Literal(lit)] => match string_value(lit) {
+            Some(source) => {
+                spans.push((0, lit.span()));
+                source
+            }
+            None => abort!(lit.span(), "Expected a string."),
+        },
+        _ => {
+            if let Some(hash) = stream
+                .clone()
+                .into_iter()
+                .find(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == '#'))
+            {
+                abort!(hash.span(), "Directives require GLSL in a string literal.")
+            }
+            let mut source = String::new();
+            to_wgsl_string(stream, &mut spans, &mut source);
+            source
+        }
+    };
+    let level = Level::get(None);
+    if level == Level::Off && !wgsl {
+        return quote! {#source};
+    }
+    let module = match Frontend::default().parse(&Options::from(stage), &source) {
+        Ok(module) => module,
+        Err(e) => match e.errors.first() {
+            Some(error) => {
+                let span = match error.meta.to_range() {
+                    Some(range) => span_at(&spans, range.start),
+                    None => Span::call_site(),
+                };
+                abort!(span, "Glsl Error: {}", error)
+            }
+            None => abort!(Span::call_site(), "Glsl Error: {}", e),
+        },
+    };
+    if level == Level::Parse && !wgsl {
+        return quote! {#source};
+    }
+    let info = match validate(&module) {
+        Ok(info) => info,
+        Err(e) => {
+            let span = match e.spans().next() {
+                Some((span, _)) => span_at(&spans, span.location(&source).offset as usize),
+                None => Span::call_site(),
+            };
+            // The reason is in the error's sources, i.e. the invalid expression.
+            let mut message = e.to_string();
+            let mut error: &dyn Error = e.as_inner();
+            while let Some(source) = error.source() {
+                message.push_str(": ");
+                message.push_str(&source.to_string());
+                error = source;
+            }
+            abort!(span, "Glsl Error: {}", message)
+        }
+    };
+    if level == Level::Strict {
+        validate_strict(&module, &source, &spans);
+    }
+    if !wgsl {
+        return quote! {#source};
+    }
+    match naga::back::wgsl::write_string(&module, &info, WriterFlags::empty()) {
+        Ok(output) => quote! {#output},
+        Err(e) => abort!(Span::call_site(), "Unable to translate to WGSL: {}", e),
+    }
+}
+
+fn is_bang(token: &TokenTree) -> bool {
+    matches!(token, TokenTree::Punct(p) if p.as_char() == '!')
+}
@@ -0,0 +1,260 @@
+use std::fmt::Write;
+
+use naga::{Handle, Module, ScalarKind, Type, TypeInner};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+use crate::{
+    options::{Options, Property},
+    wgsl2::validate,
+};
+
+/// WGSL type, Rust type and size of a value passed to or returned from a test,
+/// only 32 bit scalars and vectors are supported.
+fn gpu_type(module: &Module, ty: Handle<Type>) -> Option<(String, TokenStream, u64)> {
+    let (scalar, components) = match module.types[ty].inner {
+        TypeInner::Scalar(scalar) => (scalar, None),
+        TypeInner::Vector { size, scalar } => (scalar, Some(size as usize)),
+        _ => return None,
+    };
+    let name = match (scalar.kind, scalar.width) {
+        (ScalarKind::Float, 4) => "f32",
+        (ScalarKind::Sint, 4) => "i32",
+        (ScalarKind::Uint, 4) => "u32",
+        _ => return None,
+    };
+    let rust = format_ident!("{}", name);
+    Some(match components {
+        None => (name.to_owned(), quote! {#rust}, 4),
+        Some(n) => (
+            format!("vec{}<{}>", n, name),
+            quote! {[#rust; #n]},
+            4 * n as u64,
+        ),
+    })
+}
+
+/// Signature of a function under test as the WGSL and Rust types and sizes of its arguments and result.
+type Signature = (Vec<(String, TokenStream, u64)>, (String, TokenStream, u64));
+
+fn signature(module: &Module, function: &Ident) -> Signature {
+    let span = function.span();
+    let name = function.to_string();
+    let Some((_, function)) = module
+        .functions
+        .iter()
+        .find(|(_, f)| f.name.as_deref() == Some(&name))
+    else {
+        abort!(span, "Function `{}` not found.", name)
+    };
+    let unsupported = || {
+        abort!(
+            span,
+            "`wgsl_test!` only supports 32 bit scalar and vector arguments and results."
+        )
+    };
+    let params = function
+        .arguments
+        .iter()
+        .map(|x| gpu_type(module, x.ty).unwrap_or_else(unsupported))
+        .collect();
+    let Some(result) = &function.result else {
+        abort!(span, "`{}` does not return a value.", name)
+    };
+    (
+        params,
+        gpu_type(module, result.ty).unwrap_or_else(unsupported),
+    )
+}
+
+/// Validate a generated harness.
+fn validate_harness(source: &str, function: &Ident) {
+    let validated = naga::front::wgsl::parse_str(source)
+        .map_err(|e| e.emit_to_string(source))
+        .and_then(|module| validate(&module).map_err(|e| e.emit_to_string(source)));
+    if let Err(e) = validated {
+        abort!(
+            function.span(),
+            "Unable to generate a test harness for `{}`: {}",
+            function,
+            e
+        )
+    }
+}
+
+/// Run each `#![case(..)]` in a compute shader calling the function,
+/// with arguments in storage buffers, and assert the result,
+/// then run the `#![property(..)]` if any.
+pub fn gpu_test(options: &Options, output: &str, module: &Module) -> TokenStream {
+    if options.cases.is_empty() && options.property.is_none() {
+        abort!(
+            Span::call_site(),
+            "Expected test cases, i.e. `#![case(function(args..) == expected)]`."
+        )
+    }
+    let cases = options.cases.iter().map(|case| {
+        let span = case.function.span();
+        let name = case.function.to_string();
+        let (params, (result, result_ty, size)) = signature(module, &case.function);
+        if params.len() != case.args.len() {
+            abort!(
+                span,
+                "`{}` takes {} arguments, found {}.",
+                name,
+                params.len(),
+                case.args.len()
+            )
+        }
+        let mut source = output.to_owned();
+        let mut args = Vec::new();
+        for (i, (ty, ..)) in params.iter().enumerate() {
+            let _ = writeln!(
+                source,
+                "@group(0) @binding({}) var<storage, read> wgsl_ln_test_input_{}: {};",
+                i, i, ty
+            );
+            args.push(format!("wgsl_ln_test_input_{}", i));
+        }
+        let _ = writeln!(
+            source,
+            "@group(0) @binding({}) var<storage, read_write> wgsl_ln_test_output: {};\n\
+            @compute @workgroup_size(1)\n\
+            fn wgsl_ln_test() {{\n\
+            wgsl_ln_test_output = {}({});\n\
+            }}",
+            params.len(),
+            result,
+            name,
+            args.join(", ")
+        );
+        validate_harness(&source, &case.function);
+        let idents: Vec<_> = (0..params.len())
+            .map(|i| format_ident!("arg{}", i))
+            .collect();
+        let types = params.iter().map(|x| &x.1);
+        let count = params.len();
+        let exprs = &case.args;
+        let expected = &case.expected;
+        let text = format!(
+            "{}({}) == {}",
+            name,
+            exprs
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            expected
+        );
+        quote! {{
+            #(let #idents: #types = #exprs;)*
+            let expected: #result_ty = #expected;
+            let inputs: [::std::vec::Vec<u8>; #count] = [#({
+                let mut bytes = ::std::vec::Vec::new();
+                ::wgsl_ln::runtime::test::GpuValue::write(&#idents, &mut bytes);
+                bytes
+            }),*];
+            let output = ::wgsl_ln::runtime::test::run_compute(#source, "wgsl_ln_test", &inputs, #size, 1);
+            let actual: #result_ty = ::wgsl_ln::runtime::test::GpuValue::read(&output);
+            ::core::assert_eq!(actual, expected, "{}", #text);
+        }}
+    });
+    let property = options
+        .property
+        .as_ref()
+        .map(|property| property_test(property, output, module));
+    quote! {{#(#cases)* #property}}
+}
+
+/// Size of an element of `array<T>`, `vec3<T>` is aligned to 16 bytes.
+fn stride(size: u64) -> u64 {
+    match size {
+        12 => 16,
+        size => size,
+    }
+}
+
+/// Run the function once per sample on generated arguments in one dispatch,
+/// and compare each result to the Rust reference.
+fn property_test(property: &Property, output: &str, module: &Module) -> TokenStream {
+    let Property {
+        function,
+        reference,
+        samples,
+        tolerance,
+        range,
+        seed,
+    } = property;
+    let name = function.to_string();
+    let (params, (result, result_ty, size)) = signature(module, function);
+    let mut source = output.to_owned();
+    let mut args = Vec::new();
+    for (i, (ty, ..)) in params.iter().enumerate() {
+        let _ = writeln!(
+            source,
+            "@group(0) @binding({}) var<storage, read> wgsl_ln_test_input_{}: array<{}>;",
+            i, i, ty
+        );
+        args.push(format!("wgsl_ln_test_input_{}[id.x]", i));
+    }
+    let _ = writeln!(
+        source,
+        "@group(0) @binding({}) var<storage, read_write> wgsl_ln_test_output: array<{}>;\n\
+        @compute @workgroup_size(1)\n\
+        fn wgsl_ln_test(@builtin(global_invocation_id) id: vec3<u32>) {{\n\
+        wgsl_ln_test_output[id.x] = {}({});\n\
+        }}",
+        params.len(),
+        result,
+        name,
+        args.join(", ")
+    );
+    validate_harness(&source, function);
+    let types: Vec<_> = params.iter().map(|x| &x.1).collect();
+    let count = params.len();
+    let indices = (0..params.len()).map(Literal::usize_unsuffixed);
+    let indices2 = indices.clone();
+    let strides = params.iter().map(|x| stride(x.2) as usize);
+    let stride = stride(size) as usize;
+    let reference_text = reference.to_string();
+    quote! {{
+        use ::wgsl_ln::runtime::test::GpuValue;
+        let reference = #reference;
+        let samples: u32 = #samples;
+        let tolerance: f32 = #tolerance;
+        let range: ::core::ops::Range<f32> = #range;
+        let mut rng = ::wgsl_ln::runtime::test::Rng::new(#seed);
+        let args: ::std::vec::Vec<(#(#types,)*)> = (0..samples)
+            .map(|_| (#(<#types as GpuValue>::generate(&mut rng, &range),)*))
+            .collect();
+        let inputs: [::std::vec::Vec<u8>; #count] = [#({
+            let mut bytes = ::std::vec::Vec::new();
+            for args in &args {
+                let start = bytes.len();
+                GpuValue::write(&args.#indices, &mut bytes);
+                bytes.resize(start + #strides, 0);
+            }
+            bytes
+        }),*];
+        let output = ::wgsl_ln::runtime::test::run_compute(
+            #source,
+            "wgsl_ln_test",
+            &inputs,
+            #stride as u64 * samples as u64,
+            samples,
+        );
+        for (i, args) in args.iter().enumerate() {
+            let actual: #result_ty = GpuValue::read(&output[i * #stride..]);
+            let expected: #result_ty = reference(#(args.#indices2),*);
+            ::core::assert!(
+                actual.approx_eq(&expected, tolerance),
+                "`{}` differs from `{}` for {:?}, expected {:?}, found {:?}.",
+                #name,
+                #reference_text,
+                args,
+                expected,
+                actual,
+            );
+        }
+    }}
+}
@@ -0,0 +1,320 @@
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::format_ident;
+
+use crate::to_wgsl_string::macro_args;
+
+/// Split top level items pasted in via `$item` out of the stream.
+///
+/// Returns the remaining tokens and the pasted items with their names.
+pub fn split_pasted(
+    stream: TokenStream,
+    defined: &[Ident],
+) -> (TokenStream, Vec<(Ident, TokenStream)>) {
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let mut rest = Vec::new();
+    let mut pasted = Vec::new();
+    let mut copied = 0;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let Some((start, end)) = item_at(&tokens, idx) else {
+            idx += 1;
+            continue;
+        };
+        match item_name(&tokens[idx + 1..end]) {
+            Some(name) if defined.contains(&name) => {
+                rest.extend(tokens[copied..start].iter().cloned());
+                pasted.push((
+                    name,
+                    TokenStream::from_iter(tokens[start..end].iter().cloned()),
+                ));
+                copied = end;
+            }
+            _ => (),
+        }
+        idx = end;
+    }
+    rest.extend(tokens[copied..].iter().cloned());
+    (TokenStream::from_iter(rest), pasted)
+}
+
+/// Find the range of a top level item starting with a keyword at `idx`, including attributes.
+pub fn item_at(tokens: &[TokenTree], idx: usize) -> Option<(usize, usize)> {
+    let TokenTree::Ident(keyword) = &tokens[idx] else {
+        return None;
+    };
+    let is_block = match keyword.to_string().as_str() {
+        "fn" | "struct" => true,
+        "const" | "var" | "alias" | "override" => false,
+        _ => return None,
+    };
+    let is_at = |i: usize| matches!(&tokens[i], TokenTree::Punct(p) if p.as_char() == '@');
+    let mut start = idx;
+    loop {
+        match &tokens[..start] {
+            [.., TokenTree::Punct(_), TokenTree::Ident(_), TokenTree::Group(g)]
+                if g.delimiter() == Delimiter::Parenthesis && is_at(start - 3) =>
+            {
+                start -= 3
+            }
+            [.., TokenTree::Punct(_), TokenTree::Ident(_)] if is_at(start - 2) => start -= 2,
+            _ => break,
+        }
+    }
+    let end = tokens[idx..].iter().position(|x| match x {
+        TokenTree::Group(g) => is_block && g.delimiter() == Delimiter::Brace,
+        TokenTree::Punct(p) => !is_block && p.as_char() == ';',
+        _ => false,
+    })?;
+    Some((start, idx + end + 1))
+}
+
+/// Name of an item after its keyword, skipping `<storage, read>` in `var<storage, read> name`.
+pub fn item_name(tokens: &[TokenTree]) -> Option<Ident> {
+    let mut depth = 0;
+    tokens.iter().find_map(|x| match x {
+        TokenTree::Punct(p) if p.as_char() == '<' => {
+            depth += 1;
+            None
+        }
+        TokenTree::Punct(p) if p.as_char() == '>' => {
+            depth -= 1;
+            None
+        }
+        TokenTree::Ident(i) if depth == 0 => Some(i.clone()),
+        _ => None,
+    })
+}
+
+/// Remove repeated top level `alias` declarations, i.e. an alias pasted in by `$Color`
+/// and by an export that declares it as well, keeping the first.
+///
+/// Aliases with the same name but different types are kept, and reported by naga.
+pub fn dedup_aliases(stream: TokenStream) -> TokenStream {
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let mut result = Vec::new();
+    let mut seen = Vec::new();
+    let mut copied = 0;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let Some((start, end)) = item_at(&tokens, idx) else {
+            idx += 1;
+            continue;
+        };
+        if tokens[idx].to_string() == "alias" {
+            let alias = TokenStream::from_iter(tokens[idx..end].iter().cloned()).to_string();
+            match seen.contains(&alias) {
+                true => {
+                    result.extend(tokens[copied..start].iter().cloned());
+                    copied = end;
+                }
+                false => seen.push(alias),
+            }
+        }
+        idx = end;
+    }
+    result.extend(tokens[copied..].iter().cloned());
+    TokenStream::from_iter(result)
+}
+
+/// Apply top level `$NAME = value;` to the `const` or `override` named `NAME`,
+/// i.e. a tuning constant of an export, replacing its initializer.
+pub fn set_constants(stream: TokenStream) -> TokenStream {
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let mut values: Vec<(Ident, Vec<TokenTree>)> = Vec::new();
+    let mut rest = Vec::new();
+    let mut idx = 0;
+    while idx < tokens.len() {
+        // After an item or an option, `@id(0) name` is not the start of an item.
+        let is_start = match idx.checked_sub(1).map(|x| &tokens[x]) {
+            None => true,
+            Some(TokenTree::Punct(p)) => p.as_char() == ';',
+            Some(TokenTree::Group(g)) => g.delimiter() != Delimiter::Parenthesis,
+            _ => false,
+        };
+        match &tokens[idx..] {
+            [TokenTree::Ident(name), TokenTree::Punct(eq), ..]
+                if is_start && eq.as_char() == '=' && eq.spacing() == Spacing::Alone =>
+            {
+                let Some(end) = tokens[idx..]
+                    .iter()
+                    .position(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ';'))
+                else {
+                    abort!(name.span(), "Expected `;` after `${} = ..`.", name)
+                };
+                if values.iter().any(|(x, _)| x == name) {
+                    abort!(name.span(), "`{}` is set twice.", name)
+                }
+                values.push((name.clone(), tokens[idx + 2..idx + end].to_vec()));
+                idx += end + 1;
+            }
+            _ => {
+                rest.push(tokens[idx].clone());
+                idx += 1;
+            }
+        }
+    }
+    if values.is_empty() {
+        return TokenStream::from_iter(rest);
+    }
+    let mut result = Vec::new();
+    let mut copied = 0;
+    let mut idx = 0;
+    while idx < rest.len() {
+        let Some((_, end)) = item_at(&rest, idx) else {
+            idx += 1;
+            continue;
+        };
+        let keyword = rest[idx].to_string();
+        let name = item_name(&rest[idx + 1..end]);
+        let value = values
+            .iter()
+            .position(|(x, _)| Some(x) == name.as_ref())
+            .filter(|_| keyword == "const" || keyword == "override");
+        if let Some(value) = value {
+            let (_, value) = values.remove(value);
+            // `override` may not have a default.
+            let init = rest[idx..end]
+                .iter()
+                .position(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == '='))
+                .map_or(end - 1, |x| idx + x + 1);
+            result.extend(rest[copied..init].iter().cloned());
+            if init == end - 1 {
+                result.push(TokenTree::Punct(Punct::new('=', Spacing::Alone)));
+            }
+            result.extend(value);
+            copied = end - 1;
+        }
+        idx = end;
+    }
+    result.extend(rest[copied..].iter().cloned());
+    if let Some((name, _)) = values.first() {
+        abort!(
+            name.span(),
+            "`{}` is not a `const` or `override`, `${} = value;` sets the value of an exported constant.",
+            name,
+            name
+        )
+    }
+    TokenStream::from_iter(result)
+}
+
+/// Reorder top level items as directives and other tokens first, then `struct`s and `alias`es,
+/// then `const`, `override` and `var` declarations, then functions, otherwise in order.
+///
+/// Declarations at module scope can be used before they are declared, so the order is not observable.
+pub fn canonical_order(stream: TokenStream) -> TokenStream {
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let mut sections: [Vec<TokenTree>; 4] = Default::default();
+    let mut copied = 0;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let Some((start, end)) = item_at(&tokens, idx) else {
+            idx += 1;
+            continue;
+        };
+        let section = match tokens[idx].to_string().as_str() {
+            "struct" | "alias" => 1,
+            "fn" => 3,
+            _ => 2,
+        };
+        // Notes right before an item stay with it.
+        let mut notes = start;
+        while notes >= copied + 3 && is_note(&tokens[notes - 3..]) {
+            notes -= 3;
+        }
+        sections[0].extend(tokens[copied..notes].iter().cloned());
+        sections[section].extend(tokens[notes..end].iter().cloned());
+        copied = end;
+        idx = end;
+    }
+    sections[0].extend(tokens[copied..].iter().cloned());
+    sections.into_iter().flatten().collect()
+}
+
+/// Returns true if the tokens start with `note!(..)`.
+fn is_note(tokens: &[TokenTree]) -> bool {
+    matches!(tokens, [TokenTree::Ident(i), rest @ ..]
+        if i == "note" && macro_args(rest.iter().cloned()).is_some())
+}
+
+/// Remove `note!(..)` comments, for obfuscated shaders.
+pub fn strip_notes(stream: TokenStream) -> TokenStream {
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let mut result = Vec::new();
+    let mut idx = 0;
+    while idx < tokens.len() {
+        if is_note(&tokens[idx..]) {
+            idx += 3;
+            continue;
+        }
+        match &tokens[idx] {
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), strip_notes(g.stream()));
+                group.set_span(g.span());
+                result.push(TokenTree::Group(group));
+            }
+            tt => result.push(tt.clone()),
+        }
+        idx += 1;
+    }
+    TokenStream::from_iter(result)
+}
+
+/// Keyword of the top level item named `name`, i.e. `fn` or `struct`.
+pub fn item_kind(stream: TokenStream, name: &Ident) -> Option<String> {
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let Some((_, end)) = item_at(&tokens, idx) else {
+            idx += 1;
+            continue;
+        };
+        if item_name(&tokens[idx + 1..end]).is_some_and(|x| &x == name) {
+            return Some(tokens[idx].to_string());
+        }
+        idx = end;
+    }
+    None
+}
+
+/// Rename top level entry points, functions with `@vertex`, `@fragment` or `@compute`,
+/// either as listed in `renames` or with `prefix`.
+///
+/// Returns the stream and the names in `renames` that are not entry points.
+pub fn rename_entry_points(
+    stream: TokenStream,
+    renames: &[(Ident, Ident)],
+    prefix: Option<&Ident>,
+) -> (TokenStream, Vec<Ident>) {
+    let mut tokens: Vec<_> = stream.into_iter().collect();
+    let mut unused: Vec<_> = renames.iter().map(|(from, _)| from.clone()).collect();
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let Some((start, end)) = item_at(&tokens, idx) else {
+            idx += 1;
+            continue;
+        };
+        let is_entry = tokens[idx].to_string() == "fn" && is_entry_point(&tokens[start..idx]);
+        if let (true, Some(TokenTree::Ident(name))) = (is_entry, tokens.get(idx + 1).cloned()) {
+            let renamed = match renames.iter().find(|(from, _)| from == &name) {
+                Some((_, to)) => Some(to.clone()),
+                None => prefix.map(|prefix| format_ident!("{}{}", prefix, name)),
+            };
+            unused.retain(|x| x != &name);
+            if let Some(mut renamed) = renamed {
+                renamed.set_span(name.span());
+                tokens[idx + 1] = TokenTree::Ident(renamed);
+            }
+        }
+        idx = end;
+    }
+    (TokenStream::from_iter(tokens), unused)
+}
+
+/// Returns true if the attributes of a function contain `@vertex`, `@fragment` or `@compute`.
+pub fn is_entry_point(attributes: &[TokenTree]) -> bool {
+    attributes.iter().any(
+        |x| matches!(x, TokenTree::Ident(i) if i == "vertex" || i == "fragment" || i == "compute"),
+    )
+}
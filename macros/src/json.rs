@@ -0,0 +1,283 @@
+use std::fmt::Write;
+
+use naga::{
+    AddressSpace, ArraySize, Expression, Handle, ImageClass, ImageDimension, Literal, Module,
+    Scalar, ScalarKind, ShaderStage, StorageAccess, Type, TypeInner,
+};
+
+#[cfg(feature = "json")]
+use crate::{options::Options, wgsl2::fnv1a};
+
+/// Write entry points, bindings, structs and overrides of a shader
+/// to `OUT_DIR/wgsl_ln/<label>.json`, or a name derived from the content hash.
+///
+/// Does nothing if `OUT_DIR` is not set, i.e. in crates without a `build.rs`.
+#[cfg(feature = "json")]
+pub fn write_json(options: &Options, output: &str, module: &Module) {
+    let Ok(dir) = std::env::var("OUT_DIR") else {
+        return;
+    };
+    let hash = format!("{:032x}", fnv1a(output.as_bytes()));
+    let name = file_stem(options.label.as_deref(), &hash);
+    let json = reflect(options.label.as_deref(), &hash, module);
+    let file = std::path::Path::new(&dir)
+        .join("wgsl_ln")
+        .join(format!("{}.json", name));
+    // Unchanged files are not written to avoid triggering file watchers.
+    if std::fs::read_to_string(&file).is_ok_and(|x| x == json) {
+        return;
+    }
+    if let Some(parent) = file.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let _ = std::fs::write(&file, json);
+}
+
+/// Name of the files written for a shader, its label with unsupported characters
+/// replaced or the content hash.
+pub fn file_stem(label: Option<&str>, hash: &str) -> String {
+    match label {
+        Some(label) => label
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() || c == '-' {
+                true => c,
+                false => '_',
+            })
+            .collect(),
+        None => hash.to_owned(),
+    }
+}
+
+/// Reflection data of a shader as JSON.
+pub fn reflect(label: Option<&str>, hash: &str, module: &Module) -> String {
+    let entry_points = module.entry_points.iter().map(|entry| {
+        let stage = match entry.stage {
+            ShaderStage::Vertex => "vertex",
+            ShaderStage::Fragment => "fragment",
+            ShaderStage::Compute => "compute",
+        };
+        let [x, y, z] = entry.workgroup_size;
+        format!(
+            r#"{{"name":{},"stage":"{}","workgroup_size":[{},{},{}]}}"#,
+            string(&entry.name),
+            stage,
+            x,
+            y,
+            z
+        )
+    });
+    let bindings = module.global_variables.iter().filter_map(|(_, var)| {
+        let binding = var.binding.as_ref()?;
+        let (space, access) = match var.space {
+            AddressSpace::Uniform => ("uniform", None),
+            AddressSpace::Storage { access } => ("storage", Some(access)),
+            _ => ("handle", None),
+        };
+        // Minimum binding size of buffers, the stride for runtime sized arrays.
+        let size = match space {
+            "handle" => "null".to_owned(),
+            _ => module.types[var.ty].inner.size(module.to_ctx()).to_string(),
+        };
+        let access = match access {
+            Some(access) if access.contains(StorageAccess::STORE) => r#""read_write""#,
+            Some(_) => r#""read""#,
+            None => "null",
+        };
+        Some(format!(
+            r#"{{"group":{},"binding":{},"name":{},"space":"{}","access":{},"type":{},"size":{}}}"#,
+            binding.group,
+            binding.binding,
+            optional_string(var.name.as_deref()),
+            space,
+            access,
+            string(&type_name(module, var.ty)),
+            size,
+        ))
+    });
+    let structs = module.types.iter().filter_map(|(_, ty)| {
+        let TypeInner::Struct { members, span } = &ty.inner else {
+            return None;
+        };
+        let members = members.iter().map(|member| {
+            format!(
+                r#"{{"name":{},"type":{},"offset":{},"size":{}}}"#,
+                optional_string(member.name.as_deref()),
+                string(&type_name(module, member.ty)),
+                member.offset,
+                module.types[member.ty].inner.size(module.to_ctx()),
+            )
+        });
+        Some(format!(
+            r#"{{"name":{},"size":{},"members":{}}}"#,
+            optional_string(ty.name.as_deref()),
+            span,
+            array(members),
+        ))
+    });
+    let overrides = module.overrides.iter().map(|(_, item)| {
+        let default = match item.init.map(|x| &module.global_expressions[x]) {
+            Some(Expression::Literal(literal)) => literal_value(literal),
+            _ => "null".to_owned(),
+        };
+        format!(
+            r#"{{"name":{},"id":{},"type":{},"default":{}}}"#,
+            optional_string(item.name.as_deref()),
+            item.id.map(|x| x.to_string()).as_deref().unwrap_or("null"),
+            string(&type_name(module, item.ty)),
+            default,
+        )
+    });
+    format!(
+        r#"{{"label":{},"hash":"{}","entry_points":{},"bindings":{},"structs":{},"overrides":{}}}"#,
+        optional_string(label),
+        hash,
+        array(entry_points),
+        array(bindings),
+        array(structs),
+        array(overrides),
+    )
+}
+
+/// Name of a type as written in WGSL, i.e. `vec4<f32>`.
+fn type_name(module: &Module, ty: Handle<Type>) -> String {
+    let ty = &module.types[ty];
+    match &ty.inner {
+        TypeInner::Scalar(scalar) => scalar_name(*scalar).to_owned(),
+        TypeInner::Vector { size, scalar } => {
+            format!("vec{}<{}>", *size as u8, scalar_name(*scalar))
+        }
+        TypeInner::Matrix {
+            columns,
+            rows,
+            scalar,
+        } => format!(
+            "mat{}x{}<{}>",
+            *columns as u8,
+            *rows as u8,
+            scalar_name(*scalar)
+        ),
+        TypeInner::Atomic(scalar) => format!("atomic<{}>", scalar_name(*scalar)),
+        TypeInner::Pointer { base, .. } => format!("ptr<{}>", type_name(module, *base)),
+        TypeInner::ValuePointer { .. } => "ptr".to_owned(),
+        TypeInner::Array { base, size, .. } => match size {
+            ArraySize::Constant(n) => format!("array<{}, {}>", type_name(module, *base), n),
+            ArraySize::Dynamic => format!("array<{}>", type_name(module, *base)),
+        },
+        TypeInner::BindingArray { base, size } => match size {
+            ArraySize::Constant(n) => {
+                format!("binding_array<{}, {}>", type_name(module, *base), n)
+            }
+            ArraySize::Dynamic => format!("binding_array<{}>", type_name(module, *base)),
+        },
+        TypeInner::Struct { .. } => ty.name.clone().unwrap_or_else(|| "struct".to_owned()),
+        TypeInner::Image {
+            dim,
+            arrayed,
+            class,
+        } => {
+            let dim = match dim {
+                ImageDimension::D1 => "1d",
+                ImageDimension::D2 => "2d",
+                ImageDimension::D3 => "3d",
+                ImageDimension::Cube => "cube",
+            };
+            let array = match arrayed {
+                true => "_array",
+                false => "",
+            };
+            match class {
+                ImageClass::Sampled { kind, multi } => {
+                    let kind = match kind {
+                        ScalarKind::Sint => "i32",
+                        ScalarKind::Uint => "u32",
+                        _ => "f32",
+                    };
+                    match multi {
+                        true => format!("texture_multisampled_{}{}<{}>", dim, array, kind),
+                        false => format!("texture_{}{}<{}>", dim, array, kind),
+                    }
+                }
+                ImageClass::Depth { multi: true } => format!("texture_depth_multisampled_{}", dim),
+                ImageClass::Depth { multi: false } => format!("texture_depth_{}{}", dim, array),
+                ImageClass::Storage { format, access } => {
+                    let access = match (
+                        access.contains(StorageAccess::LOAD),
+                        access.contains(StorageAccess::STORE),
+                    ) {
+                        (true, true) => "read_write",
+                        (true, false) => "read",
+                        _ => "write",
+                    };
+                    format!(
+                        "texture_storage_{}{}<{}, {}>",
+                        dim,
+                        array,
+                        format!("{:?}", format).to_lowercase(),
+                        access
+                    )
+                }
+            }
+        }
+        TypeInner::Sampler { comparison: false } => "sampler".to_owned(),
+        TypeInner::Sampler { comparison: true } => "sampler_comparison".to_owned(),
+        TypeInner::AccelerationStructure => "acceleration_structure".to_owned(),
+        TypeInner::RayQuery => "ray_query".to_owned(),
+    }
+}
+
+fn scalar_name(scalar: Scalar) -> &'static str {
+    match (scalar.kind, scalar.width) {
+        (ScalarKind::Bool, _) => "bool",
+        (ScalarKind::Float, 2) => "f16",
+        (ScalarKind::Float, 8) => "f64",
+        (ScalarKind::Float, _) => "f32",
+        (ScalarKind::Sint, 8) => "i64",
+        (ScalarKind::Sint, _) => "i32",
+        (ScalarKind::Uint, 8) => "u64",
+        (ScalarKind::Uint, _) => "u32",
+        (ScalarKind::AbstractInt, _) => "abstract_int",
+        (ScalarKind::AbstractFloat, _) => "abstract_float",
+    }
+}
+
+fn literal_value(literal: &Literal) -> String {
+    match literal {
+        Literal::F64(x) => x.to_string(),
+        Literal::F32(x) => x.to_string(),
+        Literal::U32(x) => x.to_string(),
+        Literal::I32(x) => x.to_string(),
+        Literal::U64(x) => x.to_string(),
+        Literal::I64(x) => x.to_string(),
+        Literal::Bool(x) => x.to_string(),
+        Literal::AbstractInt(x) => x.to_string(),
+        Literal::AbstractFloat(x) => x.to_string(),
+    }
+}
+
+fn string(s: &str) -> String {
+    let mut result = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(result, "\\u{:04x}", c as u32);
+            }
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn optional_string(s: Option<&str>) -> String {
+    match s {
+        Some(s) => string(s),
+        None => "null".to_owned(),
+    }
+}
+
+fn array(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
@@ -0,0 +1,164 @@
+use std::{iter::Peekable, path::PathBuf};
+
+use proc_macro2::{token_stream::IntoIter, Delimiter, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{quote, quote_spanned};
+
+use crate::options::{join_path, Options};
+
+/// Environment variable of a file listing module paths provided at runtime,
+/// relative to `CARGO_MANIFEST_DIR`.
+const KNOWN_IMPORTS: &str = "WGSL_LN_IMPORTS";
+
+/// Warn on `#import`s not listed in the file in `WGSL_LN_IMPORTS`.
+///
+/// Only imports sharing a root with a listed module are checked, i.e. listing
+/// `bevy_pbr::pbr_functions` checks every import starting with `bevy_pbr`.
+pub fn check_imports(stream: &TokenStream, options: &Options) -> TokenStream {
+    let Ok(path) = std::env::var(KNOWN_IMPORTS) else {
+        return TokenStream::new();
+    };
+    let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = PathBuf::from(root).join(path);
+    let Ok(content) = std::fs::read_to_string(&file) else {
+        abort!(
+            Span::call_site(),
+            "Unable to read import paths \"{}\" in `{}`.",
+            file.display(),
+            KNOWN_IMPORTS
+        )
+    };
+    // One module per line, `#` starts a comment.
+    let mut known: Vec<String> = content
+        .lines()
+        .map(|x| x.split('#').next().unwrap_or_default().trim().to_owned())
+        .filter(|x| !x.is_empty())
+        .collect();
+    known.extend(options.stubs.iter().map(|x| x.module.clone()));
+    known.extend(options.module_paths.iter().map(|x| join_path(x)));
+    let mut imports = Vec::new();
+    let mut iter = stream.clone().into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        if matches!(&tt, TokenTree::Punct(p) if p.as_char() == '#')
+            && matches!(iter.peek(), Some(TokenTree::Ident(i)) if i == "import")
+        {
+            iter.next();
+            import_paths(&mut iter, String::new(), Vec::new(), &mut imports);
+        }
+    }
+    let warnings = imports.into_iter().filter_map(|(path, spans)| {
+        let first = |x: &str| x.split("::").next().unwrap_or_default().to_owned();
+        if !known.iter().any(|x| first(x) == first(&path)) {
+            return None;
+        }
+        let parent = path.rsplit_once("::").map(|x| x.0).unwrap_or_default();
+        if known.iter().any(|x| x == &path || x == parent) {
+            return None;
+        }
+        // Point to the first segment not in a known module.
+        let segments: Vec<_> = path.split("::").collect();
+        let idx = (1..segments.len())
+            .find(|n| {
+                let prefix = segments[..*n].join("::");
+                !known
+                    .iter()
+                    .any(|x| x == &prefix || x.starts_with(&format!("{}::", prefix)))
+            })
+            .unwrap_or(segments.len());
+        let span = spans.get(idx - 1).or(spans.last()).copied()?;
+        let message = match suggest(&known, &path) {
+            Some(x) => format!("Unknown import `{}`, did you mean `{}`?", path, x),
+            None => format!("Unknown import `{}`.", path),
+        };
+        Some(quote_spanned! {span=>
+            const _: () = {
+                #[deprecated(note = #message)]
+                const UNKNOWN_IMPORT: () = ();
+                UNKNOWN_IMPORT
+            };
+        })
+    });
+    let file = file.display().to_string();
+    quote! {
+        const _: &str = include_str!(#file);
+        #(#warnings)*
+    }
+}
+
+/// Parse the paths of `#import a::{b, c::d as e}` after `import`, with the span of each segment.
+fn import_paths(
+    iter: &mut Peekable<IntoIter>,
+    mut path: String,
+    mut spans: Vec<Span>,
+    result: &mut Vec<(String, Vec<Span>)>,
+) {
+    loop {
+        let is_segment = path.is_empty() || path.ends_with("::");
+        match iter.peek() {
+            Some(TokenTree::Ident(i)) if is_segment && i != "as" => {
+                spans.push(i.span());
+                path.push_str(&i.to_string());
+            }
+            Some(TokenTree::Literal(l)) if is_segment => {
+                spans.push(l.span());
+                path.push_str(&l.to_string());
+            }
+            Some(TokenTree::Punct(p)) if p.as_char() == ':' => path.push(':'),
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace && is_segment => {
+                let mut items = g.stream().into_iter().peekable();
+                iter.next();
+                loop {
+                    import_paths(&mut items, path.clone(), spans.clone(), result);
+                    match items.next() {
+                        Some(TokenTree::Punct(p)) if p.as_char() == ',' => (),
+                        _ => return,
+                    }
+                }
+            }
+            _ => break,
+        }
+        iter.next();
+    }
+    if matches!(iter.peek(), Some(TokenTree::Ident(i)) if i == "as") {
+        iter.next();
+        iter.next();
+    }
+    if !spans.is_empty() {
+        result.push((path, spans));
+    }
+}
+
+/// Find the closest known module to an import path or its parent.
+fn suggest(known: &[String], path: &str) -> Option<String> {
+    let (parent, item) = path.rsplit_once("::").unwrap_or((path, ""));
+    known
+        .iter()
+        .flat_map(|x| {
+            let item = match item {
+                "" => x.clone(),
+                item => format!("{}::{}", x, item),
+            };
+            [(distance(x, path), x.clone()), (distance(x, parent), item)]
+        })
+        .filter(|(d, x)| *d <= x.len() / 3)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, x)| x)
+}
+
+/// Levenshtein distance.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<_> = b.chars().collect();
+    let mut row: Vec<_> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (prev + (ca != *cb) as usize)
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            prev = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
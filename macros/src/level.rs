@@ -0,0 +1,96 @@
+use std::error::Error;
+
+use naga::{
+    valid::{Capabilities, ValidationFlags, Validator},
+    Module,
+};
+use proc_macro2::Span;
+use proc_macro_error::abort;
+
+use crate::{config::Config, wgsl2::span_at};
+
+/// How thoroughly shaders are checked, set with `WGSL_LN_VALIDATE=off|parse|full|strict`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Not parsed unless the naga module is needed.
+    Off,
+    /// Parsed but not validated.
+    Parse,
+    /// Validated with all capabilities.
+    Full,
+    /// Also validated with the default capabilities only, for portable shaders.
+    Strict,
+}
+
+impl Level {
+    /// `WGSL_LN_VALIDATE` if set, then `#![validate(..)]`, then `validate` in `wgsl_ln.toml`,
+    /// otherwise [`Level::Full`], or [`Level::Parse`] with `debug_parse_only` in debug builds.
+    ///
+    /// Proc macros are built with the `debug_assertions` of the profile being built.
+    pub fn get(option: Option<Level>) -> Level {
+        match std::env::var("WGSL_LN_VALIDATE").as_deref() {
+            Ok(name) => Level::parse(name).unwrap_or_else(|| {
+                abort!(
+                    Span::call_site(),
+                    "Unknown `WGSL_LN_VALIDATE={}`, expected `off`, `parse`, `full` or `strict`.",
+                    name
+                )
+            }),
+            Err(_) => match option.or_else(|| Level::parse(Config::get().validate.as_deref()?)) {
+                Some(level) => level,
+                None if cfg!(feature = "debug_parse_only") && cfg!(debug_assertions) => {
+                    Level::Parse
+                }
+                None => Level::Full,
+            },
+        }
+    }
+
+    /// Parse `off`, `parse`, `full` or `strict`.
+    pub fn parse(name: &str) -> Option<Level> {
+        match name {
+            "off" => Some(Level::Off),
+            "parse" => Some(Level::Parse),
+            "full" => Some(Level::Full),
+            "strict" => Some(Level::Strict),
+            _ => None,
+        }
+    }
+}
+
+/// Validate a module with the default capabilities,
+/// after it passed validation with all capabilities.
+pub fn validate_strict(module: &Module, source: &str, spans: &[(usize, Span)]) {
+    if let Some((span, message)) = capability_error(module, source, spans) {
+        abort!(
+            span,
+            "Wgsl Error: {}, `WGSL_LN_VALIDATE=strict` only allows the default capabilities.",
+            message
+        )
+    }
+}
+
+/// The first error of a module validated with the default capabilities, the capabilities of WebGPU.
+pub fn capability_error(
+    module: &Module,
+    source: &str,
+    spans: &[(usize, Span)],
+) -> Option<(Span, String)> {
+    let Err(e) = Validator::new(ValidationFlags::all(), Capabilities::default()).validate(module)
+    else {
+        return None;
+    };
+    // The reason is in the error's sources, i.e. the missing capability.
+    let mut message = e.to_string();
+    let mut error: &dyn Error = e.as_inner();
+    while let Some(source) = error.source() {
+        message.push_str(": ");
+        message.push_str(&source.to_string());
+        error = source;
+    }
+    let span = match e.spans().next() {
+        Some((span, _)) => span_at(spans, span.location(source).offset as usize),
+        None => Span::call_site(),
+    };
+    Some((span, message))
+}
@@ -0,0 +1,569 @@
+//! Procedural macros of [`wgsl_ln`](https://docs.rs/wgsl_ln), depend on `wgsl_ln` instead.
+
+use proc_macro::TokenStream as TokenStream1;
+use proc_macro_error::{proc_macro_error, set_dummy};
+use quote::quote;
+mod __wgsl_paste2;
+#[cfg(any(feature = "analyzer", wgsl_ln_dump))]
+mod analyzer;
+#[cfg(feature = "bevy")]
+mod bevy;
+#[cfg(any(feature = "cache", feature = "analyzer", wgsl_ln_dump))]
+mod cache;
+#[cfg(feature = "color")]
+mod color;
+mod config;
+mod consts;
+mod dump;
+mod eval;
+mod externs;
+#[cfg(feature = "glsl")]
+mod glsl2;
+#[cfg(feature = "gpu_test")]
+mod gpu_test;
+#[cfg(feature = "naga_ir")]
+mod ir;
+mod items;
+mod json;
+#[cfg(feature = "naga_oil")]
+mod known_imports;
+mod level;
+mod library;
+mod limits;
+#[cfg(feature = "manifest")]
+mod manifest;
+#[cfg(feature = "bevy")]
+mod material;
+#[cfg(feature = "math")]
+mod math;
+#[cfg(feature = "noise")]
+mod noise;
+#[cfg(feature = "naga_oil")]
+mod oil;
+mod open_close;
+mod options;
+mod passes;
+#[cfg(feature = "pbr")]
+mod pbr;
+mod portability;
+mod preprocess;
+mod profile;
+#[cfg(feature = "runtime")]
+mod reflect;
+mod rename;
+#[cfg(feature = "round_trip")]
+mod round_trip;
+mod samplers;
+mod sanitize;
+#[cfg(feature = "naga_oil")]
+mod shader_defs2;
+#[cfg(feature = "spirv")]
+mod spirv2;
+mod stats;
+mod storage;
+mod to_wgsl_string;
+#[cfg(feature = "webgl")]
+mod webgl;
+mod wesl;
+mod wgsl2;
+mod wgsl_catalog2;
+mod wgsl_concat2;
+mod wgsl_export2;
+#[cfg(feature = "gpu_test")]
+mod wgsl_pipeline_test2;
+mod wgsl_prelude2;
+#[cfg(feature = "gpu_test")]
+mod wgsl_property2;
+mod wgsl_snapshot2;
+mod wgsl_validate2;
+mod wgsl_variants2;
+
+/// Converts normal rust tokens into a wgsl `&'static str`, similar to [`stringify!`].
+/// This also validates the wgsl string using [`naga`]. Errors will be reported with
+/// the correct span.
+///
+/// ```
+/// # use wgsl_ln::wgsl;
+/// pub static MANHATTAN_DISTANCE: &str = wgsl!(
+///     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+///         return abs(a.x - b.x) + abs(a.y - b.y);
+///     }
+/// );
+/// ```
+///
+/// To import an exported item, use the `$name` syntax. See crate level documentation for details.
+///
+/// ```
+/// # use wgsl_ln::{wgsl, wgsl_export};
+/// # #[wgsl_export(manhattan_distance)]
+/// # pub static MANHATTAN_DISTANCE: &str = wgsl!(
+/// #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+/// #         return abs(a.x - b.x) + abs(a.y - b.y);
+/// #     }
+/// # );
+/// pub static MANHATTAN_DISTANCE_SQUARED: &str = wgsl!(
+///     fn manhattan_distance_squared(a: vec2<f32>, b: vec2<f32>) -> f32 {
+///         return $manhattan_distance(a, b) * manhattan_distance(a, b);
+///     }
+/// );
+/// ```
+#[proc_macro]
+#[proc_macro_error]
+pub fn wgsl(stream: TokenStream1) -> TokenStream1 {
+    set_dummy(quote! {""});
+    wgsl2::wgsl2(stream.into()).into()
+}
+
+/// [`wgsl!`] in `naga_oil` mode, equivalent to `wgsl!(#![oil] ..)`.
+///
+/// See crate level documentation for details.
+///
+/// Directives are emitted on their own line in the format `naga_oil` expects.
+///
+/// ```
+/// # use wgsl_ln::wgsl_oil;
+/// pub static IMPORTS: &str = wgsl_oil!(
+///     #![stub(a::b as fn() -> f32, a::e::f as fn() -> f32, a::e::g as fn() -> f32)]
+///     #![stub("shaders/lighting.wgsl"::light as fn() -> f32)]
+///     #import a::b as c
+///     #import a::{b, e::{f, g},}
+///     #import a::e;
+///     #import "shaders/lighting.wgsl"::light
+///     fn h() -> f32 {
+///         return c() + b() + f() + g() + e::f() + light();
+///     }
+/// );
+/// assert_eq!(
+///     IMPORTS,
+///     "\n#import a::b as c \n\n#import a::{b, e::{f, g},}\n\n#import a::e ;\n\
+///     \n#import \"shaders/lighting.wgsl\"::light \n\
+///     fn h ()-> f32 {\nreturn c ()+ b ()+ f ()+ g ()+ e::f ()+ light ();\n}\n"
+/// );
+/// ```
+#[cfg(feature = "naga_oil")]
+#[proc_macro]
+#[proc_macro_error]
+pub fn wgsl_oil(stream: TokenStream1) -> TokenStream1 {
+    set_dummy(quote! {""});
+    let stream = proc_macro2::TokenStream::from(stream);
+    wgsl2::wgsl2(quote! {#![oil] #stream}).into()
+}
+
+/// Validate GLSL with naga's GLSL frontend, requires the `glsl` feature.
+///
+/// `#![stage(vertex)]`, `#![stage(fragment)]` or `#![stage(compute)]` is required.
+/// Emits the GLSL as a `&'static str`, or the WGSL naga translates it to with `#![wgsl]`.
+/// GLSL is a string literal, or tokens written like [`wgsl!`] if it has no `#` directives.
+/// Errors in tokens are reported with the correct span, errors in a string at the string.
+///
+/// ```
+/// # use wgsl_ln::glsl;
+/// pub static RED: &str = glsl!(
+///     #![stage(fragment)]
+///     r#"
+///     #version 450
+///     layout(location = 0) out vec4 color;
+///     void main() {
+///         color = vec4(1.0, 0.0, 0.0, 1.0);
+///     }
+///     "#
+/// );
+///
+/// pub static RED_WGSL: &str = glsl!(
+///     #![stage(fragment)]
+///     #![wgsl]
+///     layout(location = 0) out vec4 color;
+///     void main() {
+///         color = vec4(1.0, 0.0, 0.0, 1.0);
+///     }
+/// );
+/// assert!(RED_WGSL.contains("@fragment"));
+/// ```
+#[cfg(feature = "glsl")]
+#[proc_macro]
+#[proc_macro_error]
+pub fn glsl(stream: TokenStream1) -> TokenStream1 {
+    set_dummy(quote! {""});
+    glsl2::glsl2(stream.into()).into()
+}
+
+/// Validate a SPIR-V binary with naga's SPIR-V frontend, requires the `spirv` feature.
+///
+/// The path is relative to the calling file like in [`include_bytes!`], the crate is rebuilt
+/// when the binary changes. Emits the bytes as a `&'static [u8]`, or the WGSL naga decompiles
+/// the binary to with `#![wgsl]`.
+///
+/// ```
+/// # /*
+/// pub static BLUR_SPV: &[u8] = wgsl_from_spirv!(include_bytes!("kernels/blur.spv"));
+///
+/// pub static BLUR: &str = wgsl_from_spirv!(
+///     #![wgsl]
+///     include_bytes!("kernels/blur.spv")
+/// );
+/// # */
+/// ```
+#[cfg(feature = "spirv")]
+#[proc_macro]
+#[proc_macro_error]
+pub fn wgsl_from_spirv(stream: TokenStream1) -> TokenStream1 {
+    spirv2::wgsl_from_spirv2(stream.into()).into()
+}
+
+/// Evaluate a constant WGSL expression with naga's constant evaluator, as a Rust value.
+///
+/// Scalars are Rust literals of the same type, vectors, matrices and arrays are Rust arrays,
+/// i.e. `[f32; 3]` for `vec3<f32>` and `[[f32; 2]; 4]` for `mat4x2<f32>`, columns first.
+/// Exported constants can be used with `$name`, so Rust and the shaders using them agree.
+///
+/// ```
+/// # use wgsl_ln::wgsl_eval;
+/// const LUT_SIZE: u32 = wgsl_eval!(4u * 16u + 3u);
+/// const SCALE: [f32; 3] = wgsl_eval!(vec3(0.5) * vec3(1.0, 2.0, 4.0));
+/// assert_eq!(LUT_SIZE, 67);
+/// assert_eq!(SCALE, [0.5, 1.0, 2.0]);
+/// ```
+#[proc_macro]
+#[proc_macro_error]
+pub fn wgsl_eval(stream: TokenStream1) -> TokenStream1 {
+    let stream = proc_macro2::TokenStream::from(stream);
+    let name = quote::format_ident!("{}", eval::EVAL);
+    wgsl2::wgsl2(quote! {#![eval] const #name = #stream;}).into()
+}
+
+/// Validate a WGSL string known at compile time with [`naga`], for shaders not written as tokens.
+///
+/// Accepts a string literal, `include_str!("path")` relative to the calling file,
+/// or `concat!(..)` of them, and emits the same `&'static str`. Errors are shown at
+/// the argument with naga's report. The value of a `const` is not known to macros,
+/// so strings built in `const`s cannot be validated.
+///
+/// ```
+/// # use wgsl_ln::wgsl_validate;
+/// pub static LEGACY: &str = wgsl_validate!(concat!(
+///     "fn double(x: f32) -> f32 {\n",
+///     "    return x * 2.0;\n",
+///     "}\n",
+/// ));
+/// assert!(LEGACY.starts_with("fn double"));
+/// # /*
+/// pub static BLUR: &str = wgsl_validate!(include_str!("shaders/blur.wgsl"));
+/// # */
+/// ```
+#[proc_macro]
+#[proc_macro_error]
+pub fn wgsl_validate(stream: TokenStream1) -> TokenStream1 {
+    wgsl_validate2::wgsl_validate2(stream.into()).into()
+}
+
+/// Test wgsl functions on the GPU, requires the `gpu_test` feature.
+///
+/// Each `#![case(function(args..) == expected)]` runs the function once in a compute shader
+/// with the arguments uploaded to storage buffers, and asserts the result read back equals
+/// `expected`. Arguments and results are Rust values of 32 bit scalars or vectors as arrays,
+/// i.e. `[f32; 2]` for `vec2<f32>`, and are compared exactly.
+///
+/// Functions under test are written or pasted in like in `wgsl!`,
+/// `import package::item;` pastes an exported item without calling it.
+/// A GPU or a software adapter like lavapipe or llvmpipe is required.
+///
+/// ```
+/// # /*
+/// #[test]
+/// fn manhattan_distance() {
+///     wgsl_test!(
+///         #![case(manhattan_distance([0.0, 0.0], [1.0, 2.0]) == 3.0)]
+///         #![case(manhattan_distance([1.0, 1.0], [1.0, 1.0]) == 0.0)]
+///         import package::manhattan_distance;
+///     );
+/// }
+/// # */
+/// ```
+#[cfg(feature = "gpu_test")]
+#[proc_macro]
+#[proc_macro_error]
+pub fn wgsl_test(stream: TokenStream1) -> TokenStream1 {
+    set_dummy(quote! {()});
+    let stream = proc_macro2::TokenStream::from(stream);
+    wgsl2::wgsl2(quote! {#![gpu_test] #stream}).into()
+}
+
+/// Test an exported wgsl function against the Rust function the attribute is on,
+/// requires the `gpu_test` feature.
+///
+/// Adds a test running the function on `samples` generated arguments in one dispatch,
+/// and comparing each result to the Rust reference. Floats may differ by `tolerance`
+/// relative to the larger value, or absolute below 1, integers are compared exactly.
+/// Generated components are uniform in `range`, integers are rounded down and clamped,
+/// a fixed `seed` makes failures reproducible.
+///
+/// | Argument    | Default        |
+/// |-------------|----------------|
+/// | `samples`   | `256`          |
+/// | `tolerance` | `1e-5`         |
+/// | `range`     | `-10.0..10.0`  |
+/// | `seed`      | `0`            |
+///
+/// The equivalent `#![property(function, reference = path, ..)]` can be used in [`wgsl_test!`],
+/// i.e. with a closure as `reference = (|a: f32| a * 2.0)`.
+///
+/// ```
+/// # /*
+/// #[wgsl_property(manhattan_distance, samples = 1000, tolerance = 1e-6)]
+/// fn manhattan_distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+///     (a[0] - b[0]).abs() + (a[1] - b[1]).abs()
+/// }
+/// # */
+/// ```
+#[cfg(feature = "gpu_test")]
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn wgsl_property(attr: TokenStream1, stream: TokenStream1) -> TokenStream1 {
+    set_dummy(stream.clone().into());
+    wgsl_property2::wgsl_property2(attr.into(), stream.into()).into()
+}
+
+/// Add a test creating every pipeline of a `#![shader]` on a headless GPU, requires the
+/// `gpu_test` feature.
+///
+/// Pipelines use the reflected bind group layouts, vertex entry points get a vertex
+/// buffer per input and fragment entry points a stub vertex stage and color targets
+/// matching their outputs. This catches backend specific errors `naga` validation misses.
+///
+/// ```
+/// # /*
+/// #[wgsl_pipeline_test]
+/// pub static BLUR: Shader = wgsl!(
+///     #![shader]
+///     @group(0) @binding(0) var<storage, read_write> pixels: array<vec4<f32>>;
+///     @compute @workgroup_size(8, 8)
+///     fn blur() {
+///         pixels[0] = vec4(0.0);
+///     }
+/// );
+/// # */
+/// ```
+#[cfg(feature = "gpu_test")]
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn wgsl_pipeline_test(attr: TokenStream1, stream: TokenStream1) -> TokenStream1 {
+    set_dummy(stream.clone().into());
+    wgsl_pipeline_test2::wgsl_pipeline_test2(attr.into(), stream.into()).into()
+}
+
+/// Export a wgsl item (function, struct, etc).
+///
+/// Must have the same `name` as the exported item.
+///
+/// Public `static` exports show the composed shader, with imports resolved,
+/// in a collapsed section of their documentation. `const`s, which can be associated
+/// items in an `impl` block, don't.
+///
+/// Use `wgsl!(#![export(name)] ..)` if the `wgsl!` call is generated by a macro.
+///
+/// ```
+/// # use wgsl_ln::{wgsl, wgsl_export};
+/// #[wgsl_export(manhattan_distance)]
+/// pub static MANHATTAN_DISTANCE: &str = wgsl!(
+///     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+///         return abs(a.x - b.x) + abs(a.y - b.y);
+///     }
+/// );
+/// ```
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn wgsl_export(attr: TokenStream1, stream: TokenStream1) -> TokenStream1 {
+    // The item without the export, so an error is not followed by errors at its uses.
+    set_dummy(stream.clone().into());
+
+    wgsl_export2::wgsl_export2(attr.into(), stream.into()).into()
+}
+
+/// Test that a shader matches a checked-in file, relative to `CARGO_MANIFEST_DIR`.
+///
+/// Adds a `#[test]` comparing the `&str` item to the file in test builds.
+/// Run with `WGSL_LN_UPDATE_SNAPSHOTS=1` to write the files instead.
+///
+/// ```
+/// # use wgsl_ln::{wgsl, wgsl_snapshot};
+/// #[wgsl_snapshot("snapshots/manhattan_distance.wgsl")]
+/// pub static MANHATTAN_DISTANCE: &str = wgsl!(
+///     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+///         return abs(a.x - b.x) + abs(a.y - b.y);
+///     }
+/// );
+/// ```
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn wgsl_snapshot(attr: TokenStream1, stream: TokenStream1) -> TokenStream1 {
+    let stream = proc_macro2::TokenStream::from(stream);
+    set_dummy(stream.clone());
+    wgsl_snapshot2::wgsl_snapshot2(attr.into(), stream).into()
+}
+
+/// Generate a module listing the exports of the crate, `wgsl_catalog` by default.
+///
+/// Each export has a constant with its composed source, documented with the kind
+/// of the exported item and the `static` or `const` it is exported from.
+/// `EXPORTS` lists them as `(name, kind, item)`, `SOURCES` lists `(name, source)`
+/// of the exports that are valid on their own, without `$extern`s or imports.
+///
+/// Must be placed at the end of the crate root, after every module with exports.
+/// Exports expanded later are not listed and cause a warning.
+/// rust-analyzer expands macros on demand and may show an incomplete catalog.
+///
+/// ```
+/// # use wgsl_ln::{wgsl, wgsl_catalog, wgsl_export};
+/// #[wgsl_export(manhattan_distance)]
+/// pub static MANHATTAN_DISTANCE: &str = wgsl!(
+///     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+///         return abs(a.x - b.x) + abs(a.y - b.y);
+///     }
+/// );
+///
+/// wgsl_catalog!(shaders);
+///
+/// assert_eq!(shaders::EXPORTS, &[("manhattan_distance", "fn", "MANHATTAN_DISTANCE")]);
+/// assert_eq!(shaders::manhattan_distance, MANHATTAN_DISTANCE);
+/// ```
+#[proc_macro]
+#[proc_macro_error]
+pub fn wgsl_catalog(stream: TokenStream1) -> TokenStream1 {
+    wgsl_catalog2::wgsl_catalog2(stream.into()).into()
+}
+
+/// Concatenate exported shaders and WGSL strings into one shader, validated like [`wgsl!`].
+///
+/// Items are `static`s or `const`s exported with [`macro@wgsl_export`] earlier in the crate,
+/// imported like `import package::name;`, so dependencies they share are pasted once.
+/// Strings are written as is. Leading `#![..]` options are passed to [`wgsl!`].
+///
+/// ```
+/// # use wgsl_ln::{wgsl, wgsl_concat, wgsl_export};
+/// #[wgsl_export(manhattan_distance)]
+/// pub static MANHATTAN_DISTANCE: &str = wgsl!(
+///     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+///         return abs(a.x - b.x) + abs(a.y - b.y);
+///     }
+/// );
+///
+/// pub static DISTANCES: &str = wgsl_concat!(
+///     MANHATTAN_DISTANCE,
+///     "fn chebyshev_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+///         return max(abs(a.x - b.x), abs(a.y - b.y));
+///     }",
+/// );
+/// assert!(DISTANCES.contains("fn manhattan_distance"));
+/// assert!(DISTANCES.contains("fn chebyshev_distance"));
+/// ```
+#[proc_macro]
+#[proc_macro_error]
+pub fn wgsl_concat(stream: TokenStream1) -> TokenStream1 {
+    set_dummy(quote! {""});
+    wgsl_concat2::wgsl_concat2(stream.into()).into()
+}
+
+/// Generate a function returning a shader per variant of a Rust enum, each validated like [`wgsl!`].
+///
+/// The function's body maps patterns to shader defs, `NAME` or `NAME = value`. The shader
+/// after it is written once, `#ifdef`, `#ifndef`, `#if NAME == value`, `#else` and `#endif`
+/// are resolved for each variant at compile time, and must be closed in the block they open in.
+/// The generated `match` has no wildcard, so a new variant is an error until it is mapped.
+///
+/// ```
+/// # use wgsl_ln::wgsl_variants;
+/// #[derive(Clone, Copy)]
+/// pub enum Quality {
+///     Low,
+///     High,
+/// }
+///
+/// wgsl_variants!(
+///     pub fn shader_for(quality: Quality) -> &'static str {
+///         Quality::Low => (),
+///         Quality::High => (SHADOWS, SAMPLES = 4),
+///     }
+///     fn shadow(x: f32) -> f32 {
+///         #ifdef SHADOWS
+///             #if SAMPLES >= 4
+///             return x * 0.25;
+///             #else
+///             return x;
+///             #endif
+///         #else
+///         return 1.0;
+///         #endif
+///     }
+/// );
+/// assert!(shader_for(Quality::Low).contains("return 1.0"));
+/// assert!(shader_for(Quality::High).contains("return x * 0.25"));
+/// ```
+#[proc_macro]
+#[proc_macro_error]
+pub fn wgsl_variants(stream: TokenStream1) -> TokenStream1 {
+    wgsl_variants2::wgsl_variants2(stream.into()).into()
+}
+
+/// Import exported items in every [`wgsl!`] and [`wgsl_oil!`] call in the rest of the module,
+/// like `import package::item;`.
+///
+/// Defines `wgsl!` and `wgsl_oil!` macros shadowing the ones of this crate, don't import those.
+/// Items a shader declares itself are not imported, so exports can be defined in the same module.
+///
+/// ```
+/// # use wgsl_ln::{wgsl_export, wgsl_prelude};
+/// wgsl_prelude!(manhattan_distance);
+///
+/// #[wgsl_export(manhattan_distance)]
+/// pub static MANHATTAN_DISTANCE: &str = wgsl!(
+///     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+///         return abs(a.x - b.x) + abs(a.y - b.y);
+///     }
+/// );
+///
+/// pub static MANHATTAN_DISTANCE_TIMES_FIVE: &str = wgsl!(
+///     fn manhattan_distance_times_five(a: vec2<f32>, b: vec2<f32>) -> f32 {
+///         return manhattan_distance(a, b) * 5.0;
+///     }
+/// );
+/// assert!(MANHATTAN_DISTANCE_TIMES_FIVE.contains("fn manhattan_distance"));
+/// ```
+#[proc_macro]
+#[proc_macro_error]
+pub fn wgsl_prelude(stream: TokenStream1) -> TokenStream1 {
+    wgsl_prelude2::wgsl_prelude2(stream.into()).into()
+}
+
+/// Paste and avoid duplicates.
+#[doc(hidden)]
+#[proc_macro]
+#[proc_macro_error]
+pub fn __wgsl_paste(stream: TokenStream1) -> TokenStream1 {
+    set_dummy(quote! {""});
+
+    __wgsl_paste2::__wgsl_paste2(stream.into()).into()
+}
+
+/// Create a `HashMap<String, naga_oil::compose::ShaderDefValue>` from rust values.
+///
+/// Each entry is `NAME: type = value` where `type` is `bool`, `i32` or `u32`,
+/// or just `NAME` for `true`. Attributes like `#[cfg(..)]` are applied to the entry.
+///
+/// ```
+/// # /*
+/// const MSAA_SAMPLES: u32 = 4;
+///
+/// let defs = shader_defs! {
+///     MSAA_SAMPLES: u32 = MSAA_SAMPLES,
+///     SHADOWS: bool = cfg!(feature = "shadows"),
+///     #[cfg(feature = "fog")]
+///     FOG,
+/// };
+/// # */
+/// ```
+#[cfg(feature = "naga_oil")]
+#[proc_macro]
+#[proc_macro_error]
+pub fn shader_defs(stream: TokenStream1) -> TokenStream1 {
+    shader_defs2::shader_defs2(stream.into()).into()
+}
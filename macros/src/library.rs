@@ -0,0 +1,51 @@
+use proc_macro2::{Ident, TokenStream};
+use proc_macro_error::abort;
+
+/// Names of the exports of a library and their sources.
+type Library = (&'static [&'static str], fn(&str) -> Option<TokenStream>);
+
+/// Libraries enabled by features, pasted with `$wgsl_ln::name`.
+const LIBRARIES: &[Library] = &[
+    #[cfg(feature = "color")]
+    (crate::color::EXPORTS, crate::color::source),
+    #[cfg(feature = "math")]
+    (crate::math::EXPORTS, crate::math::source),
+    #[cfg(feature = "noise")]
+    (crate::noise::EXPORTS, crate::noise::source),
+    #[cfg(feature = "pbr")]
+    (crate::pbr::EXPORTS, crate::pbr::source),
+];
+
+/// Features providing a library.
+const FEATURES: &[&str] = &["color", "math", "noise", "pbr"];
+
+/// Source of `$wgsl_ln::name` from an enabled library.
+pub fn source(krate: &Ident, name: &Ident) -> TokenStream {
+    let name_str = name.to_string();
+    if let Some(source) = LIBRARIES.iter().find_map(|(_, source)| source(&name_str)) {
+        return source;
+    }
+    let exports: Vec<_> = LIBRARIES
+        .iter()
+        .flat_map(|(exports, _)| exports.iter())
+        .map(|x| format!("`{}`", x))
+        .collect();
+    match exports.is_empty() {
+        true => abort!(
+            krate.span(),
+            "`$wgsl_ln::{}` requires one of the features {}.",
+            name,
+            FEATURES
+                .iter()
+                .map(|x| format!("`{}`", x))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        false => abort!(
+            name.span(),
+            "`{}` is not in the enabled libraries of `wgsl_ln`, expected one of {}.",
+            name,
+            exports.join(", ")
+        ),
+    }
+}
@@ -0,0 +1,274 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use naga::{
+    valid::{GlobalUse, ModuleInfo},
+    AddressSpace, ArraySize, ImageClass, Module, ShaderStage, TypeInner,
+};
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use toml::{Table, Value};
+
+use crate::{config::parse_toml, options::string_literal, wgsl2::global_span};
+
+/// Limits of a target adapter, read from the file set by `limits` in `wgsl_ln.toml`.
+///
+/// The file is `wgpu::Limits` serialized as JSON or TOML, keys in `camelCase` or `snake_case`.
+/// Limits missing from the file are not checked.
+#[derive(Debug)]
+pub struct Limits {
+    /// The file in quotes or `WebGPU`, for errors.
+    origin: String,
+    values: HashMap<String, u64>,
+}
+
+impl Limits {
+    /// Read a limits file relative to `CARGO_MANIFEST_DIR`.
+    pub fn read(path: &str) -> Limits {
+        let file =
+            PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default()).join(path);
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            abort!(
+                Span::call_site(),
+                "Unable to read limits \"{}\".",
+                file.display()
+            )
+        };
+        let file = file.display().to_string();
+        // `{"maxBindGroups": 4, ..}` is also a valid token stream, TOML cannot start with `{`.
+        let values = match content.trim_start().starts_with('{') {
+            true => json_numbers(&content).unwrap_or_else(|| {
+                abort!(
+                    Span::call_site(),
+                    "Expected an object of numbers in limits \"{}\".",
+                    file
+                )
+            }),
+            false => {
+                let mut values = Vec::new();
+                toml_numbers(&parse_toml(&content, &file), &file, &mut values);
+                values
+            }
+        };
+        let values = values
+            .into_iter()
+            .map(|(key, value)| (snake_case(&key), value))
+            .collect();
+        Limits {
+            origin: format!("\"{}\"", path),
+            values,
+        }
+    }
+
+    /// The default limits of WebGPU, supported by every browser.
+    pub fn webgpu() -> Limits {
+        let values = [
+            ("max_bind_groups", 4),
+            ("max_bindings_per_bind_group", 1000),
+            ("max_sampled_textures_per_shader_stage", 16),
+            ("max_samplers_per_shader_stage", 16),
+            ("max_storage_buffers_per_shader_stage", 8),
+            ("max_storage_textures_per_shader_stage", 4),
+            ("max_uniform_buffers_per_shader_stage", 12),
+            ("max_uniform_buffer_binding_size", 64 << 10),
+            ("max_storage_buffer_binding_size", 128 << 20),
+            ("max_compute_workgroup_storage_size", 16384),
+            ("max_compute_invocations_per_workgroup", 256),
+            ("max_compute_workgroup_size_x", 256),
+            ("max_compute_workgroup_size_y", 256),
+            ("max_compute_workgroup_size_z", 64),
+        ];
+        Limits {
+            origin: "WebGPU".to_owned(),
+            values: values.map(|(k, v)| (k.to_owned(), v)).into(),
+        }
+    }
+
+    fn check(
+        &self,
+        exceeded: &mut Vec<(Span, String)>,
+        span: Span,
+        limit: &str,
+        value: u64,
+        what: &str,
+    ) {
+        if let Some(max) = self.values.get(limit).filter(|max| value > **max) {
+            let message = format!(
+                "{}, exceeding `{} = {}` of {}.",
+                what, limit, max, self.origin
+            );
+            exceeded.push((span, message));
+        }
+    }
+
+    /// Bindings, workgroup sizes and buffer binding sizes of a validated module exceeding the limits.
+    pub fn exceeded(
+        &self,
+        module: &Module,
+        info: &ModuleInfo,
+        source: &str,
+        spans: &[(usize, Span)],
+    ) -> Vec<(Span, String)> {
+        let mut exceeded = Vec::new();
+        for (handle, var) in module.global_variables.iter() {
+            let span = global_span(module, handle, source, spans);
+            let name = var.name.as_deref().unwrap_or_default();
+            if let Some(binding) = &var.binding {
+                let what = format!("`{}` is in `@group({})`", name, binding.group);
+                self.check(
+                    &mut exceeded,
+                    span,
+                    "max_bind_groups",
+                    binding.group as u64 + 1,
+                    &what,
+                );
+                let what = format!("`{}` is `@binding({})`", name, binding.binding);
+                let limit = "max_bindings_per_bind_group";
+                self.check(
+                    &mut exceeded,
+                    span,
+                    limit,
+                    binding.binding as u64 + 1,
+                    &what,
+                );
+            }
+            let size = module.types[var.ty].inner.size(module.to_ctx()) as u64;
+            let limit = match var.space {
+                AddressSpace::Uniform => "max_uniform_buffer_binding_size",
+                AddressSpace::Storage { .. } => "max_storage_buffer_binding_size",
+                AddressSpace::PushConstant => "max_push_constant_size",
+                _ => continue,
+            };
+            self.check(
+                &mut exceeded,
+                span,
+                limit,
+                size,
+                &format!("`{}` is {} bytes", name, size),
+            );
+        }
+        for (idx, entry) in module.entry_points.iter().enumerate() {
+            let used = info.get_entry_point(idx);
+            let mut counts = HashMap::new();
+            let mut workgroup_storage = 0;
+            for (handle, var) in module.global_variables.iter() {
+                if used[handle] == GlobalUse::empty() {
+                    continue;
+                }
+                let (ty, count) = match module.types[var.ty].inner {
+                    TypeInner::BindingArray {
+                        base,
+                        size: ArraySize::Constant(size),
+                    } => (base, size.get() as u64),
+                    TypeInner::BindingArray { base, .. } => (base, 1),
+                    _ => (var.ty, 1),
+                };
+                let kind = match (var.space, &module.types[ty].inner) {
+                    (AddressSpace::Uniform, _) => ("uniform_buffers", "uniform buffers"),
+                    (AddressSpace::Storage { .. }, _) => ("storage_buffers", "storage buffers"),
+                    (AddressSpace::WorkGroup, inner) => {
+                        workgroup_storage += inner.size(module.to_ctx()) as u64;
+                        continue;
+                    }
+                    (_, TypeInner::Sampler { .. }) => ("samplers", "samplers"),
+                    (_, TypeInner::Image { class, .. }) => match class {
+                        ImageClass::Storage { .. } => ("storage_textures", "storage textures"),
+                        _ => ("sampled_textures", "sampled textures"),
+                    },
+                    _ => continue,
+                };
+                *counts.entry(kind).or_insert(0) += count;
+            }
+            let mut counts: Vec<_> = counts.into_iter().collect();
+            counts.sort();
+            for ((kind, noun), count) in counts {
+                let limit = format!("max_{}_per_shader_stage", kind);
+                let what = format!("`{}` uses {} {}", entry.name, count, noun);
+                self.check(&mut exceeded, Span::call_site(), &limit, count, &what);
+            }
+            if entry.stage != ShaderStage::Compute {
+                continue;
+            }
+            let [x, y, z] = entry.workgroup_size.map(u64::from);
+            for (dim, size) in ["x", "y", "z"].into_iter().zip([x, y, z]) {
+                let limit = format!("max_compute_workgroup_size_{}", dim);
+                let what = format!(
+                    "`@workgroup_size` of `{}` is {} in {}",
+                    entry.name, size, dim
+                );
+                self.check(&mut exceeded, Span::call_site(), &limit, size, &what);
+            }
+            let limit = "max_compute_invocations_per_workgroup";
+            let what = format!(
+                "`{}` has {} invocations per workgroup",
+                entry.name,
+                x * y * z
+            );
+            self.check(&mut exceeded, Span::call_site(), limit, x * y * z, &what);
+            let limit = "max_compute_workgroup_storage_size";
+            let what = format!(
+                "`{}` uses {} bytes of workgroup storage",
+                entry.name, workgroup_storage
+            );
+            self.check(
+                &mut exceeded,
+                Span::call_site(),
+                limit,
+                workgroup_storage,
+                &what,
+            );
+        }
+        exceeded
+    }
+}
+
+/// `key = value` pairs of a TOML file, in tables as well.
+fn toml_numbers(table: &Table, file: &str, values: &mut Vec<(String, u64)>) {
+    for (key, value) in table {
+        match value {
+            Value::Table(table) => toml_numbers(table, file, values),
+            Value::Integer(value) if *value >= 0 => values.push((key.clone(), *value as u64)),
+            _ => abort!(
+                Span::call_site(),
+                "Expected a number for `{}` in limits \"{}\".",
+                key,
+                file
+            ),
+        }
+    }
+}
+
+/// `"key": value` pairs of a JSON object, `None` if it has values other than numbers.
+fn json_numbers(content: &str) -> Option<Vec<(String, u64)>> {
+    let Some(TokenTree::Group(object)) = content.parse::<TokenStream>().ok()?.into_iter().next()
+    else {
+        return None;
+    };
+    if object.delimiter() != Delimiter::Brace {
+        return None;
+    }
+    let tokens: Vec<_> = object.stream().into_iter().collect();
+    tokens
+        .split(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ','))
+        .filter(|x| !x.is_empty())
+        .map(|entry| match entry {
+            [TokenTree::Literal(key), TokenTree::Punct(p), TokenTree::Literal(value)]
+                if p.as_char() == ':' =>
+            {
+                Some((string_literal(key)?, value.to_string().parse().ok()?))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// `maxBindGroups` as `max_bind_groups`.
+fn snake_case(key: &str) -> String {
+    let mut result = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            result.push('_');
+        }
+        result.push(c.to_ascii_lowercase());
+    }
+    result
+}
@@ -0,0 +1,51 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use crate::{options::Options, wgsl2::fnv1a};
+
+/// Lines of the manifest by `OUT_DIR` and call site.
+///
+/// The proc macro is loaded once per compilation of a crate,
+/// so call sites removed since the last build are not listed.
+/// See `CATALOG` in `wgsl_catalog2.rs` for what state kept across expansions relies on.
+#[allow(clippy::type_complexity)]
+static MANIFEST: Mutex<BTreeMap<String, BTreeMap<(String, u32, u32), String>>> =
+    Mutex::new(BTreeMap::new());
+
+/// Write `<hash> <file>:<line>:<column> <label>` for each shader
+/// to `OUT_DIR/wgsl_ln/manifest.txt`, sorted by call site.
+///
+/// Does nothing if `OUT_DIR` is not set, i.e. in crates without a `build.rs`.
+pub fn record(options: &Options, output: &str) {
+    let (Ok(dir), Some(call_site)) = (std::env::var("OUT_DIR"), &options.call_site) else {
+        return;
+    };
+    let label = match &options.label {
+        Some(label) => label.replace(['\r', '\n'], " "),
+        None => "-".to_owned(),
+    };
+    let line = format!(
+        "{:032x} {} {}\n",
+        fnv1a(output.as_bytes()),
+        call_site,
+        label
+    );
+    // Sort by line and column as numbers.
+    let mut parts = call_site.rsplitn(3, ':');
+    let column = parts.next().and_then(|x| x.parse().ok()).unwrap_or(0);
+    let row = parts.next().and_then(|x| x.parse().ok()).unwrap_or(0);
+    let key = (parts.next().unwrap_or_default().to_owned(), row, column);
+    let mut manifest = MANIFEST.lock().unwrap();
+    let lines = manifest.entry(dir.clone()).or_default();
+    lines.insert(key, line);
+    let content: String = lines.values().map(String::as_str).collect();
+    let file = std::path::Path::new(&dir)
+        .join("wgsl_ln")
+        .join("manifest.txt");
+    if std::fs::read_to_string(&file).is_ok_and(|x| x == content) {
+        return;
+    }
+    if let Some(parent) = file.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let _ = std::fs::write(&file, content);
+}
@@ -0,0 +1,448 @@
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+/// Bind group of material bindings in `bevy`.
+const MATERIAL_GROUP: &str = "2";
+
+/// Generate an `AsBindGroup` struct from the bindings in `@group(2)`, for `#![material(Name)]`.
+///
+/// Uniform and storage buffers of structs declared in the shader
+/// generate `ShaderType` structs, other structs are expected to be in scope.
+pub fn material_items(vis: &[TokenTree], name: &Ident, source: TokenStream) -> TokenStream {
+    let tokens: Vec<_> = source.into_iter().collect();
+    let structs = wgsl_structs(&tokens);
+    let mut used = Vec::new();
+    let mut fields: Vec<TokenStream> = Vec::new();
+    // The last texture field, samplers are attached to it.
+    let mut texture: Option<(TokenStream, Ident)> = None;
+    for binding in bindings(&tokens) {
+        let Binding {
+            index,
+            space,
+            name: field,
+            ty,
+        } = binding;
+        let space: Vec<_> = space.iter().map(|x| x.to_string()).collect();
+        let space: Vec<_> = space.iter().map(|x| x.as_str()).collect();
+        let attr = match space.as_slice() {
+            ["uniform"] => quote! {#[uniform(#index)]},
+            ["storage"] | ["storage", "read"] => quote! {#[storage(#index, read_only)]},
+            ["storage", "read_write"] => quote! {#[storage(#index)]},
+            [] => {
+                let kind = ty.name.to_string();
+                if kind == "sampler" || kind == "sampler_comparison" {
+                    let Some((attrs, _)) = &mut texture else {
+                        abort!(ty.name.span(), "Expected a texture before `{}`.", kind)
+                    };
+                    attrs.extend(match kind.as_str() {
+                        "sampler" => quote! {#[sampler(#index)]},
+                        _ => quote! {#[sampler(#index, sampler_type = "comparison")]},
+                    });
+                    continue;
+                }
+                if let Some((attrs, field)) = texture.take() {
+                    fields.push(quote! {#attrs #(#vis)* #field: Option<::bevy::asset::Handle<::bevy::render::texture::Image>>});
+                }
+                texture = Some((texture_attr(index, &ty), field));
+                continue;
+            }
+            _ => abort!(
+                field.span(),
+                "Unsupported address space in `#![material(..)]`."
+            ),
+        };
+        let ty = call_site(host_type(&ty, &structs, &mut used));
+        fields.push(quote! {#attr #(#vis)* #field: #ty});
+    }
+    if let Some((attrs, field)) = texture {
+        fields.push(quote! {#attrs #(#vis)* #field: Option<::bevy::asset::Handle<::bevy::render::texture::Image>>});
+    }
+    let mut shader_types = Vec::new();
+    let mut idx = 0;
+    // Fields of generated structs may add more structs.
+    while idx < used.len() {
+        let (ident, members) = structs.iter().find(|(i, _)| i == &used[idx]).unwrap();
+        let members: Vec<_> = members
+            .iter()
+            .map(|(field, ty)| {
+                let runtime = ty.name == "array" && ty.args.len() == 1;
+                let ty = call_site(host_type(ty, &structs, &mut used));
+                match runtime {
+                    true => quote! {#[size(runtime)] #(#vis)* #field: #ty},
+                    false => quote! {#(#vis)* #field: #ty},
+                }
+            })
+            .collect();
+        shader_types.push(quote! {
+            #[derive(::bevy::render::render_resource::ShaderType, Clone, Debug)]
+            #(#vis)* struct #ident {
+                #(#members,)*
+            }
+        });
+        idx += 1;
+    }
+    quote! {
+        #[derive(
+            ::bevy::render::render_resource::AsBindGroup,
+            ::bevy::asset::Asset,
+            ::bevy::reflect::TypePath,
+            Clone,
+            Debug,
+        )]
+        #(#vis)* struct #name {
+            #(#fields,)*
+        }
+        #(#shader_types)*
+    }
+}
+
+/// A WGSL type, i.e. `array<vec4<f32>, 4>`.
+struct Type {
+    name: Ident,
+    args: Vec<Vec<TokenTree>>,
+}
+
+/// A global `@group(2) @binding(index) var<space> name: ty;`.
+struct Binding {
+    index: u32,
+    space: Vec<Ident>,
+    name: Ident,
+    ty: Type,
+}
+
+/// Find top level bindings in the material bind group.
+fn bindings(tokens: &[TokenTree]) -> Vec<Binding> {
+    let mut result = Vec::new();
+    let mut group = None;
+    let mut binding = None;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match &tokens[idx..] {
+            [TokenTree::Punct(at), TokenTree::Ident(attr), TokenTree::Group(g), ..]
+                if at.as_char() == '@' && g.delimiter() == Delimiter::Parenthesis =>
+            {
+                let value = g.stream().to_string();
+                match attr.to_string().as_str() {
+                    "group" => group = Some(value),
+                    "binding" => match value.trim_end_matches('u').parse::<u32>() {
+                        Ok(index) => binding = Some(index),
+                        Err(_) => abort!(g.span(), "Expected a binding index."),
+                    },
+                    _ => (),
+                }
+                idx += 3;
+                continue;
+            }
+            [TokenTree::Ident(var), ..] if var == "var" => {
+                let end = tokens[idx..]
+                    .iter()
+                    .position(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ';'))
+                    .map(|x| idx + x)
+                    .unwrap_or(tokens.len());
+                if let (Some(MATERIAL_GROUP), Some(index)) = (group.as_deref(), binding) {
+                    result.push(parse_var(index, &tokens[idx + 1..end], var.span()));
+                }
+                idx = end;
+            }
+            _ => (),
+        }
+        group = None;
+        binding = None;
+        idx += 1;
+    }
+    result
+}
+
+/// Parse `<space> name: ty` after `var`.
+fn parse_var(index: u32, tokens: &[TokenTree], span: Span) -> Binding {
+    let mut iter = tokens.iter().peekable();
+    let mut space = Vec::new();
+    if matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '<') {
+        for tt in iter.by_ref() {
+            match tt {
+                TokenTree::Ident(i) => space.push(i.clone()),
+                TokenTree::Punct(p) if p.as_char() == '>' => break,
+                _ => (),
+            }
+        }
+    }
+    let (Some(TokenTree::Ident(name)), Some(TokenTree::Punct(colon))) = (iter.next(), iter.next())
+    else {
+        abort!(span, "Expected `var name: type`.")
+    };
+    if colon.as_char() != ':' {
+        abort!(colon.span(), "Expected `:`.")
+    }
+    let ty: Vec<_> = iter.cloned().collect();
+    Binding {
+        index,
+        space,
+        name: name.clone(),
+        ty: parse_type(&ty, span),
+    }
+}
+
+/// Parse a type, ignoring `$` and `#` in front of pasted names.
+fn parse_type(tokens: &[TokenTree], span: Span) -> Type {
+    let mut iter = tokens.iter().skip_while(
+        |x| matches!(x, TokenTree::Punct(p) if p.as_char() == '$' || p.as_char() == '#'),
+    );
+    let Some(TokenTree::Ident(name)) = iter.next() else {
+        abort!(span, "Expected a type.")
+    };
+    let mut args = Vec::new();
+    let mut arg = Vec::new();
+    let mut depth = 0;
+    for tt in iter {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == '<' => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            }
+            TokenTree::Punct(p) if p.as_char() == '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            TokenTree::Punct(p) if p.as_char() == ',' && depth == 1 => {
+                args.push(std::mem::take(&mut arg));
+                continue;
+            }
+            _ => (),
+        }
+        arg.push(tt.clone());
+    }
+    if !arg.is_empty() {
+        args.push(arg);
+    }
+    Type {
+        name: name.clone(),
+        args,
+    }
+}
+
+/// Find top level `struct Name { field: ty, .. }`.
+fn wgsl_structs(tokens: &[TokenTree]) -> Vec<(Ident, Vec<(Ident, Type)>)> {
+    let mut result = Vec::new();
+    for window in tokens.windows(3) {
+        let [TokenTree::Ident(keyword), TokenTree::Ident(name), TokenTree::Group(g)] = window
+        else {
+            continue;
+        };
+        if keyword != "struct" || g.delimiter() != Delimiter::Brace {
+            continue;
+        }
+        let mut fields = Vec::new();
+        let mut field = Vec::new();
+        let mut depth = 0;
+        for tt in g.stream() {
+            match &tt {
+                TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+                TokenTree::Punct(p) if p.as_char() == '>' => depth -= 1,
+                TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => {
+                    fields.extend(struct_member(&std::mem::take(&mut field)));
+                    continue;
+                }
+                _ => (),
+            }
+            field.push(tt);
+        }
+        fields.extend(struct_member(&field));
+        result.push((name.clone(), fields));
+    }
+    result
+}
+
+/// Parse `@align(16) name: ty`.
+fn struct_member(tokens: &[TokenTree]) -> Option<(Ident, Type)> {
+    let mut idx = 0;
+    while let [TokenTree::Punct(at), TokenTree::Ident(_), rest @ ..] = &tokens[idx..] {
+        if at.as_char() != '@' {
+            break;
+        }
+        idx += match rest.first() {
+            Some(TokenTree::Group(_)) => 3,
+            _ => 2,
+        };
+    }
+    match &tokens[idx..] {
+        [TokenTree::Ident(name), TokenTree::Punct(colon), ty @ ..] if colon.as_char() == ':' => {
+            Some((name.clone(), parse_type(ty, name.span())))
+        }
+        [] => None,
+        [tt, ..] => abort!(tt.span(), "Expected `name: type`."),
+    }
+}
+
+/// `#[texture(..)]` for a texture type.
+fn texture_attr(index: u32, ty: &Type) -> TokenStream {
+    let kind = ty.name.to_string();
+    if let Some(dimension) = kind.strip_prefix("texture_storage_") {
+        let [format, access] = ty.args.as_slice() else {
+            abort!(ty.name.span(), "Expected `{}<format, access>`.", kind)
+        };
+        let format = format_ident!("{}", texture_format(&tokens_string(format)));
+        let access = match tokens_string(access).as_str() {
+            "read" => quote! {ReadOnly},
+            "write" => quote! {WriteOnly},
+            _ => quote! {ReadWrite},
+        };
+        return quote! {#[storage_texture(#index, image_format = #format, access = #access, dimension = #dimension)]};
+    }
+    let (depth, rest) = match kind.strip_prefix("texture_depth_") {
+        Some(rest) => (true, rest),
+        None => match kind.strip_prefix("texture_") {
+            Some(rest) => (false, rest),
+            None => abort!(
+                ty.name.span(),
+                "Unsupported type `{}` in `#![material(..)]`.",
+                kind
+            ),
+        },
+    };
+    let (multisampled, dimension) = match rest.strip_prefix("multisampled_") {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let sample_type = match ty.args.first().map(|x| tokens_string(x)).as_deref() {
+        _ if depth => "depth",
+        Some("i32") => "s_int",
+        Some("u32") => "u_int",
+        _ => "float",
+    };
+    let mut args = vec![quote! {#index}];
+    if dimension != "2d" {
+        args.push(quote! {dimension = #dimension});
+    }
+    if sample_type != "float" {
+        args.push(quote! {sample_type = #sample_type});
+    }
+    if multisampled {
+        args.push(quote! {multisampled = true});
+    }
+    quote! {#[texture(#(#args),*)]}
+}
+
+/// Convert a WGSL texel format to a `TextureFormat` variant, i.e. `rgba8unorm` to `Rgba8Unorm`.
+fn texture_format(format: &str) -> String {
+    let digits = format.find(|c: char| c.is_ascii_digit()).unwrap_or(0);
+    let suffix = format[digits..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map(|x| x + digits)
+        .unwrap_or(format.len());
+    let capitalize = |s: &str| {
+        let mut chars = s.chars();
+        chars
+            .next()
+            .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+            .unwrap_or_default()
+    };
+    format!(
+        "{}{}{}",
+        capitalize(&format[..digits]),
+        &format[digits..suffix],
+        capitalize(&format[suffix..])
+    )
+}
+
+fn tokens_string(tokens: &[TokenTree]) -> String {
+    tokens.iter().map(|x| x.to_string()).collect()
+}
+
+/// Rust type of a host-shareable WGSL type.
+fn host_type(
+    ty: &Type,
+    structs: &[(Ident, Vec<(Ident, Type)>)],
+    used: &mut Vec<Ident>,
+) -> TokenStream {
+    let name = ty.name.to_string();
+    let span = ty.name.span();
+    let arg = |i: usize| match ty.args.get(i) {
+        Some(arg) => parse_type(arg, span),
+        None => abort!(span, "Expected a type argument for `{}`.", name),
+    };
+    // Element type of shorthands like `vec4f`.
+    let scalar = |shorthand: Option<char>| match shorthand {
+        Some('f') => "f32".to_owned(),
+        Some('i') => "i32".to_owned(),
+        Some('u') => "u32".to_owned(),
+        Some(c) => abort!(span, "Unsupported element type `{}` in `{}`.", c, name),
+        None => arg(0).name.to_string(),
+    };
+    match name.as_str() {
+        "f32" | "i32" | "u32" => {
+            let ident = &ty.name;
+            quote! {#ident}
+        }
+        "atomic" => host_type(&arg(0), structs, used),
+        "array" => {
+            let element = host_type(&arg(0), structs, used);
+            match ty.args.get(1) {
+                Some(len) => {
+                    let len = tokens_string(len);
+                    let Ok(len) = len.trim_end_matches(['u', 'i']).parse::<usize>() else {
+                        abort!(span, "Expected a literal array length.")
+                    };
+                    quote! {[#element; #len]}
+                }
+                None => quote! {Vec<#element>},
+            }
+        }
+        _ if name.starts_with("vec") => {
+            let Some(n) = name[3..].chars().next().filter(|c| ('2'..='4').contains(c)) else {
+                abort!(span, "Expected `vec2`, `vec3` or `vec4`.")
+            };
+            let prefix = match scalar(name[4..].chars().next()).as_str() {
+                "f32" => "",
+                "i32" => "I",
+                "u32" => "U",
+                x => abort!(span, "Unsupported element type `{}`.", x),
+            };
+            let ident = format_ident!("{}Vec{}", prefix, n);
+            quote! {::bevy::math::#ident}
+        }
+        _ if name.starts_with("mat") => {
+            let dims = &name[3..6.min(name.len())];
+            let n = match dims {
+                "2x2" | "3x3" | "4x4" => &dims[..1],
+                _ => abort!(
+                    span,
+                    "Only square matrices are supported in `#![material(..)]`."
+                ),
+            };
+            if scalar(name[6..].chars().next()) != "f32" {
+                abort!(
+                    span,
+                    "Only `f32` matrices are supported in `#![material(..)]`."
+                )
+            }
+            let ident = format_ident!("Mat{}", n);
+            quote! {::bevy::math::#ident}
+        }
+        _ => {
+            if structs.iter().any(|(i, _)| i == &ty.name) && !used.contains(&ty.name) {
+                used.push(ty.name.clone());
+            }
+            let ident = &ty.name;
+            quote! {#ident}
+        }
+    }
+}
+
+/// Respan types, checks generated by `ShaderType` on user spans emit `dead_code` warnings.
+fn call_site(stream: TokenStream) -> TokenStream {
+    stream
+        .into_iter()
+        .map(|mut tt| {
+            if let TokenTree::Group(g) = &tt {
+                tt = TokenTree::Group(Group::new(g.delimiter(), call_site(g.stream())));
+            }
+            tt.set_span(Span::call_site());
+            tt
+        })
+        .collect()
+}
@@ -0,0 +1,100 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Names of the exports of the math library.
+pub const EXPORTS: &[&str] = &[
+    "PI",
+    "TAU",
+    "saturate",
+    "inverse_lerp",
+    "remap",
+    "rotate2d",
+    "rotate_x",
+    "rotate_y",
+    "rotate_z",
+    "quat_mul",
+    "quat_conj",
+    "quat_rotate",
+    "quat_from_axis_angle",
+];
+
+/// Source of an export of the math library, quaternions are `vec4<f32>` with `w` last.
+pub fn source(name: &str) -> Option<TokenStream> {
+    let source = match name {
+        "PI" => quote! {
+            const PI: f32 = 3.141592653589793;
+        },
+        "TAU" => quote! {
+            const TAU: f32 = 6.283185307179586;
+        },
+        "saturate" => quote! {
+            fn saturate(x: f32) -> f32 {
+                return clamp(x, 0.0, 1.0);
+            }
+        },
+        "inverse_lerp" => quote! {
+            fn inverse_lerp(a: f32, b: f32, x: f32) -> f32 {
+                return (x - a) / (b - a);
+            }
+        },
+        "remap" => quote! {
+            fn remap(x: f32, from_min: f32, from_max: f32, to_min: f32, to_max: f32) -> f32 {
+                return to_min + (x - from_min) / (from_max - from_min) * (to_max - to_min);
+            }
+        },
+        "rotate2d" => quote! {
+            fn rotate2d(angle: f32) -> mat2x2<f32> {
+                let c = cos(angle);
+                let s = sin(angle);
+                return mat2x2<f32>(c, s, -s, c);
+            }
+        },
+        "rotate_x" => quote! {
+            fn rotate_x(angle: f32) -> mat3x3<f32> {
+                let c = cos(angle);
+                let s = sin(angle);
+                return mat3x3<f32>(1.0, 0.0, 0.0, 0.0, c, s, 0.0, -s, c);
+            }
+        },
+        "rotate_y" => quote! {
+            fn rotate_y(angle: f32) -> mat3x3<f32> {
+                let c = cos(angle);
+                let s = sin(angle);
+                return mat3x3<f32>(c, 0.0, -s, 0.0, 1.0, 0.0, s, 0.0, c);
+            }
+        },
+        "rotate_z" => quote! {
+            fn rotate_z(angle: f32) -> mat3x3<f32> {
+                let c = cos(angle);
+                let s = sin(angle);
+                return mat3x3<f32>(c, s, 0.0, -s, c, 0.0, 0.0, 0.0, 1.0);
+            }
+        },
+        "quat_mul" => quote! {
+            fn quat_mul(a: vec4<f32>, b: vec4<f32>) -> vec4<f32> {
+                return vec4<f32>(
+                    a.w * b.xyz + b.w * a.xyz + cross(a.xyz, b.xyz),
+                    a.w * b.w - dot(a.xyz, b.xyz),
+                );
+            }
+        },
+        "quat_conj" => quote! {
+            fn quat_conj(q: vec4<f32>) -> vec4<f32> {
+                return vec4<f32>(-q.xyz, q.w);
+            }
+        },
+        "quat_rotate" => quote! {
+            fn quat_rotate(q: vec4<f32>, v: vec3<f32>) -> vec3<f32> {
+                let t = 2.0 * cross(q.xyz, v);
+                return v + q.w * t + cross(q.xyz, t);
+            }
+        },
+        "quat_from_axis_angle" => quote! {
+            fn quat_from_axis_angle(axis: vec3<f32>, angle: f32) -> vec4<f32> {
+                return vec4<f32>(normalize(axis) * sin(angle * 0.5), cos(angle * 0.5));
+            }
+        },
+        _ => return None,
+    };
+    Some(source)
+}
@@ -0,0 +1,172 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Names of the exports of the noise library.
+pub const EXPORTS: &[&str] = &[
+    "pcg_hash",
+    "pcg3d",
+    "hash12",
+    "hash13",
+    "hash33",
+    "value_noise_2d",
+    "value_noise_3d",
+    "noise_permute_3",
+    "noise_permute_4",
+    "simplex_noise_2d",
+    "simplex_noise_3d",
+];
+
+/// Source of an export of the noise library, simplex noise is Stefan Gustavson's, MIT licensed.
+pub fn source(name: &str) -> Option<TokenStream> {
+    let source = match name {
+        "pcg_hash" => quote! {
+            fn pcg_hash(v: u32) -> u32 {
+                let state = v * 747796405u + 2891336453u;
+                let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+                return (word >> 22u) ^ word;
+            }
+        },
+        "pcg3d" => quote! {
+            fn pcg3d(p: vec3<u32>) -> vec3<u32> {
+                var v = p * 1664525u + 1013904223u;
+                v.x += v.y * v.z;
+                v.y += v.z * v.x;
+                v.z += v.x * v.y;
+                v ^= v >> vec3<u32>(16u);
+                v.x += v.y * v.z;
+                v.y += v.z * v.x;
+                v.z += v.x * v.y;
+                return v;
+            }
+        },
+        "hash12" => quote! {
+            fn hash12(p: vec2<f32>) -> f32 {
+                var p3 = fract(p.xyx * 0.1031);
+                p3 += dot(p3, p3.yzx + 33.33);
+                return fract((p3.x + p3.y) * p3.z);
+            }
+        },
+        "hash13" => quote! {
+            fn hash13(p: vec3<f32>) -> f32 {
+                var p3 = fract(p * 0.1031);
+                p3 += dot(p3, p3.zyx + 31.32);
+                return fract((p3.x + p3.y) * p3.z);
+            }
+        },
+        "hash33" => quote! {
+            fn hash33(p: vec3<f32>) -> vec3<f32> {
+                var p3 = fract(p * vec3<f32>(0.1031, 0.1030, 0.0973));
+                p3 += dot(p3, p3.yxz + 33.33);
+                return fract((p3.xxy + p3.yxx) * p3.zyx);
+            }
+        },
+        "value_noise_2d" => quote! {
+            fn value_noise_2d(p: vec2<f32>) -> f32 {
+                let i = floor(p);
+                let f = fract(p);
+                let u = f * f * (3.0 - 2.0 * f);
+                let a = $wgsl_ln::hash12(i);
+                let b = hash12(i + vec2<f32>(1.0, 0.0));
+                let c = hash12(i + vec2<f32>(0.0, 1.0));
+                let d = hash12(i + vec2<f32>(1.0, 1.0));
+                return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+            }
+        },
+        "value_noise_3d" => quote! {
+            fn value_noise_3d(p: vec3<f32>) -> f32 {
+                let i = floor(p);
+                let f = fract(p);
+                let u = f * f * (3.0 - 2.0 * f);
+                let a = mix($wgsl_ln::hash13(i), hash13(i + vec3<f32>(1.0, 0.0, 0.0)), u.x);
+                let b = mix(hash13(i + vec3<f32>(0.0, 1.0, 0.0)), hash13(i + vec3<f32>(1.0, 1.0, 0.0)), u.x);
+                let c = mix(hash13(i + vec3<f32>(0.0, 0.0, 1.0)), hash13(i + vec3<f32>(1.0, 0.0, 1.0)), u.x);
+                let d = mix(hash13(i + vec3<f32>(0.0, 1.0, 1.0)), hash13(i + vec3<f32>(1.0, 1.0, 1.0)), u.x);
+                return mix(mix(a, b, u.y), mix(c, d, u.y), u.z);
+            }
+        },
+        "noise_permute_3" => quote! {
+            fn noise_permute_3(x: vec3<f32>) -> vec3<f32> {
+                let y = (x * 34.0 + 1.0) * x;
+                return y - floor(y * (1.0 / 289.0)) * 289.0;
+            }
+        },
+        "noise_permute_4" => quote! {
+            fn noise_permute_4(x: vec4<f32>) -> vec4<f32> {
+                let y = (x * 34.0 + 1.0) * x;
+                return y - floor(y * (1.0 / 289.0)) * 289.0;
+            }
+        },
+        "simplex_noise_2d" => quote! {
+            fn simplex_noise_2d(v: vec2<f32>) -> f32 {
+                let C = vec4<f32>(0.211324865405187, 0.366025403784439, -0.577350269189626, 0.024390243902439);
+                var i = floor(v + dot(v, C.yy));
+                let x0 = v - i + dot(i, C.xx);
+                let i1 = select(vec2<f32>(0.0, 1.0), vec2<f32>(1.0, 0.0), x0.x > x0.y);
+                let x12 = x0.xyxy + C.xxzz - vec4<f32>(i1, 0.0, 0.0);
+                i -= floor(i * (1.0 / 289.0)) * 289.0;
+                let p = $wgsl_ln::noise_permute_3(
+                    noise_permute_3(i.y + vec3<f32>(0.0, i1.y, 1.0)) + i.x + vec3<f32>(0.0, i1.x, 1.0),
+                );
+                var m = max(0.5 - vec3<f32>(dot(x0, x0), dot(x12.xy, x12.xy), dot(x12.zw, x12.zw)), vec3<f32>(0.0));
+                m *= m;
+                m *= m;
+                let x = 2.0 * fract(p * C.www) - 1.0;
+                let h = abs(x) - 0.5;
+                let a0 = x - floor(x + 0.5);
+                m *= 1.79284291400159 - 0.85373472095314 * (a0 * a0 + h * h);
+                let g = vec3<f32>(a0.x * x0.x + h.x * x0.y, a0.yz * x12.xz + h.yz * x12.yw);
+                return 130.0 * dot(m, g);
+            }
+        },
+        "simplex_noise_3d" => quote! {
+            fn simplex_noise_3d(v: vec3<f32>) -> f32 {
+                let C = vec2<f32>(1.0 / 6.0, 1.0 / 3.0);
+                let D = vec4<f32>(0.0, 0.5, 1.0, 2.0);
+                var i = floor(v + dot(v, C.yyy));
+                let x0 = v - i + dot(i, C.xxx);
+                let g = step(x0.yzx, x0.xyz);
+                let l = 1.0 - g;
+                let i1 = min(g.xyz, l.zxy);
+                let i2 = max(g.xyz, l.zxy);
+                let x1 = x0 - i1 + C.xxx;
+                let x2 = x0 - i2 + C.yyy;
+                let x3 = x0 - D.yyy;
+                i -= floor(i * (1.0 / 289.0)) * 289.0;
+                let p = $wgsl_ln::noise_permute_4(
+                    noise_permute_4(
+                        noise_permute_4(i.z + vec4<f32>(0.0, i1.z, i2.z, 1.0))
+                            + i.y + vec4<f32>(0.0, i1.y, i2.y, 1.0),
+                    ) + i.x + vec4<f32>(0.0, i1.x, i2.x, 1.0),
+                );
+                let ns = 0.142857142857 * D.wyz - D.xzx;
+                let j = p - 49.0 * floor(p * ns.z * ns.z);
+                let gx = floor(j * ns.z);
+                let gy = floor(j - 7.0 * gx);
+                let x = gx * ns.x + ns.yyyy;
+                let y = gy * ns.x + ns.yyyy;
+                let h = 1.0 - abs(x) - abs(y);
+                let b0 = vec4<f32>(x.xy, y.xy);
+                let b1 = vec4<f32>(x.zw, y.zw);
+                let s0 = floor(b0) * 2.0 + 1.0;
+                let s1 = floor(b1) * 2.0 + 1.0;
+                let sh = -step(h, vec4<f32>(0.0));
+                let a0 = b0.xzyw + s0.xzyw * sh.xxyy;
+                let a1 = b1.xzyw + s1.xzyw * sh.zzww;
+                var p0 = vec3<f32>(a0.xy, h.x);
+                var p1 = vec3<f32>(a0.zw, h.y);
+                var p2 = vec3<f32>(a1.xy, h.z);
+                var p3 = vec3<f32>(a1.zw, h.w);
+                let norm = 1.79284291400159 - 0.85373472095314 * vec4<f32>(dot(p0, p0), dot(p1, p1), dot(p2, p2), dot(p3, p3));
+                p0 *= norm.x;
+                p1 *= norm.y;
+                p2 *= norm.z;
+                p3 *= norm.w;
+                var m = max(0.6 - vec4<f32>(dot(x0, x0), dot(x1, x1), dot(x2, x2), dot(x3, x3)), vec4<f32>(0.0));
+                m *= m;
+                return 42.0 * dot(m * m, vec4<f32>(dot(p0, x0), dot(p1, x1), dot(p2, x2), dot(p3, x3)));
+            }
+        },
+        _ => return None,
+    };
+    Some(source)
+}
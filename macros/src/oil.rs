@@ -0,0 +1,416 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use naga::{
+    valid::{Capabilities, ShaderStages},
+    Module,
+};
+use naga_oil::compose::{
+    ComposableModuleDescriptor, Composer, ComposerError, ComposerErrorInner, ErrSource,
+    NagaModuleDescriptor, ShaderDefValue,
+};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{quote, quote_spanned};
+
+use crate::{
+    items::split_pasted,
+    options::{extract_options, Options, Stub},
+    sanitize::is_naga_oil_name,
+    to_wgsl_string::to_wgsl_string,
+    wgsl2::{span_at, validate},
+};
+
+/// Maximum number of shader defs in `#![permutations]`.
+const MAX_PERMUTATION_DEFS: usize = 10;
+
+/// Validate a `naga_oil` shader by composing it with `naga_oil`'s [`Composer`].
+///
+/// Modules in `#![modules(..)]` are registered as composable modules,
+/// either read from a file or pasted in from a `#[wgsl_export]` with `#define_import_path`,
+/// stubs in `#![stub(..)]` are registered as generated modules.
+///
+/// With `#![permutations]`, every combination of shader defs is validated.
+///
+/// If an `#import` cannot be resolved, only items pasted in via `$item` are validated.
+///
+/// Returns `include_str!`s of module files and the composed module, if any.
+pub fn validate_oil(
+    options: &Options,
+    defs: &[String],
+    defined: &[Ident],
+    stream: &TokenStream,
+    externs: &TokenStream,
+    source: &str,
+    spans: &[(usize, Span)],
+) -> (TokenStream, Option<Module>) {
+    let mut composer =
+        Composer::default().with_capabilities(Capabilities::all(), ShaderStages::all());
+    let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let mut files = Vec::new();
+    let mut modules = Vec::new();
+    for (path, span) in &options.modules {
+        let file = PathBuf::from(&root).join(path);
+        let Ok(module) = std::fs::read_to_string(&file) else {
+            abort!(span, "Unable to read module \"{}\".", file.display())
+        };
+        files.push(file.display().to_string());
+        modules.push((path.clone(), *span, module));
+    }
+    for (path, stream) in &options.module_sources {
+        let (stream, _) = extract_options(stream.clone());
+        let mut module = String::new();
+        to_wgsl_string(stream, &mut Vec::new(), &mut module);
+        modules.push((path.clone(), Span::call_site(), module));
+    }
+    modules.extend(stub_modules(&options.stubs));
+    // Modules must be added after their dependencies.
+    while !modules.is_empty() {
+        let len = modules.len();
+        let mut pending = Vec::new();
+        for (path, span, module) in modules {
+            match add_module(&mut composer, &path, &module) {
+                Ok(()) => (),
+                Err(ComposerError {
+                    inner: ComposerErrorInner::ImportNotFound(..),
+                    ..
+                }) => pending.push((path, span, module)),
+                Err(e) => abort!(span, "Wgsl Error: {}", describe(&e, &composer)),
+            }
+        }
+        // No progress, report the missing import.
+        if pending.len() == len {
+            let (path, span, module) = &pending[0];
+            if let Err(e) = add_module(&mut composer, path, module) {
+                abort!(span, "Wgsl Error: {}", describe(&e, &composer))
+            }
+        }
+        modules = pending;
+    }
+    let mut values = HashMap::new();
+    for (name, value) in options.defs.iter().flatten() {
+        if let Some(value) = value {
+            values.insert(name.to_string(), def_value(name, value));
+        }
+    }
+    let permutations = if has_commented_path(stream.clone()) {
+        // `naga_oil` reads `//` in `#import "embedded://.."` as a comment, validate what we can.
+        validate_pasted(options, defined, stream, externs);
+        Vec::new()
+    } else if options.permutations {
+        let defs: Vec<_> = defs
+            .iter()
+            .filter(|x| !values.contains_key(*x))
+            .cloned()
+            .collect();
+        permutations(&defs)
+    } else {
+        vec![HashMap::new()]
+    };
+    let mut validated = None;
+    for mut shader_defs in permutations {
+        let mut enabled: Vec<_> = shader_defs.keys().cloned().collect();
+        enabled.sort();
+        shader_defs.extend(values.clone());
+        let result = composer.make_naga_module(NagaModuleDescriptor {
+            source,
+            file_path: "wgsl!",
+            shader_defs,
+            ..Default::default()
+        });
+        let e = match result {
+            Ok(module) => {
+                validated.get_or_insert(module);
+                continue;
+            }
+            Err(e) => e,
+        };
+        // Imports are provided at runtime, validate what we can.
+        if let ComposerErrorInner::ImportNotFound(..) = e.inner {
+            validate_pasted(options, defined, stream, externs);
+            break;
+        }
+        let message = match options.permutations {
+            true => format!("Wgsl Error (defs: [{}]): ", enabled.join(", ")),
+            false => "Wgsl Error: ".to_owned(),
+        };
+        match error_position(&e) {
+            Some(pos) => abort!(span_at(spans, pos), "{}{}", message, e.inner),
+            None => abort!(Span::call_site(), "{}{}", message, describe(&e, &composer)),
+        }
+    }
+    let includes = quote! {#(const _: &str = include_str!(#files);)*};
+    (includes, validated)
+}
+
+/// Validate items pasted in via `$item` with `naga`,
+/// names provided by `#import` must be declared with `#![stub(..)]` or `$extern`.
+///
+/// Items containing `naga_oil` directives are skipped.
+fn validate_pasted(
+    options: &Options,
+    defined: &[Ident],
+    stream: &TokenStream,
+    externs: &TokenStream,
+) {
+    let mut pasted = TokenStream::new();
+    for stub in &options.stubs {
+        pasted.extend(stub_decl(stub));
+    }
+    for (_, item) in split_pasted(stream.clone(), defined).1 {
+        if !has_directive(item.clone()) {
+            pasted.extend(item);
+        }
+    }
+    pasted.extend(externs.clone());
+    let mut spans = Vec::new();
+    let mut source = String::new();
+    to_wgsl_string(pasted, &mut spans, &mut source);
+    let offset = match naga::front::wgsl::parse_str(&source) {
+        Ok(module) => match validate(&module) {
+            Ok(_) => return,
+            Err(e) => e
+                .spans()
+                .next()
+                .map(|(span, _)| span.location(&source).offset as usize)
+                .map(|x| (x, e.to_string())),
+        },
+        Err(e) => e
+            .labels()
+            .next()
+            .map(|(span, _)| span.location(&source).offset as usize)
+            .map(|x| (x, e.to_string())),
+    };
+    if let Some((offset, e)) = offset {
+        abort!(span_at(&spans, offset), "Wgsl Error: {}", e)
+    }
+}
+
+/// Check for `#import "a//b"`, `naga_oil` cannot compose these.
+fn has_commented_path(stream: TokenStream) -> bool {
+    let mut iter = stream.into_iter();
+    while let Some(tt) = iter.next() {
+        if matches!(&tt, TokenTree::Ident(i) if i == "import") {
+            if let Some(TokenTree::Literal(lit)) = iter.next() {
+                let lit = lit.to_string();
+                if lit.contains("//") || lit.contains("/*") {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Check if a stream contains a `naga_oil` directive like `#ifdef`, other uses of `#` are not `naga_oil`.
+fn has_directive(stream: TokenStream) -> bool {
+    let mut last_is_hash = false;
+    stream.into_iter().any(|tt| {
+        let found = match &tt {
+            TokenTree::Ident(i) => last_is_hash && is_naga_oil_name(i),
+            TokenTree::Group(g) => has_directive(g.stream()),
+            _ => false,
+        };
+        last_is_hash = matches!(&tt, TokenTree::Punct(p) if p.as_char() == '#');
+        found
+    })
+}
+
+#[allow(clippy::result_large_err)]
+fn add_module(composer: &mut Composer, path: &str, source: &str) -> Result<(), ComposerError> {
+    composer.add_composable_module(ComposableModuleDescriptor {
+        source,
+        file_path: path,
+        ..Default::default()
+    })?;
+    Ok(())
+}
+
+/// Check names in `#ifdef`, `#ifndef` and `#if` against `#![defs(..)]`,
+/// and collect names used in `#ifdef` and `#ifndef`.
+pub fn check_defs(options: &Options, stream: TokenStream, used: &mut Vec<String>) {
+    let mut iter = stream.into_iter().peekable();
+    // After `#` or `#else`.
+    let mut last_is_hash = false;
+    while let Some(tt) = iter.next() {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == '#' => {
+                last_is_hash = true;
+                continue;
+            }
+            TokenTree::Ident(i) if last_is_hash && i == "else" => continue,
+            TokenTree::Ident(i) if last_is_hash && (i == "ifdef" || i == "ifndef" || i == "if") => {
+                if let Some(TokenTree::Ident(def)) = iter.peek() {
+                    if let Some(defs) = &options.defs {
+                        if !defs.iter().any(|(x, _)| x == def) {
+                            abort!(
+                                def.span(),
+                                "Shader def `{}` is not declared in `#![defs(..)]`.",
+                                def
+                            )
+                        }
+                    }
+                    let def = def.to_string();
+                    if i != "if" && !used.contains(&def) {
+                        used.push(def);
+                    }
+                }
+            }
+            TokenTree::Group(g) => check_defs(options, g.stream(), used),
+            _ => (),
+        }
+        last_is_hash = false;
+    }
+}
+
+/// Parse `true`, `false`, `1` or `1u` as a [`ShaderDefValue`].
+fn def_value(name: &Ident, value: &str) -> ShaderDefValue {
+    if let Ok(b) = value.parse() {
+        ShaderDefValue::Bool(b)
+    } else if let Ok(i) = value.parse() {
+        ShaderDefValue::Int(i)
+    } else if let Some(Ok(u)) = value.strip_suffix('u').map(str::parse) {
+        ShaderDefValue::UInt(u)
+    } else {
+        abort!(
+            name.span(),
+            "Expected `true`, `false`, an `i32` or an `u32` as the value of `{}`.",
+            name
+        )
+    }
+}
+
+/// Every combination of enabled shader defs.
+fn permutations(defs: &[String]) -> Vec<HashMap<String, ShaderDefValue>> {
+    if defs.len() > MAX_PERMUTATION_DEFS {
+        abort!(
+            Span::call_site(),
+            "Too many shader defs to validate every permutation, found {}, maximum is {}.",
+            defs.len(),
+            MAX_PERMUTATION_DEFS
+        )
+    }
+    (0..1u32 << defs.len())
+        .map(|mask| {
+            defs.iter()
+                .enumerate()
+                .filter(|(idx, _)| mask & (1 << idx) != 0)
+                .map(|(_, def)| (def.clone(), ShaderDefValue::Bool(true)))
+                .collect()
+        })
+        .collect()
+}
+
+/// Generate a module for each module path in `#![stub(..)]`.
+fn stub_modules(stubs: &[Stub]) -> Vec<(String, Span, String)> {
+    let mut modules: BTreeMap<&str, Vec<&Stub>> = BTreeMap::new();
+    for stub in stubs {
+        modules.entry(&stub.module).or_default().push(stub);
+    }
+    let mut result = Vec::new();
+    for (module, items) in &modules {
+        let mut source = format!("#define_import_path {}\n", module);
+        let mut decls = TokenStream::new();
+        for stub in items {
+            decls.extend(stub_decl(stub));
+        }
+        // Import stubs from other modules this module refers to.
+        for stub in stubs {
+            if stub.module != *module && mentions(decls.clone(), &stub.name.to_string()) {
+                source.push_str(&format!("#import {}::{}\n", stub.module, stub.name));
+            }
+        }
+        to_wgsl_string(decls, &mut Vec::new(), &mut source);
+        result.push((module.to_string(), items[0].name.span(), source));
+    }
+    result
+}
+
+/// Convert `fn() -> f32`, `struct {..}`, `var: T` or `const: T` into a declaration.
+fn stub_decl(stub: &Stub) -> TokenStream {
+    let name = &stub.name;
+    let Some(TokenTree::Ident(kind)) = stub.decl.first() else {
+        abort!(name.span(), "Expected `fn`, `struct`, `var` or `const`.")
+    };
+    let rest = TokenStream::from_iter(stub.decl[1..].iter().cloned());
+    let ty = || {
+        let mut iter = stub.decl[1..].iter();
+        match iter.next() {
+            Some(TokenTree::Punct(p)) if p.as_char() == ':' => {
+                TokenStream::from_iter(iter.cloned())
+            }
+            _ => abort!(kind.span(), "Expected `{}: type`.", kind),
+        }
+    };
+    match kind.to_string().as_str() {
+        "fn" => {
+            let arrow = stub
+                .decl
+                .iter()
+                .position(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == '>'));
+            let body = match arrow {
+                Some(idx) => {
+                    let ret = TokenStream::from_iter(stub.decl[idx + 1..].iter().cloned());
+                    quote_spanned!(name.span()=> return #ret ();)
+                }
+                None => TokenStream::new(),
+            };
+            quote_spanned!(name.span()=> fn #name #rest {#body})
+        }
+        "struct" => quote_spanned!(name.span()=> struct #name #rest),
+        "var" => {
+            let ty = ty();
+            quote_spanned!(name.span()=> var<private> #name: #ty;)
+        }
+        "const" => {
+            let ty = ty();
+            quote_spanned!(name.span()=> const #name: #ty = #ty();)
+        }
+        _ => abort!(kind.span(), "Expected `fn`, `struct`, `var` or `const`."),
+    }
+}
+
+/// Check if an identifier is used in a stream.
+fn mentions(stream: TokenStream, name: &str) -> bool {
+    stream.into_iter().any(|tt| match tt {
+        TokenTree::Ident(i) => i == name,
+        TokenTree::Group(g) => mentions(g.stream(), name),
+        _ => false,
+    })
+}
+
+/// Format an error without `codespan_reporting`'s terminal colors.
+fn describe(e: &ComposerError, composer: &Composer) -> String {
+    format!("{}: {}", e.source.path(composer), e.inner)
+}
+
+/// Find the byte offset of the error in the top level shader, if applicable.
+fn error_position(e: &ComposerError) -> Option<usize> {
+    let ErrSource::Constructing { offset, .. } = &e.source else {
+        return None;
+    };
+    let map = |x: usize| (x & ((1 << 21) - 1)).saturating_sub(*offset);
+    match &e.inner {
+        ComposerErrorInner::ImportNotFound(_, pos)
+        | ComposerErrorInner::ImportParseError(_, pos)
+        | ComposerErrorInner::NotEnoughEndIfs(pos)
+        | ComposerErrorInner::TooManyEndIfs(pos)
+        | ComposerErrorInner::ElseWithoutCondition(pos)
+        | ComposerErrorInner::UnknownShaderDef { pos, .. }
+        | ComposerErrorInner::UnknownShaderDefOperator { pos, .. }
+        | ComposerErrorInner::InvalidShaderDefComparisonValue { pos, .. }
+        | ComposerErrorInner::DefineInModule(pos)
+        | ComposerErrorInner::InvalidShaderDefDefinitionValue { pos, .. } => Some(*pos),
+        ComposerErrorInner::WgslParseError(e) => {
+            let (span, _) = e.labels().next()?;
+            Some(map(span.to_range()?.start))
+        }
+        ComposerErrorInner::ShaderValidationError(e) => {
+            let (span, _) = e.spans().next()?;
+            Some(map(span.to_range()?.start))
+        }
+        _ => None,
+    }
+}
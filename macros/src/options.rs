@@ -0,0 +1,754 @@
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+
+use crate::{config::Config, consts::RustConsts, level::Level};
+
+/// Options of the shader itself, removed from the source of an export so shaders pasting it
+/// don't inherit them.
+pub const LOCAL_OPTIONS: &[&str] = &[
+    "wesl",
+    "embedded_asset",
+    "material",
+    "wgpu",
+    "shader",
+    "hot_reload",
+    "out_dir",
+    "label",
+    "dump",
+    "stats",
+    "export",
+    "doc_item",
+    "validate",
+    "portability",
+    "rename",
+    "entry_prefix",
+    "obfuscate",
+    "canonical_order",
+    "naga_ir",
+];
+
+/// Options specified via inner attributes, i.e. `#![modules("utils.wgsl")]`.
+#[derive(Debug, Default)]
+pub struct Options {
+    /// `naga_oil` composable modules, relative to `CARGO_MANIFEST_DIR`.
+    pub modules: Vec<(String, Span)>,
+    /// `naga_oil` composable modules exported via `#[wgsl_export]`, i.e. `my_crate::utils`.
+    pub module_paths: Vec<Vec<Ident>>,
+    /// Sources of exported modules, pasted in by `__wgsl_module_*` macros.
+    pub module_sources: Vec<(String, TokenStream)>,
+    /// Stubs for items imported via `#import`.
+    pub stubs: Vec<Stub>,
+    /// Shader defs allowed in `#ifdef`, `#ifndef` and `#if`, with optional values,
+    /// i.e. `#![defs(SHADOWS, MSAA_SAMPLES = 4)]`.
+    pub defs: Option<Vec<(Ident, Option<String>)>>,
+    /// `naga_oil` mode, enabled by `#![oil]` or `wgsl_oil!`.
+    pub oil: bool,
+    /// Validate every combination of shader defs used in `#ifdef` and `#ifndef`.
+    pub permutations: bool,
+    /// Emit a WESL module, importing pasted items from the package instead of inlining them.
+    pub wesl: bool,
+    /// Write the output to this path in `OUT_DIR`, i.e. `#![embedded_asset("lighting.wgsl")]`.
+    pub embedded_asset: Option<(String, Span)>,
+    /// Write the output to this path relative to `CARGO_MANIFEST_DIR` and load it at runtime
+    /// in debug builds, i.e. `#![hot_reload("shaders/lighting.wgsl")]`.
+    pub hot_reload: Option<(String, Span)>,
+    /// Write the output to this path in `OUT_DIR` and `include_str!` it instead of a string literal,
+    /// i.e. `#![out_dir("lighting.wgsl")]`, empty for a name derived from the content hash.
+    pub out_dir: Option<(String, Span)>,
+    /// Generate an `AsBindGroup` struct from `@group(2)` for exports, i.e. `#![material(FogMaterial)]`.
+    pub material: Option<Ident>,
+    /// Emit a `wgpu::ShaderModuleDescriptor` instead of a string.
+    pub wgpu: bool,
+    /// Emit a `wgsl_ln_runtime::Shader` with a content hash instead of a string.
+    pub shader: bool,
+    /// Label of the `wgpu::ShaderModuleDescriptor`, `Shader` or reflection file,
+    /// i.e. `#![label("lighting")]` or `#![wgpu("lighting")]`.
+    pub label: Option<String>,
+    /// `file:line:column` of the outermost `wgsl!`, added with the `manifest` or `analyzer` feature
+    /// since pasting changes `Span::call_site`.
+    pub call_site: Option<String>,
+    /// Emit an item with the output in its documentation, used by `#[wgsl_export]`
+    /// under `cfg(doc)`, i.e. `#![doc_item(pub static NAME: &str =)]`.
+    pub doc_item: Option<TokenStream>,
+    /// Show the output as a warning at compile time, i.e. `#![dump]`.
+    pub dump: Option<Span>,
+    /// Show function, global and instruction counts as a warning at compile time, i.e. `#![stats]`.
+    pub stats: Option<Span>,
+    /// Warn if the output changes meaning after a round trip through naga's WGSL writer,
+    /// i.e. `#![round_trip]`.
+    pub round_trip: Option<Span>,
+    /// Run `cases` in compute shaders instead of emitting the output, added by `wgsl_test!`.
+    pub gpu_test: Option<Span>,
+    /// Emit the value of the constant `wgsl_ln_eval` instead of the output, added by `wgsl_eval!`.
+    pub eval: Option<Span>,
+    /// Test cases of `wgsl_test!`, i.e. `#![case(distance(1.0, 2.0) == 1.0)]`.
+    pub cases: Vec<Case>,
+    /// Property test of `wgsl_test!` added by `#[wgsl_property]`,
+    /// i.e. `#![property(distance, reference = distance, samples = 256)]`.
+    pub property: Option<Property>,
+    /// Validation level of this shader, i.e. `#![validate(strict)]`.
+    pub validate: Option<Level>,
+    /// Warn about what browsers do not support, i.e. `#![portability(browser)]`.
+    pub portability: Option<Span>,
+    /// Entry points renamed in the output, i.e. `#![rename(vs_main = sprite_vs_main)]`.
+    pub renames: Vec<(Ident, Ident)>,
+    /// Prefix of entry points not in `renames`, i.e. `#![entry_prefix(sprite_)]`.
+    pub entry_prefix: Option<Ident>,
+    /// Rename identifiers to short names in release builds, i.e. `#![obfuscate]`.
+    pub obfuscate: Option<Span>,
+    /// Order the output as directives, types, globals and functions, i.e. `#![canonical_order]`.
+    pub canonical_order: Option<Span>,
+    /// Crates defining pasted items, added by `__wgsl_paste_*`, i.e. `#![origin(hash, noise)]`.
+    pub origins: Vec<(Ident, Ident)>,
+    /// Prefix pasted items with the name of their crate, i.e. `#![namespaced]`.
+    pub namespaced: Option<Span>,
+    /// Write the validated naga module to `OUT_DIR`, i.e. `#![naga_ir]`.
+    pub naga_ir: Option<Span>,
+    /// Rust constants in `@workgroup_size` and `override` defaults, i.e. `@workgroup_size(${WG_X})`,
+    /// found after options are parsed.
+    pub rust_consts: RustConsts,
+    /// Names and sources of the items pasted into a `#![hot_reload(..)]` shader,
+    /// found when its file is written.
+    pub pasted: Vec<(String, String)>,
+}
+
+impl Options {
+    /// Returns true if the naga module is used after validation, by reflection,
+    /// statistics or `cargo wgsl-ln`, so parsing cannot be skipped.
+    pub fn needs_module(&self) -> bool {
+        self.shader
+            || self.stats.is_some()
+            || self.round_trip_enabled()
+            || self.gpu_test.is_some()
+            || self.eval.is_some()
+            || self.naga_ir.is_some()
+            || self.portability_enabled()
+            || !Config::get().passes.is_empty()
+            || cfg!(feature = "json")
+            || std::env::var_os("WGSL_LN_STATS").is_some()
+            || std::env::var_os("WGSL_LN_DUMP").is_some()
+    }
+
+    /// Returns true if `#![round_trip]`, `WGSL_LN_ROUND_TRIP` or `round_trip = true` in
+    /// `wgsl_ln.toml` is set with the `round_trip` feature.
+    pub fn round_trip_enabled(&self) -> bool {
+        cfg!(feature = "round_trip")
+            && (self.round_trip.is_some()
+                || std::env::var_os("WGSL_LN_ROUND_TRIP").is_some()
+                || Config::get().round_trip)
+    }
+
+    /// Returns true if `#![portability(browser)]` or `portability = "browser"` in `wgsl_ln.toml` is set.
+    pub fn portability_enabled(&self) -> bool {
+        self.portability.is_some() || Config::get().portability.is_some()
+    }
+
+    /// Returns true if `#![obfuscate]` or `obfuscate = true` in `wgsl_ln.toml` is set
+    /// in a release build, shaders in `naga_oil` or WESL mode are only obfuscated by the option.
+    pub fn obfuscate_enabled(&self) -> bool {
+        !cfg!(debug_assertions)
+            && self.eval.is_none()
+            && (self.obfuscate.is_some() || (!self.oil && !self.wesl && Config::get().obfuscate))
+    }
+
+    /// Returns true if `#![canonical_order]` or `canonical_order = true` in `wgsl_ln.toml` is set,
+    /// shaders in `naga_oil` mode are not reordered.
+    pub fn canonical_order_enabled(&self) -> bool {
+        !self.oil && (self.canonical_order.is_some() || Config::get().canonical_order)
+    }
+
+    /// Returns the first exported module whose source has not been pasted in.
+    pub fn unresolved_module(&self) -> Option<&[Ident]> {
+        self.module_paths
+            .iter()
+            .find(|path| {
+                let name = join_path(path);
+                !self.module_sources.iter().any(|(n, _)| n == &name)
+            })
+            .map(|x| x.as_slice())
+    }
+}
+
+/// A stub for an item imported via `#import`, i.e. `#![stub(module::item as fn() -> f32)]`.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "naga_oil"), allow(dead_code))]
+pub struct Stub {
+    /// Import path of the module, i.e. `bevy_pbr::pbr_functions` or `"embedded://shader.wgsl"`.
+    pub module: String,
+    pub name: Ident,
+    /// Declaration without the name, i.e. `fn() -> f32` or `struct {..}`.
+    pub decl: Vec<TokenTree>,
+}
+
+/// A test case of `wgsl_test!`, i.e. `#![case(distance([0.0, 0.0], [1.0, 0.0]) == 1.0)]`.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "gpu_test"), allow(dead_code))]
+pub struct Case {
+    pub function: Ident,
+    /// Rust expressions of the arguments.
+    pub args: Vec<TokenStream>,
+    /// Rust expression of the expected result.
+    pub expected: TokenStream,
+}
+
+/// A property test of `wgsl_test!`, comparing a function to a Rust reference over generated inputs.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "gpu_test"), allow(dead_code))]
+pub struct Property {
+    pub function: Ident,
+    /// Rust expressions of the reference, the number of samples, the relative tolerance,
+    /// the range of generated values and the seed.
+    pub reference: TokenStream,
+    pub samples: TokenStream,
+    pub tolerance: TokenStream,
+    pub range: TokenStream,
+    pub seed: TokenStream,
+}
+
+/// Remove top level `#![..]` inner attributes and parse them as [`Options`].
+pub fn extract_options(stream: TokenStream) -> (TokenStream, Options) {
+    let mut options = Options::default();
+    let mut result: Vec<TokenTree> = Vec::new();
+    let mut iter = stream.into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        match &tt {
+            TokenTree::Punct(p) if p.as_char() == '#' => {
+                let is_inner =
+                    matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '!');
+                if !is_inner {
+                    result.push(tt);
+                    continue;
+                }
+                let bang = iter.next().unwrap();
+                match iter.next() {
+                    Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Bracket => {
+                        parse_attribute(g, &mut options);
+                    }
+                    _ => abort!(bang.span(), "Expected `#![..]`."),
+                }
+            }
+            _ => result.push(tt),
+        }
+    }
+    (TokenStream::from_iter(result), options)
+}
+
+/// Expand `#![options(a, b(..))]` to `#![a] #![b(..)]`, before other options are looked up.
+pub fn flatten_options(stream: TokenStream) -> TokenStream {
+    if !has_option(&stream, "options") {
+        return stream;
+    }
+    let mut result: Vec<TokenTree> = Vec::new();
+    for tt in stream {
+        let TokenTree::Group(g) = &tt else {
+            result.push(tt);
+            continue;
+        };
+        let is_inner = matches!(result.as_slice(), [.., TokenTree::Punct(h), TokenTree::Punct(b)]
+            if h.as_char() == '#' && b.as_char() == '!');
+        let mut iter = g.stream().into_iter();
+        let args = match (iter.next(), iter.next(), iter.next()) {
+            (Some(TokenTree::Ident(i)), Some(TokenTree::Group(args)), None)
+                if is_inner && g.delimiter() == Delimiter::Bracket && i == "options" =>
+            {
+                args
+            }
+            (Some(TokenTree::Ident(i)), _, _) if is_inner && i == "options" => {
+                abort!(g.span(), "Expected `#![options(a, b(..))]`.")
+            }
+            _ => {
+                result.push(tt);
+                continue;
+            }
+        };
+        result.truncate(result.len() - 2);
+        let mut option: Vec<TokenTree> = Vec::new();
+        for tt in args
+            .stream()
+            .into_iter()
+            .chain([Punct::new(',', Spacing::Alone).into()])
+        {
+            match tt {
+                TokenTree::Punct(p) if p.as_char() == ',' => {
+                    if !option.is_empty() {
+                        let option = TokenStream::from_iter(option.drain(..));
+                        result.extend(quote::quote!(#![#option]));
+                    }
+                }
+                tt => option.push(tt),
+            }
+        }
+    }
+    TokenStream::from_iter(result)
+}
+
+/// Crate of `$krate::name` from `#![paste_from(name, krate)]`, before options are parsed.
+pub fn paste_from(stream: &TokenStream, name: &Ident) -> Option<Ident> {
+    let mut last_is_bang = false;
+    for tt in stream.clone() {
+        if let (true, TokenTree::Group(g)) = (last_is_bang, &tt) {
+            let tokens = Vec::from_iter(g.stream());
+            if let [TokenTree::Ident(option), TokenTree::Group(args)] = tokens.as_slice() {
+                if let [TokenTree::Ident(item), TokenTree::Punct(_), TokenTree::Ident(krate)] =
+                    Vec::from_iter(args.stream()).as_slice()
+                {
+                    if option == "paste_from" && item == name {
+                        return Some(krate.clone());
+                    }
+                }
+            }
+        }
+        last_is_bang = matches!(&tt, TokenTree::Punct(p) if p.as_char() == '!');
+    }
+    None
+}
+
+/// Check for `#![oil]` before options are parsed.
+pub fn is_oil(stream: &TokenStream) -> bool {
+    has_option(stream, "oil")
+}
+
+/// Check for an option before options are parsed.
+pub fn has_option(stream: &TokenStream, name: &str) -> bool {
+    let mut last_is_hash = false;
+    let mut last_is_bang = false;
+    for tt in stream.clone() {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == '#' => {
+                last_is_hash = true;
+                continue;
+            }
+            TokenTree::Punct(p) if last_is_hash && p.as_char() == '!' => {
+                last_is_bang = true;
+                last_is_hash = false;
+                continue;
+            }
+            TokenTree::Group(g) if last_is_bang && g.delimiter() == Delimiter::Bracket => {
+                if matches!(g.stream().into_iter().next(), Some(TokenTree::Ident(i)) if i == name) {
+                    return true;
+                }
+            }
+            _ => (),
+        }
+        last_is_hash = false;
+        last_is_bang = false;
+    }
+    false
+}
+
+/// Add `#![call_site(..)]` to the outermost `wgsl!`, for the `manifest` and `analyzer` features.
+#[cfg(any(feature = "manifest", feature = "analyzer"))]
+pub fn with_call_site(stream: TokenStream) -> TokenStream {
+    if has_option(&stream, "call_site") {
+        return stream;
+    }
+    let span = Span::call_site();
+    let start = span.start();
+    // `/` on every platform, so the manifest does not depend on where it was built.
+    let file = span.file().replace('\\', "/");
+    let location = format!("{}:{}:{}", file, start.line, start.column + 1);
+    quote::quote! {#![call_site(#location)] #stream}
+}
+
+fn parse_attribute(group: Group, options: &mut Options) {
+    let mut iter = group.stream().into_iter();
+    let Some(TokenTree::Ident(name)) = iter.next() else {
+        abort!(group.span(), "Expected `#![name(..)]`.")
+    };
+    let args = match iter.next() {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => g.stream(),
+        None => TokenStream::new(),
+        Some(tt) => abort!(tt.span(), "Expected `(..)`."),
+    };
+    match name.to_string().as_str() {
+        "modules" => parse_modules(args, options),
+        "module_source" => {
+            let mut path = Vec::new();
+            for tt in args {
+                match tt {
+                    TokenTree::Ident(i) => path.push(i),
+                    TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+                        options.module_sources.push((join_path(&path), g.stream()));
+                    }
+                    _ => (),
+                }
+            }
+        }
+        "stub" => options.stubs.extend(parse_stubs(args)),
+        "permutations" => options.permutations = true,
+        "oil" => options.oil = true,
+        "wesl" => options.wesl = true,
+        "dump" => options.dump = Some(name.span()),
+        "stats" => options.stats = Some(name.span()),
+        "round_trip" => options.round_trip = Some(name.span()),
+        "naga_ir" => options.naga_ir = Some(name.span()),
+        "obfuscate" => options.obfuscate = Some(name.span()),
+        "canonical_order" => options.canonical_order = Some(name.span()),
+        "namespaced" => options.namespaced = Some(name.span()),
+        // Read by `paste_from` while pasting.
+        "paste_from" => (),
+        // Removed by `export_option` before options are parsed, unless it has no name.
+        "export" => abort!(name.span(), "Expected `#![export(name)]`."),
+        "origin" => match Vec::from_iter(args).as_slice() {
+            [TokenTree::Ident(item), TokenTree::Punct(_), TokenTree::Ident(krate)] => {
+                options.origins.push((item.clone(), krate.clone()))
+            }
+            _ => abort!(name.span(), "Expected `#![origin(item, crate)]`."),
+        },
+        "gpu_test" => options.gpu_test = Some(name.span()),
+        "eval" => options.eval = Some(name.span()),
+        "case" => options.cases.push(parse_case(args, name.span())),
+        "property" => options.property = Some(parse_property(args, name.span())),
+        "embedded_asset" | "hot_reload" => match args.into_iter().next() {
+            Some(TokenTree::Literal(lit)) => match string_literal(&lit) {
+                Some(path) if name == "embedded_asset" => {
+                    options.embedded_asset = Some((path, lit.span()))
+                }
+                Some(path) => options.hot_reload = Some((path, lit.span())),
+                None => abort!(lit.span(), "Expected a string literal."),
+            },
+            _ => abort!(name.span(), "Expected `#![{}(\"path.wgsl\")]`.", name),
+        },
+        "out_dir" => match args.into_iter().next() {
+            Some(TokenTree::Literal(lit)) => match string_literal(&lit) {
+                Some(path) => options.out_dir = Some((path, lit.span())),
+                None => abort!(lit.span(), "Expected a string literal."),
+            },
+            None => options.out_dir = Some((String::new(), name.span())),
+            Some(tt) => abort!(tt.span(), "Expected `#![out_dir(\"path.wgsl\")]`."),
+        },
+        "doc_item" => options.doc_item = Some(args),
+        "rename" => {
+            for pair in Vec::from_iter(args)
+                .split(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ','))
+            {
+                match pair {
+                    [] => (),
+                    [TokenTree::Ident(from), TokenTree::Punct(eq), TokenTree::Ident(to)]
+                        if eq.as_char() == '=' =>
+                    {
+                        options.renames.push((from.clone(), to.clone()))
+                    }
+                    _ => abort!(
+                        name.span(),
+                        "Expected `#![rename(vs_main = sprite_vs_main)]`."
+                    ),
+                }
+            }
+        }
+        "entry_prefix" => match Vec::from_iter(args).as_slice() {
+            [TokenTree::Ident(prefix)] => options.entry_prefix = Some(prefix.clone()),
+            _ => abort!(name.span(), "Expected `#![entry_prefix(prefix_)]`."),
+        },
+        "validate" => match Vec::from_iter(args).as_slice() {
+            [TokenTree::Ident(i)] => match Level::parse(&i.to_string()) {
+                Some(level) => options.validate = Some(level),
+                None => abort!(i.span(), "Expected `off`, `parse`, `full` or `strict`."),
+            },
+            _ => abort!(name.span(), "Expected `#![validate(level)]`."),
+        },
+        "portability" => match Vec::from_iter(args).as_slice() {
+            [TokenTree::Ident(i)] if i == "browser" => options.portability = Some(i.span()),
+            _ => abort!(name.span(), "Expected `#![portability(browser)]`."),
+        },
+        "label" => match args.into_iter().next() {
+            Some(TokenTree::Literal(lit)) => match string_literal(&lit) {
+                Some(label) => options.label = Some(label),
+                None => abort!(lit.span(), "Expected a string literal."),
+            },
+            _ => abort!(name.span(), "Expected `#![label(\"label\")]`."),
+        },
+        "call_site" => match args.into_iter().next() {
+            Some(TokenTree::Literal(lit)) => options.call_site = string_literal(&lit),
+            _ => abort!(
+                name.span(),
+                "Expected `#![call_site(\"file:line:column\")]`."
+            ),
+        },
+        "material" => match args.into_iter().next() {
+            Some(TokenTree::Ident(i)) => options.material = Some(i),
+            _ => abort!(name.span(), "Expected `#![material(Name)]`."),
+        },
+        "wgpu" | "shader" => {
+            match name.to_string().as_str() {
+                "wgpu" => options.wgpu = true,
+                _ => options.shader = true,
+            }
+            match args.into_iter().next() {
+                Some(TokenTree::Literal(lit)) => match string_literal(&lit) {
+                    Some(label) => options.label = Some(label),
+                    None => abort!(lit.span(), "Expected a string literal."),
+                },
+                None => (),
+                Some(tt) => abort!(tt.span(), "Expected `#![{}(\"label\")]`.", name),
+            }
+        }
+        "defs" => {
+            let defs = options.defs.get_or_insert_with(Vec::new);
+            let mut iter = args.into_iter();
+            while let Some(tt) = iter.next() {
+                match tt {
+                    TokenTree::Ident(i) => defs.push((i, None)),
+                    TokenTree::Punct(p) if p.as_char() == ',' => (),
+                    TokenTree::Punct(p) if p.as_char() == '=' => {
+                        let mut value = String::new();
+                        for tt in iter.by_ref() {
+                            match tt {
+                                TokenTree::Punct(p) if p.as_char() == ',' => break,
+                                tt => value.push_str(&tt.to_string()),
+                            }
+                        }
+                        match defs.last_mut() {
+                            Some((_, v @ None)) if !value.is_empty() => *v = Some(value),
+                            _ => abort!(p.span(), "Expected `NAME = value`."),
+                        }
+                    }
+                    tt => abort!(tt.span(), "Expected a shader def."),
+                }
+            }
+        }
+        _ => abort!(name.span(), "Unknown option `{}`.", name),
+    }
+}
+
+/// Parse `function(args..) == expected`.
+fn parse_case(stream: TokenStream, span: Span) -> Case {
+    let mut iter = stream.into_iter();
+    let (Some(TokenTree::Ident(function)), Some(TokenTree::Group(args))) =
+        (iter.next(), iter.next())
+    else {
+        abort!(span, "Expected `#![case(function(args..) == expected)]`.")
+    };
+    match (iter.next(), iter.next()) {
+        (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)))
+            if a.as_char() == '=' && b.as_char() == '=' => {}
+        _ => abort!(span, "Expected `#![case(function(args..) == expected)]`."),
+    }
+    let mut split = vec![Vec::new()];
+    for tt in args.stream() {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == ',' => split.push(Vec::new()),
+            tt => split.last_mut().unwrap().push(tt),
+        }
+    }
+    Case {
+        function,
+        args: split
+            .into_iter()
+            .filter(|x| !x.is_empty())
+            .map(TokenStream::from_iter)
+            .collect(),
+        expected: iter.collect(),
+    }
+}
+
+/// Parse `#![property(function, reference = path, samples = 256, tolerance = 1e-5, range = -10.0..10.0, seed = 0)]`,
+/// every argument except `function` and `reference` is optional.
+pub fn parse_property(stream: TokenStream, span: Span) -> Property {
+    let mut iter = stream.into_iter();
+    let Some(TokenTree::Ident(function)) = iter.next() else {
+        abort!(
+            span,
+            "Expected `#![property(function, reference = path, ..)]`."
+        )
+    };
+    let mut property = Property {
+        function,
+        reference: TokenStream::new(),
+        samples: quote::quote! {256},
+        tolerance: quote::quote! {1e-5},
+        range: quote::quote! {-10.0..10.0},
+        seed: quote::quote! {0},
+    };
+    let mut split = vec![Vec::new()];
+    for tt in iter {
+        match tt {
+            TokenTree::Punct(p) if p.as_char() == ',' => split.push(Vec::new()),
+            tt => split.last_mut().unwrap().push(tt),
+        }
+    }
+    for arg in split.into_iter().filter(|x| !x.is_empty()) {
+        let mut arg = arg.into_iter();
+        let (Some(TokenTree::Ident(key)), Some(TokenTree::Punct(eq))) = (arg.next(), arg.next())
+        else {
+            abort!(span, "Expected `key = value`.")
+        };
+        if eq.as_char() != '=' {
+            abort!(eq.span(), "Expected `key = value`.")
+        }
+        let value = arg.collect();
+        match key.to_string().as_str() {
+            "reference" => property.reference = value,
+            "samples" => property.samples = value,
+            "tolerance" => property.tolerance = value,
+            "range" => property.range = value,
+            "seed" => property.seed = value,
+            _ => abort!(
+                key.span(),
+                "Unknown argument `{}`, expected `reference`, `samples`, `tolerance`, `range` or `seed`.",
+                key
+            ),
+        }
+    }
+    if property.reference.is_empty() {
+        abort!(span, "Expected a Rust reference, i.e. `reference = path`.")
+    }
+    property
+}
+
+/// Parse a comma separated list of file paths as string literals or module paths.
+fn parse_modules(stream: TokenStream, options: &mut Options) {
+    let mut path = Vec::new();
+    for tt in stream {
+        match tt {
+            TokenTree::Literal(lit) => {
+                let Some(s) = string_literal(&lit) else {
+                    abort!(lit.span(), "Expected a string literal or a module path.")
+                };
+                options.modules.push((s, lit.span()));
+            }
+            TokenTree::Ident(i) => path.push(i),
+            TokenTree::Punct(p) if p.as_char() == ':' => (),
+            TokenTree::Punct(p) if p.as_char() == ',' => {
+                if !path.is_empty() {
+                    options.module_paths.push(std::mem::take(&mut path));
+                }
+            }
+            tt => abort!(tt.span(), "Expected a string literal or a module path."),
+        }
+    }
+    if !path.is_empty() {
+        options.module_paths.push(path);
+    }
+}
+
+/// Value of a string literal.
+pub fn string_literal(lit: &Literal) -> Option<String> {
+    let s = lit.to_string();
+    let s = s.strip_prefix('"').and_then(|s| s.strip_suffix('"'))?;
+    Some(s.replace("\\\\", "\\"))
+}
+
+/// The value of a string or raw string literal.
+pub fn string_value(lit: &Literal) -> Option<String> {
+    let s = lit.to_string();
+    if let Some(raw) = s.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let raw = &raw[hashes..raw.len() - hashes];
+        return Some(raw.strip_prefix('"')?.strip_suffix('"')?.to_owned());
+    }
+    let s = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => result.push('\n'),
+            't' => result.push('\t'),
+            'r' => result.push('\r'),
+            '0' => result.push('\0'),
+            // A line continuation skips the newline and leading whitespace.
+            '\n' => {
+                let rest = chars.as_str().trim_start();
+                chars = rest.chars();
+            }
+            c => result.push(c),
+        }
+    }
+    Some(result)
+}
+
+/// Join a path as `a::b::c`.
+pub fn join_path(path: &[Ident]) -> String {
+    path.iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Parse a comma separated list of `module::item as declaration`.
+fn parse_stubs(stream: TokenStream) -> Vec<Stub> {
+    let mut result = Vec::new();
+    let mut segment = Vec::new();
+    let mut depth = 0;
+    for tt in stream {
+        let is_arrow = matches!(segment.last(), Some(TokenTree::Punct(p)) if p.as_char() == '-');
+        match &tt {
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' && !is_arrow => depth -= 1,
+            TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => {
+                result.push(parse_stub(std::mem::take(&mut segment)));
+                continue;
+            }
+            _ => (),
+        }
+        segment.push(tt);
+    }
+    if !segment.is_empty() {
+        result.push(parse_stub(segment));
+    }
+    result
+}
+
+fn parse_stub(tokens: Vec<TokenTree>) -> Stub {
+    let mut path = Vec::new();
+    let mut iter = tokens.into_iter();
+    loop {
+        match iter.next() {
+            Some(TokenTree::Ident(i)) if i == "as" => break,
+            // `"embedded://shader.wgsl"::item`
+            Some(tt @ (TokenTree::Ident(_) | TokenTree::Literal(_))) => path.push(tt),
+            Some(TokenTree::Punct(p)) if p.as_char() == ':' => (),
+            Some(tt) => abort!(tt.span(), "Expected `module::item as declaration`."),
+            None => abort!(Span::call_site(), "Expected `module::item as declaration`."),
+        }
+    }
+    let Some(TokenTree::Ident(name)) = path.pop() else {
+        abort!(Span::call_site(), "Expected `module::item as declaration`.")
+    };
+    if path.is_empty() {
+        abort!(
+            name.span(),
+            "Expected a module path, i.e. `module::{}`.",
+            name
+        )
+    }
+    let module = path
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join("::");
+    let decl: Vec<_> = iter.collect();
+    if decl.is_empty() {
+        abort!(name.span(), "Expected a declaration after `as`.")
+    }
+    Stub { module, name, decl }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LOCAL_OPTIONS;
+
+    /// Every local option has an arm in `parse_attribute`.
+    #[test]
+    fn local_options_are_parsed() {
+        let source = include_str!("options.rs");
+        let start = source.find("fn parse_attribute").unwrap();
+        let end = start + source[start..].find("\n}\n").unwrap();
+        let patterns = Vec::from_iter(source[start..end].lines().filter_map(|line| {
+            let (pattern, _) = line.trim().split_once(" => ")?;
+            Some(Vec::from_iter(pattern.split(" | ")))
+        }));
+        for name in LOCAL_OPTIONS {
+            let quoted = format!("\"{}\"", name);
+            assert!(
+                patterns.iter().any(|x| x.contains(&quoted.as_str())),
+                "`{}` is not parsed by `parse_attribute`.",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+use proc_macro2::{Span, TokenStream};
+use proc_macro_error::abort;
+use quote::quote_spanned;
+
+/// Run the passes listed in `passes` of `wgsl_ln.toml` on a validated shader, in order.
+///
+/// Returns the source rewritten by the passes, if any, and their warnings.
+pub fn run_passes(
+    passes: &[String],
+    label: Option<&str>,
+    source: &str,
+) -> (Option<String>, TokenStream) {
+    let mut rewritten: Option<String> = None;
+    let mut warnings = TokenStream::new();
+    for pass in passes {
+        let input = rewritten.as_deref().unwrap_or(source);
+        let output = run_program(pass, label, input);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut errors = Vec::new();
+        for line in stderr.lines() {
+            if let Some(warning) = line.strip_prefix("warning: ") {
+                let message = format!("{}: {}", pass, warning);
+                warnings.extend(quote_spanned! {Span::call_site()=>
+                    const _: () = {
+                        #[deprecated(note = #message)]
+                        const PASS_WARNING: () = ();
+                        PASS_WARNING
+                    };
+                });
+            } else {
+                errors.push(line.strip_prefix("error: ").unwrap_or(line));
+            }
+        }
+        if !output.status.success() {
+            let errors = errors.join("\n");
+            abort!(Span::call_site(), "Pass \"{}\" failed: {}", pass, errors)
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !stdout.trim().is_empty() {
+            rewritten = Some(stdout.into_owned());
+        }
+    }
+    (rewritten, warnings)
+}
+
+/// Run a program of `wgsl_ln.toml` with `input` on stdin, `WGSL_LN_LABEL` is set to the label.
+///
+/// Paths with more than one component are relative to `CARGO_MANIFEST_DIR`, others are found in `PATH`.
+pub fn run_program(name: &str, label: Option<&str>, input: &str) -> Output {
+    let program = match Path::new(name).components().count() > 1 {
+        true => PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default()).join(name),
+        false => PathBuf::from(name),
+    };
+    let mut command = Command::new(&program);
+    if let Some(label) = label {
+        command.env("WGSL_LN_LABEL", label);
+    }
+    let child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let Ok(mut child) = child else {
+        abort!(
+            Span::call_site(),
+            "Unable to run \"{}\" in `wgsl_ln.toml`.",
+            program.display()
+        )
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    match child.wait_with_output() {
+        Ok(output) => output,
+        Err(_) => abort!(Span::call_site(), "\"{}\" did not finish.", name),
+    }
+}
@@ -0,0 +1,64 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// Names of the exports of the lighting library.
+pub const EXPORTS: &[&str] = &[
+    "fresnel_schlick",
+    "distribution_ggx",
+    "geometry_schlick_ggx",
+    "geometry_smith",
+    "tonemap_reinhard",
+    "tonemap_reinhard_extended",
+    "tonemap_aces",
+];
+
+/// Source of an export of the lighting library, roughness is perceptual, squared for GGX.
+pub fn source(name: &str) -> Option<TokenStream> {
+    let source = match name {
+        "fresnel_schlick" => quote! {
+            fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+                return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+            }
+        },
+        "distribution_ggx" => quote! {
+            fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+                let a = roughness * roughness;
+                let a2 = a * a;
+                let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+                return a2 / (3.141592653589793 * d * d);
+            }
+        },
+        "geometry_schlick_ggx" => quote! {
+            fn geometry_schlick_ggx(n_dot_v: f32, roughness: f32) -> f32 {
+                let r = roughness + 1.0;
+                let k = r * r / 8.0;
+                return n_dot_v / (n_dot_v * (1.0 - k) + k);
+            }
+        },
+        "geometry_smith" => quote! {
+            fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+                return $wgsl_ln::geometry_schlick_ggx(n_dot_v, roughness)
+                    * geometry_schlick_ggx(n_dot_l, roughness);
+            }
+        },
+        "tonemap_reinhard" => quote! {
+            fn tonemap_reinhard(c: vec3<f32>) -> vec3<f32> {
+                return c / (1.0 + c);
+            }
+        },
+        "tonemap_reinhard_extended" => quote! {
+            fn tonemap_reinhard_extended(c: vec3<f32>, white: f32) -> vec3<f32> {
+                return c * (1.0 + c / (white * white)) / (1.0 + c);
+            }
+        },
+        "tonemap_aces" => quote! {
+            fn tonemap_aces(c: vec3<f32>) -> vec3<f32> {
+                let x = c * (2.51 * c + 0.03);
+                let y = c * (2.43 * c + 0.59) + 0.14;
+                return clamp(x / y, vec3<f32>(0.0), vec3<f32>(1.0));
+            }
+        },
+        _ => return None,
+    };
+    Some(source)
+}
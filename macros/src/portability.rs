@@ -0,0 +1,51 @@
+use naga::{valid::ModuleInfo, Module};
+use proc_macro2::{Span, TokenStream};
+use quote::quote_spanned;
+
+use crate::{
+    level::{capability_error, Level},
+    limits::Limits,
+    storage::storage_format_errors,
+    wgsl2::validate,
+};
+
+/// Warn about what native `wgpu` supports but browsers do not, for `#![portability(browser)]`.
+///
+/// Capabilities outside of core WebGPU, i.e. push constants or `@builtin(primitive_index)`,
+/// storage texture formats outside of core WebGPU and bindings over its default limits.
+///
+/// `info` is from the validation of the shader, a shader that was only parsed is validated here.
+pub fn browser_lints(
+    module: &Module,
+    info: Option<&ModuleInfo>,
+    source: &str,
+    spans: &[(usize, Span)],
+) -> TokenStream {
+    let validated;
+    let info = match info {
+        Some(info) => info,
+        // Errors of a shader that was only parsed are not portability issues.
+        None => match validate(module) {
+            Ok(info) => {
+                validated = info;
+                &validated
+            }
+            Err(_) => return TokenStream::new(),
+        },
+    };
+    let mut lints = Vec::new();
+    lints.extend(capability_error(module, source, spans));
+    lints.extend(storage_format_errors(module, source, spans, Level::Strict));
+    lints.extend(Limits::webgpu().exceeded(module, info, source, spans));
+    let lints = lints.into_iter().map(|(span, message)| {
+        let message = format!("Not portable to browsers: {}", message);
+        quote_spanned! {span=>
+            const _: () = {
+                #[deprecated(note = #message)]
+                const PORTABILITY_WARNING: () = ();
+                PORTABILITY_WARNING
+            };
+        }
+    });
+    lints.collect()
+}
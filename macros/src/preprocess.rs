@@ -0,0 +1,67 @@
+use proc_macro2::{Span, TokenStream};
+use proc_macro_error::abort;
+
+use crate::{config::Config, passes::run_program, to_wgsl_string::to_wgsl_string};
+
+/// Apply `substitute` then the `preprocess` programs of `wgsl_ln.toml` to a shader, before naga sees it.
+///
+/// Returns `None` if the shader is unchanged.
+pub fn preprocess(config: &Config, label: Option<&str>, source: &str) -> Option<String> {
+    if config.substitute.is_empty() && config.preprocess.is_empty() {
+        return None;
+    }
+    let mut result = source.to_owned();
+    for (from, to) in &config.substitute {
+        result = substitute(&result, &as_written(from), &as_written(to));
+    }
+    for program in &config.preprocess {
+        let output = run_program(program, label, &result);
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            abort!(
+                Span::call_site(),
+                "Preprocessor \"{}\" failed: {}",
+                program,
+                stderr.trim()
+            )
+        }
+        result = String::from_utf8_lossy(&output.stdout).into_owned();
+    }
+    (result != source).then_some(result)
+}
+
+/// A pattern as `wgsl_ln` writes it, `@wg64` is written as `@ wg64`.
+fn as_written(pattern: &str) -> String {
+    let Ok(stream) = pattern.parse::<TokenStream>() else {
+        return pattern.to_owned();
+    };
+    let mut result = String::new();
+    to_wgsl_string(stream, &mut Vec::new(), &mut result);
+    result.trim().to_owned()
+}
+
+/// Replace whole tokens, `@ wg64` does not match `@ wg640`.
+fn substitute(source: &str, from: &str, to: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(idx) = rest.find(from) {
+        let (before, after) = (&rest[..idx], &rest[idx + from.len()..]);
+        let joined = |a: Option<char>, b: Option<char>| is_word(a) && is_word(b);
+        if joined(
+            before.chars().next_back().or(result.chars().next_back()),
+            from.chars().next(),
+        ) || joined(from.chars().next_back(), after.chars().next())
+        {
+            let end = idx + from.chars().next().map_or(1, char::len_utf8);
+            result.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        result.push_str(before);
+        result.push_str(to);
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
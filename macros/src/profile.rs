@@ -0,0 +1,80 @@
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use proc_macro2::TokenStream;
+
+use crate::__wgsl_paste2::Chain;
+
+/// Timing of a `wgsl!` invocation, appended to the file in `WGSL_LN_PROFILE` when dropped.
+///
+/// Each line is `<crate> <shader> depth=<n> <phase>=<ms>.. total=<ms>`, where `depth`
+/// is the number of items pasted in so far and `shader` is the pasted item for
+/// invocations that continue the paste chain.
+pub struct Profile {
+    file: Option<String>,
+    start: Instant,
+    last: Instant,
+    depth: usize,
+    pub shader: String,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Profile {
+    pub fn new(stream: &TokenStream) -> Self {
+        let file = std::env::var("WGSL_LN_PROFILE").ok();
+        let depth = match &file {
+            Some(_) => Chain::parse(stream.clone()).map_or(0, |chain| chain.defined.len()),
+            None => 0,
+        };
+        let now = Instant::now();
+        Profile {
+            file,
+            start: now,
+            last: now,
+            depth,
+            shader: "-".to_owned(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Record the time since the last phase.
+    pub fn phase(&mut self, name: &'static str) {
+        if self.file.is_none() {
+            return;
+        }
+        let now = Instant::now();
+        self.phases.push((name, now - self.last));
+        self.last = now;
+    }
+}
+
+impl Drop for Profile {
+    fn drop(&mut self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let mut line = format!(
+            "{} {} depth={}",
+            std::env::var("CARGO_CRATE_NAME").unwrap_or_default(),
+            self.shader.replace(char::is_whitespace, "_"),
+            self.depth
+        );
+        for (name, duration) in &self.phases {
+            line.push_str(&format!(" {}={:.3}", name, duration.as_secs_f64() * 1000.0));
+        }
+        line.push_str(&format!(
+            " total={:.3}\n",
+            self.start.elapsed().as_secs_f64() * 1000.0
+        ));
+        // A single write per line, since crates are compiled in parallel.
+        if let Ok(mut file) = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file)
+        {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+}
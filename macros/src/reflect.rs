@@ -0,0 +1,369 @@
+use naga::{
+    valid::GlobalUse, AddressSpace, ArraySize, Binding, FunctionArgument, FunctionResult,
+    ImageClass, ImageDimension, Interpolation, Module, Sampling, ScalarKind, ShaderStage,
+    StorageAccess, TypeInner,
+};
+use proc_macro2::{Span, TokenStream};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+use crate::{
+    consts::{workgroup_size_of, RustConsts},
+    wgsl2::{global_span, validate},
+};
+
+/// Reflect resource bindings used by entry points as `&[wgsl_ln_runtime::Binding]`.
+///
+/// Globals sharing a binding must have the same type.
+pub fn bindings(module: &Module, source: &str, spans: &[(usize, Span)]) -> TokenStream {
+    let Ok(info) = validate(module) else {
+        return quote! {&[]};
+    };
+    let mut bindings: Vec<(u32, u32, u32, String, TokenStream)> = Vec::new();
+    for (handle, var) in module.global_variables.iter() {
+        let Some(binding) = &var.binding else {
+            continue;
+        };
+        let mut stages = 0;
+        for (idx, entry) in module.entry_points.iter().enumerate() {
+            if info.get_entry_point(idx)[handle] != GlobalUse::empty() {
+                stages |= match entry.stage {
+                    ShaderStage::Vertex => 1,
+                    ShaderStage::Fragment => 2,
+                    ShaderStage::Compute => 4,
+                };
+            }
+        }
+        if stages == 0 {
+            continue;
+        }
+        let (ty, count) = match module.types[var.ty].inner {
+            TypeInner::BindingArray { base, size } => match size {
+                ArraySize::Constant(n) => {
+                    let n = n.get();
+                    (base, quote! {::core::num::NonZeroU32::new(#n)})
+                }
+                ArraySize::Dynamic => abort!(
+                    global_span(module, handle, source, spans),
+                    "Runtime sized binding arrays are not supported in `#![shader]`."
+                ),
+            },
+            _ => (var.ty, quote! {None}),
+        };
+        let binding_type = binding_type(module, var.space, ty);
+        let signature = quote! {#binding_type #count}.to_string();
+        match bindings
+            .iter()
+            .position(|x| x.0 == binding.group && x.1 == binding.binding)
+        {
+            Some(idx) if bindings[idx].3 != signature => abort!(
+                global_span(module, handle, source, spans),
+                "Entry points disagree about the type of `@group({}) @binding({})`.",
+                binding.group,
+                binding.binding
+            ),
+            Some(idx) => {
+                bindings[idx].2 |= stages;
+                continue;
+            }
+            None => (),
+        }
+        bindings.push((
+            binding.group,
+            binding.binding,
+            stages,
+            signature,
+            quote! {
+                ty: #binding_type,
+                count: #count,
+            },
+        ));
+    }
+    bindings.sort_by_key(|x| (x.0, x.1));
+    let bindings = bindings
+        .into_iter()
+        .map(|(group, binding, stages, signature, fields)| {
+            quote! {
+                ::wgsl_ln::runtime::Binding {
+                    group: #group,
+                    binding: #binding,
+                    visibility: ::wgsl_ln::runtime::wgpu::ShaderStages::from_bits_truncate(#stages),
+                    #fields
+                    signature: #signature,
+                }
+            }
+        });
+    quote! {&[#(#bindings),*]}
+}
+
+/// Reflect entry points as `&[wgsl_ln_runtime::EntryPoint]`.
+pub fn entry_points(module: &Module, consts: &RustConsts) -> TokenStream {
+    let entry_points = module.entry_points.iter().map(|entry| {
+        let name = &entry.name;
+        let stage = match entry.stage {
+            ShaderStage::Vertex => quote! {VERTEX},
+            ShaderStage::Fragment => quote! {FRAGMENT},
+            ShaderStage::Compute => quote! {COMPUTE},
+        };
+        let [x, y, z] = workgroup_size_of(consts, name, entry.workgroup_size);
+        let inputs = stage_inputs(module, entry.stage, &entry.function.arguments);
+        let outputs = match entry.stage {
+            ShaderStage::Fragment => fragment_outputs(module, entry.function.result.as_ref()),
+            _ => Vec::new(),
+        };
+        quote! {
+            ::wgsl_ln::runtime::EntryPoint {
+                name: #name,
+                stage: ::wgsl_ln::runtime::wgpu::ShaderStages::#stage,
+                workgroup_size: [#x, #y, #z],
+                inputs: &[#(#inputs),*],
+                outputs: &[#(#outputs),*],
+            }
+        }
+    });
+    quote! {&[#(#entry_points),*]}
+}
+
+/// Reflect `override`s as `&[wgsl_ln_runtime::Override]`.
+pub fn overrides(module: &Module) -> TokenStream {
+    let overrides = module.overrides.iter().filter_map(|(_, item)| {
+        let name = item.name.as_deref()?;
+        let ty = match module.types[item.ty].inner {
+            TypeInner::Scalar(scalar) => match (scalar.kind, scalar.width) {
+                (ScalarKind::Bool, _) => quote! {Bool},
+                (ScalarKind::Sint, 4) => quote! {I32},
+                (ScalarKind::Uint, 4) => quote! {U32},
+                (ScalarKind::Float, 4) => quote! {F32},
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let key = item.id.map_or_else(|| name.to_owned(), |id| id.to_string());
+        let id = match item.id {
+            Some(id) => quote! {Some(#id)},
+            None => quote! {None},
+        };
+        Some(quote! {
+            ::wgsl_ln::runtime::Override {
+                name: #name,
+                id: #id,
+                key: #key,
+                ty: ::wgsl_ln::runtime::OverrideType::#ty,
+            }
+        })
+    });
+    quote! {&[#(#overrides),*]}
+}
+
+/// Reflect `@location` inputs of a vertex or fragment entry point as `wgsl_ln_runtime::StageInput`s.
+fn stage_inputs(
+    module: &Module,
+    stage: ShaderStage,
+    arguments: &[FunctionArgument],
+) -> Vec<TokenStream> {
+    let mut inputs = Vec::new();
+    for argument in arguments {
+        match &module.types[argument.ty].inner {
+            TypeInner::Struct { members, .. } => {
+                inputs.extend(members.iter().map(|x| (x.binding.as_ref(), x.ty)))
+            }
+            _ => inputs.push((argument.binding.as_ref(), argument.ty)),
+        }
+    }
+    let mut inputs: Vec<_> = inputs
+        .into_iter()
+        .filter_map(|(binding, ty)| match binding {
+            Some(Binding::Location {
+                location,
+                interpolation,
+                sampling,
+                ..
+            }) => Some((*location, *interpolation, *sampling, ty)),
+            _ => None,
+        })
+        .collect();
+    inputs.sort_by_key(|x| x.0);
+    inputs
+        .into_iter()
+        .map(|(location, interpolation, sampling, ty)| {
+            let (scalar, components) = match module.types[ty].inner {
+                TypeInner::Scalar(scalar) => (scalar, 1),
+                TypeInner::Vector { size, scalar } => (scalar, size as u8),
+                _ => unreachable!("validated by naga"),
+            };
+            let kind = match scalar.kind {
+                ScalarKind::Sint => "Sint32",
+                ScalarKind::Uint => "Uint32",
+                ScalarKind::Float if scalar.width == 8 => "Float64",
+                _ => "Float32",
+            };
+            let format = match components {
+                1 => format_ident!("{}", kind),
+                n => format_ident!("{}x{}", kind, n),
+            };
+            let interpolation = match stage {
+                ShaderStage::Fragment => {
+                    let interpolation = match interpolation {
+                        Some(Interpolation::Linear) => quote! {Linear},
+                        Some(Interpolation::Flat) => quote! {Flat},
+                        _ => quote! {Perspective},
+                    };
+                    let sampling = match sampling {
+                        Some(Sampling::Centroid) => quote! {Centroid},
+                        Some(Sampling::Sample) => quote! {Sample},
+                        _ => quote! {Center},
+                    };
+                    quote! {Some((
+                        ::wgsl_ln::runtime::Interpolation::#interpolation,
+                        ::wgsl_ln::runtime::Sampling::#sampling,
+                    ))}
+                }
+                _ => quote! {None},
+            };
+            quote! {
+                ::wgsl_ln::runtime::StageInput {
+                    location: #location,
+                    format: ::wgsl_ln::runtime::wgpu::VertexFormat::#format,
+                    interpolation: #interpolation,
+                }
+            }
+        })
+        .collect()
+}
+
+/// Reflect `@location` outputs of a fragment entry point as `wgsl_ln_runtime::FragmentOutput`s.
+fn fragment_outputs(module: &Module, result: Option<&FunctionResult>) -> Vec<TokenStream> {
+    let Some(result) = result else {
+        return Vec::new();
+    };
+    let outputs = match &module.types[result.ty].inner {
+        TypeInner::Struct { members, .. } => {
+            members.iter().map(|x| (x.binding.as_ref(), x.ty)).collect()
+        }
+        _ => vec![(result.binding.as_ref(), result.ty)],
+    };
+    let mut outputs: Vec<_> = outputs
+        .into_iter()
+        .filter_map(|(binding, ty)| match binding {
+            Some(Binding::Location { location, .. }) => Some((*location, ty)),
+            _ => None,
+        })
+        .collect();
+    outputs.sort_by_key(|x| x.0);
+    outputs
+        .into_iter()
+        .map(|(location, ty)| {
+            let (scalar, components) = match module.types[ty].inner {
+                TypeInner::Scalar(scalar) => (scalar, 1u8),
+                TypeInner::Vector { size, scalar } => (scalar, size as u8),
+                _ => unreachable!("validated by naga"),
+            };
+            let kind = match scalar.kind {
+                ScalarKind::Sint => quote! {Sint},
+                ScalarKind::Uint => quote! {Uint},
+                _ => quote! {Float},
+            };
+            quote! {
+                ::wgsl_ln::runtime::FragmentOutput {
+                    location: #location,
+                    kind: ::wgsl_ln::runtime::OutputKind::#kind,
+                    components: #components,
+                }
+            }
+        })
+        .collect()
+}
+
+/// `wgpu::BindingType` of a global.
+fn binding_type(module: &Module, space: AddressSpace, ty: naga::Handle<naga::Type>) -> TokenStream {
+    let wgpu = quote! {::wgsl_ln::runtime::wgpu};
+    match space {
+        AddressSpace::Uniform => {
+            let size = module.types[ty].inner.size(module.to_ctx()) as u64;
+            return quote! {
+                #wgpu::BindingType::Buffer {
+                    ty: #wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: ::core::num::NonZeroU64::new(#size),
+                }
+            };
+        }
+        AddressSpace::Storage { access } => {
+            let read_only = !access.contains(StorageAccess::STORE);
+            return quote! {
+                #wgpu::BindingType::Buffer {
+                    ty: #wgpu::BufferBindingType::Storage { read_only: #read_only },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                }
+            };
+        }
+        _ => (),
+    }
+    match &module.types[ty].inner {
+        TypeInner::Sampler { comparison: false } => {
+            quote! {#wgpu::BindingType::Sampler(#wgpu::SamplerBindingType::Filtering)}
+        }
+        TypeInner::Sampler { comparison: true } => {
+            quote! {#wgpu::BindingType::Sampler(#wgpu::SamplerBindingType::Comparison)}
+        }
+        TypeInner::AccelerationStructure => quote! {#wgpu::BindingType::AccelerationStructure},
+        TypeInner::Image {
+            dim,
+            arrayed,
+            class,
+        } => {
+            let view_dimension = match (dim, arrayed) {
+                (ImageDimension::D1, _) => quote! {D1},
+                (ImageDimension::D2, false) => quote! {D2},
+                (ImageDimension::D2, true) => quote! {D2Array},
+                (ImageDimension::D3, _) => quote! {D3},
+                (ImageDimension::Cube, false) => quote! {Cube},
+                (ImageDimension::Cube, true) => quote! {CubeArray},
+            };
+            let view_dimension = quote! {#wgpu::TextureViewDimension::#view_dimension};
+            match class {
+                ImageClass::Sampled { kind, multi } => {
+                    let sample_type = match kind {
+                        ScalarKind::Sint => quote! {Sint},
+                        ScalarKind::Uint => quote! {Uint},
+                        _ => quote! {Float { filterable: true }},
+                    };
+                    quote! {
+                        #wgpu::BindingType::Texture {
+                            sample_type: #wgpu::TextureSampleType::#sample_type,
+                            view_dimension: #view_dimension,
+                            multisampled: #multi,
+                        }
+                    }
+                }
+                ImageClass::Depth { multi } => quote! {
+                    #wgpu::BindingType::Texture {
+                        sample_type: #wgpu::TextureSampleType::Depth,
+                        view_dimension: #view_dimension,
+                        multisampled: #multi,
+                    }
+                },
+                ImageClass::Storage { format, access } => {
+                    let format = format_ident!("{}", format!("{:?}", format));
+                    let access = match (
+                        access.contains(StorageAccess::LOAD),
+                        access.contains(StorageAccess::STORE),
+                    ) {
+                        (true, true) => quote! {ReadWrite},
+                        (true, false) => quote! {ReadOnly},
+                        _ => quote! {WriteOnly},
+                    };
+                    quote! {
+                        #wgpu::BindingType::StorageTexture {
+                            access: #wgpu::StorageTextureAccess::#access,
+                            format: #wgpu::TextureFormat::#format,
+                            view_dimension: #view_dimension,
+                        }
+                    }
+                }
+            }
+        }
+        _ => abort!(Span::call_site(), "Unsupported binding type."),
+    }
+}
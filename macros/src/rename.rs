@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet};
+
+use proc_macro2::{Delimiter, Group, Ident, TokenStream, TokenTree};
+
+use quote::format_ident;
+
+use crate::{
+    items::{is_entry_point, item_at, item_kind, item_name},
+    options::extract_options,
+    sanitize::{DOLLAR, HASH},
+    to_wgsl_string::macro_args,
+};
+
+/// Names in scope and their replacements.
+type Scope = HashMap<String, Ident>;
+
+/// Short names like `_0`, `_1`, .., `_z`, `_10`, skipping identifiers already in the shader.
+struct Names {
+    count: usize,
+    used: HashSet<String>,
+}
+
+impl Names {
+    fn next(&mut self, original: &Ident) -> Ident {
+        loop {
+            let mut n = self.count;
+            self.count += 1;
+            let mut digits = Vec::new();
+            loop {
+                digits.push(char::from_digit((n % 36) as u32, 36).unwrap());
+                n /= 36;
+                if n == 0 {
+                    break;
+                }
+            }
+            let name = format!("_{}", digits.iter().rev().collect::<String>());
+            if !self.used.contains(&name) {
+                return Ident::new(&name, original.span());
+            }
+        }
+    }
+}
+
+fn collect_idents(stream: TokenStream, used: &mut HashSet<String>) {
+    for tt in stream {
+        match tt {
+            TokenTree::Ident(i) => {
+                used.insert(i.to_string());
+            }
+            TokenTree::Group(g) => collect_idents(g.stream(), used),
+            _ => (),
+        }
+    }
+}
+
+/// Rename identifiers to short names for `#![obfuscate]`, consistently across pasted items.
+///
+/// Names seen from outside the shader are kept: entry points, `override`s, resources with
+/// `@group` or `@binding`, struct members and `$extern` declarations.
+/// `externs` are renamed with the same names so they still validate.
+pub fn obfuscate(stream: TokenStream, externs: TokenStream) -> (TokenStream, TokenStream) {
+    let mut used = HashSet::new();
+    collect_idents(stream.clone(), &mut used);
+    collect_idents(externs.clone(), &mut used);
+    let mut names = Some(Names { count: 0, used });
+    let tokens: Vec<_> = stream.into_iter().collect();
+    // Module scope declarations are visible everywhere, regardless of order.
+    let mut scope = Scope::new();
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let Some((start, end)) = item_at(&tokens, idx) else {
+            idx += 1;
+            continue;
+        };
+        let attributes = &tokens[start..idx];
+        let has_binding = attributes
+            .iter()
+            .any(|x| matches!(x, TokenTree::Ident(i) if i == "group" || i == "binding"));
+        let keep = match tokens[idx].to_string().as_str() {
+            "fn" => is_entry_point(attributes),
+            "var" => has_binding,
+            "override" => true,
+            _ => false,
+        };
+        if let (false, Some(name)) = (keep, item_name(&tokens[idx + 1..end])) {
+            let renamed = fresh(&mut names, &name);
+            scope.insert(name.to_string(), renamed);
+        }
+        idx = end;
+    }
+    let stream = rename(tokens, scope.clone(), &mut names, true).0;
+    let externs = rename(externs.into_iter().collect(), scope, &mut names, true).0;
+    (stream, externs)
+}
+
+/// For `#![namespaced]`, prefix functions, structs, constants and aliases pasted in from
+/// other `wgsl!` calls with the name of their crate, i.e. `noise_hash` for `hash`.
+///
+/// References in the body are renamed unless the body declares an item of the same name.
+pub fn namespaced(
+    defined: &[Ident],
+    pasted: TokenStream,
+    body: TokenStream,
+) -> (TokenStream, TokenStream) {
+    let (_, options) = extract_options(pasted.clone());
+    let mut scope = Scope::new();
+    for (item, krate) in &options.origins {
+        let kind = item_kind(pasted.clone(), item);
+        if defined.contains(item)
+            && matches!(kind.as_deref(), Some("fn" | "struct" | "const" | "alias"))
+        {
+            scope.insert(item.to_string(), format_ident!("{}_{}", krate, item));
+        }
+    }
+    let pasted = rename(pasted.into_iter().collect(), scope.clone(), &mut None, true).0;
+    scope.retain(|name, _| item_kind(body.clone(), &format_ident!("{}", name)).is_none());
+    let body = rename(body.into_iter().collect(), scope, &mut None, true).0;
+    (pasted, body)
+}
+
+/// A new name with `names`, otherwise the same name, shadowing a renamed item.
+fn fresh(names: &mut Option<Names>, ident: &Ident) -> Ident {
+    match names {
+        Some(names) => names.next(ident),
+        None => ident.clone(),
+    }
+}
+
+fn renamed(ident: Ident, scope: &Scope) -> TokenTree {
+    match scope.get(&ident.to_string()) {
+        Some(renamed) => TokenTree::Ident(renamed.clone()),
+        None => TokenTree::Ident(ident),
+    }
+}
+
+fn is_punct(tt: Option<&TokenTree>, c: char) -> bool {
+    matches!(tt, Some(TokenTree::Punct(p)) if p.as_char() == c)
+}
+
+/// Rename the tokens of a group, returns the scope at its end for `for (..) {..}`.
+///
+/// With `names`, local declarations are renamed as well.
+fn rename(
+    tokens: Vec<TokenTree>,
+    mut scope: Scope,
+    names: &mut Option<Names>,
+    module: bool,
+) -> (TokenStream, Scope) {
+    let mut result: Vec<TokenTree> = Vec::new();
+    // Local declarations are in scope after their statement.
+    let mut pending: Vec<(String, Ident)> = Vec::new();
+    // Scope of the next braced group, the parameters of a function or the initializer of a `for`.
+    let mut next_scope: Option<Scope> = None;
+    // After `let`, `var` or `const`, the next identifier outside of `<..>` is declared.
+    let mut declaring = false;
+    let mut template_depth = 0;
+    let mut iter = tokens.into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        // Member accesses, swizzles, escaped placeholders like `$$value` and `ident!("name")`.
+        let keep = is_punct(result.last(), '.')
+            || matches!(result.last(), Some(TokenTree::Ident(i)) if i == DOLLAR || i == HASH)
+            || matches!(&tt, TokenTree::Ident(i) if i == "ident")
+                && macro_args(iter.clone()).is_some();
+        match tt {
+            TokenTree::Ident(i) if !module && (i == "let" || i == "var" || i == "const") => {
+                declaring = true;
+                result.push(TokenTree::Ident(i));
+            }
+            TokenTree::Ident(i) if declaring && template_depth == 0 => {
+                declaring = false;
+                let renamed = fresh(names, &i);
+                pending.push((i.to_string(), renamed.clone()));
+                result.push(TokenTree::Ident(renamed));
+            }
+            // Address spaces and access modes of `var<storage, read>`.
+            TokenTree::Ident(i) if template_depth > 0 || keep => result.push(TokenTree::Ident(i)),
+            TokenTree::Ident(i) => result.push(renamed(i, &scope)),
+            TokenTree::Punct(p) if declaring && p.as_char() == '<' => {
+                template_depth += 1;
+                result.push(TokenTree::Punct(p));
+            }
+            TokenTree::Punct(p) if template_depth > 0 && p.as_char() == '>' => {
+                template_depth -= 1;
+                result.push(TokenTree::Punct(p));
+            }
+            TokenTree::Punct(p) if p.as_char() == ';' => {
+                scope.extend(pending.drain(..));
+                result.push(TokenTree::Punct(p));
+            }
+            TokenTree::Group(g) => {
+                let keyword = |n: usize| match result.len().checked_sub(n).map(|i| &result[i]) {
+                    Some(TokenTree::Ident(i)) => i.to_string(),
+                    _ => String::new(),
+                };
+                let is_attribute = is_punct(result.len().checked_sub(2).map(|i| &result[i]), '@');
+                let tokens: Vec<_> = g.stream().into_iter().collect();
+                let stream = match g.delimiter() {
+                    // Options like `#![origin(..)]`.
+                    Delimiter::Bracket if is_punct(result.last(), '!') => g.stream(),
+                    // `@builtin(position)` and `@interpolate(flat)` name enumerants.
+                    Delimiter::Parenthesis
+                        if is_attribute
+                            && matches!(keyword(1).as_str(), "builtin" | "interpolate") =>
+                    {
+                        g.stream()
+                    }
+                    // Parameters are in scope of the function body.
+                    Delimiter::Parenthesis if module && keyword(2) == "fn" => {
+                        let mut params = scope.clone();
+                        for (i, tt) in tokens.iter().enumerate() {
+                            match tt {
+                                TokenTree::Ident(name) if is_punct(tokens.get(i + 1), ':') => {
+                                    params.insert(name.to_string(), fresh(names, name));
+                                }
+                                _ => (),
+                            }
+                        }
+                        let (stream, params) = rename(tokens, params, names, false);
+                        next_scope = Some(params);
+                        stream
+                    }
+                    Delimiter::Parenthesis if keyword(1) == "for" => {
+                        let (stream, init) = rename(tokens, scope.clone(), names, false);
+                        next_scope = Some(init);
+                        stream
+                    }
+                    // Member names are kept, their types are renamed.
+                    Delimiter::Brace if module && keyword(2) == "struct" => {
+                        let mut members = Vec::new();
+                        let mut tokens = tokens.into_iter().peekable();
+                        while let Some(tt) = tokens.next() {
+                            match tt {
+                                TokenTree::Ident(i) if !is_punct(tokens.peek(), ':') => {
+                                    members.push(renamed(i, &scope))
+                                }
+                                tt => members.push(tt),
+                            }
+                        }
+                        TokenStream::from_iter(members)
+                    }
+                    Delimiter::Brace => {
+                        let scope = next_scope.take().unwrap_or_else(|| scope.clone());
+                        rename(tokens, scope, names, false).0
+                    }
+                    _ => rename(tokens, scope.clone(), names, module).0,
+                };
+                let mut group = Group::new(g.delimiter(), stream);
+                group.set_span(g.span());
+                result.push(TokenTree::Group(group));
+            }
+            tt => result.push(tt),
+        }
+    }
+    scope.extend(pending);
+    (TokenStream::from_iter(result), scope)
+}
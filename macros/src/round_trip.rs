@@ -0,0 +1,68 @@
+use naga::{back::wgsl::WriterFlags, Module};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+
+use crate::{options::Options, wgsl2::validate};
+
+/// Write a module with naga's WGSL backend, `None` if it is invalid.
+fn canonical(module: &Module) -> Option<String> {
+    let info = validate(module).ok()?;
+    naga::back::wgsl::write_string(module, &info, WriterFlags::empty()).ok()
+}
+
+/// For `#![round_trip]`, warn if the output and a plain printing of its tokens
+/// differ after a round trip through naga's WGSL writer.
+///
+/// Both are written from the naga IR, so only differences in meaning remain,
+/// i.e. a literal or operator changed by formatting. Checked for every shader
+/// if `WGSL_LN_ROUND_TRIP` is set.
+pub fn round_trip(
+    options: &Options,
+    tokens: Option<&str>,
+    module: &Module,
+    expr: TokenStream,
+) -> TokenStream {
+    let Some(tokens) = tokens else {
+        return expr;
+    };
+    // Token printing is not a supported format, there is nothing to compare to if it fails.
+    let Some(expected) = naga::front::wgsl::parse_str(tokens)
+        .ok()
+        .and_then(|module| canonical(&module))
+    else {
+        return expr;
+    };
+    let Some(actual) = canonical(module) else {
+        return expr;
+    };
+    let Some((line, (a, b))) = actual
+        .lines()
+        .zip(expected.lines())
+        .enumerate()
+        .find(|(_, (a, b))| a != b)
+        .or_else(|| {
+            let line = actual.lines().count().min(expected.lines().count());
+            let a = actual.lines().nth(line).unwrap_or_default();
+            let b = expected.lines().nth(line).unwrap_or_default();
+            (a != b).then_some((line, (a, b)))
+        })
+    else {
+        return expr;
+    };
+    let message = format!(
+        "`#![round_trip]` the output differs from its tokens after naga's WGSL writer, line {}:\n\
+        output: {}\ntokens: {}",
+        line + 1,
+        a.trim(),
+        b.trim()
+    );
+    let span = options.round_trip.unwrap_or_else(Span::call_site);
+    let note = quote_spanned! {span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            const WGSL: () = ();
+            WGSL
+        };
+    };
+    quote! {{#note #expr}}
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+
+use naga::{Expression, GlobalVariable, Handle, ImageClass, Module, ScalarKind, TypeInner};
+use proc_macro2::{Span, TokenStream};
+use quote::quote_spanned;
+
+use crate::wgsl2::global_span;
+
+/// Warn about texture bindings that are never sampled while a sampler of the same group
+/// is never used with a texture, and comparison samplers used with non-depth textures.
+///
+/// Textures and samplers passed to a function are treated as sampled.
+pub fn sampler_lints(module: &Module, source: &str, spans: &[(usize, Span)]) -> TokenStream {
+    let mut referenced = HashSet::new();
+    let mut sampled = HashSet::new();
+    let mut pairs = HashSet::new();
+    let functions = module
+        .functions
+        .iter()
+        .map(|(_, function)| function)
+        .chain(module.entry_points.iter().map(|entry| &entry.function));
+    for function in functions {
+        let global = |handle: Handle<Expression>| match function.expressions[handle] {
+            Expression::GlobalVariable(global) => Some(global),
+            _ => None,
+        };
+        // Globals consumed by an image expression, any other use is a function argument.
+        let mut consumed = HashSet::new();
+        for (_, expression) in function.expressions.iter() {
+            match *expression {
+                Expression::ImageSample { image, sampler, .. } => {
+                    consumed.extend([image, sampler]);
+                    sampled.extend([global(image), global(sampler)].into_iter().flatten());
+                    if let (Some(image), Some(sampler)) = (global(image), global(sampler)) {
+                        pairs.insert((image, sampler));
+                    }
+                }
+                Expression::ImageLoad { image, .. } | Expression::ImageQuery { image, .. } => {
+                    consumed.insert(image);
+                }
+                _ => (),
+            }
+        }
+        for (handle, expression) in function.expressions.iter() {
+            if let Expression::GlobalVariable(global) = *expression {
+                referenced.insert(global);
+                if !consumed.contains(&handle) {
+                    sampled.insert(global);
+                }
+            }
+        }
+    }
+    let mut warnings = TokenStream::new();
+    let mut warn = |handle: Handle<GlobalVariable>, message: String| {
+        let span = global_span(module, handle, source, spans);
+        warnings.extend(quote_spanned! {span=>
+            const _: () = {
+                #[deprecated(note = #message)]
+                const SAMPLER_WARNING: () = ();
+                SAMPLER_WARNING
+            };
+        });
+    };
+    let name = |handle: Handle<GlobalVariable>| {
+        module.global_variables[handle]
+            .name
+            .clone()
+            .unwrap_or_default()
+    };
+    let mut unpaired = Vec::new();
+    for (handle, var) in module.global_variables.iter() {
+        let Some(binding) = &var.binding else {
+            continue;
+        };
+        if matches!(module.types[var.ty].inner, TypeInner::Sampler { .. })
+            && !sampled.contains(&handle)
+        {
+            unpaired.push((binding.group, handle));
+        }
+    }
+    for (handle, var) in module.global_variables.iter() {
+        let (Some(binding), Some(class)) = (&var.binding, image_class(module, var)) else {
+            continue;
+        };
+        let samplable = matches!(
+            class,
+            ImageClass::Sampled {
+                kind: ScalarKind::Float,
+                multi: false
+            } | ImageClass::Depth { multi: false }
+        );
+        if !samplable || !referenced.contains(&handle) || sampled.contains(&handle) {
+            continue;
+        }
+        if let Some((_, sampler)) = unpaired.iter().find(|(group, _)| *group == binding.group) {
+            let message = format!(
+                "`{}` is never used with a sampler and `{}` of the same group is never used with a texture.",
+                name(handle),
+                name(*sampler),
+            );
+            warn(handle, message);
+        }
+    }
+    let mut pairs: Vec<_> = pairs.into_iter().collect();
+    pairs.sort_by_key(|(image, sampler)| (image.index(), sampler.index()));
+    for (image, sampler) in pairs {
+        let comparison = matches!(
+            module.types[module.global_variables[sampler].ty].inner,
+            TypeInner::Sampler { comparison: true }
+        );
+        let depth = matches!(
+            image_class(module, &module.global_variables[image]),
+            Some(ImageClass::Depth { .. })
+        );
+        if comparison && !depth {
+            let message = format!(
+                "`{}` is a comparison sampler used with `{}`, which is not a depth texture.",
+                name(sampler),
+                name(image),
+            );
+            warn(sampler, message);
+        }
+    }
+    warnings
+}
+
+/// Class of a texture, including binding arrays of textures.
+fn image_class(module: &Module, var: &GlobalVariable) -> Option<ImageClass> {
+    let ty = match module.types[var.ty].inner {
+        TypeInner::BindingArray { base, .. } => base,
+        _ => var.ty,
+    };
+    match module.types[ty].inner {
+        TypeInner::Image { class, .. } => Some(class),
+        _ => None,
+    }
+}
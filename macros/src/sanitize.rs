@@ -0,0 +1,201 @@
+use std::iter::Peekable;
+
+use proc_macro2::{token_stream::IntoIter, Group, Ident, Spacing, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote;
+
+use crate::externs::is_extern;
+
+/// Stands in for a literal `$`, written as `$$`, so it is not taken for an import
+/// when the stream is sanitized again in the paste chain.
+pub const DOLLAR: &str = "__wgsl_dollar";
+
+/// Stands in for a literal `#`, written as `##`, which is neither an import nor a `naga_oil` directive.
+pub const HASH: &str = "__wgsl_hash";
+
+/// Find every `$ident` or `#ident`, remove the sigils and return the distinct `ident`s in order.
+///
+/// In `naga_oil` mode `#directive`s are kept, otherwise they are errors.
+/// `$$` and `##` are replaced with [`DOLLAR`] and [`HASH`].
+/// `$krate::ident` imports from a specific crate, recorded as `#![paste_from(ident, krate)]`.
+pub fn sanitize(stream: TokenStream, oil: bool) -> (TokenStream, Vec<Ident>) {
+    let mut pastes = Vec::new();
+    let mut sources = Vec::new();
+    let stream = sanitize_group(escape_sigils(stream), oil, &mut pastes, &mut sources);
+    let sources = sources
+        .into_iter()
+        .map(|(name, krate)| quote! {#![paste_from(#name, #krate)]});
+    (quote! {#(#sources)* #stream}, pastes)
+}
+
+fn sanitize_group(
+    stream: TokenStream,
+    oil: bool,
+    pastes: &mut Vec<Ident>,
+    sources: &mut Vec<(Ident, Ident)>,
+) -> TokenStream {
+    let mut result = Vec::new();
+    let mut last_sigil = None;
+    let mut iter = stream.into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        match tt {
+            TokenTree::Punct(p) if is_sigil(p.as_char()) => {
+                last_sigil = Some(p.as_char());
+                result.push(TokenTree::Punct(p));
+            }
+            // `$extern` is handled by `wgsl!` itself.
+            TokenTree::Ident(ident) if last_sigil == Some('$') && is_extern(&ident) => {
+                last_sigil = None;
+                result.push(TokenTree::Ident(ident));
+            }
+            // if is a naga_oil definition, write `#def`
+            TokenTree::Ident(ident) if last_sigil == Some('#') && is_naga_oil_name(&ident) => {
+                if !oil {
+                    abort!(
+                        ident.span(),
+                        "`#{}` is a `naga_oil` directive, use `wgsl_oil!` or `#![oil]` to enable `naga_oil` mode.",
+                        ident
+                    )
+                }
+                last_sigil = None;
+                result.push(TokenTree::Ident(ident));
+            }
+            // If `$ident`, import it and remove the `$`.
+            TokenTree::Ident(ident) if last_sigil.is_some() => {
+                last_sigil = None;
+                let _ = result.pop();
+                // `$krate::ident`
+                let ident = match path_segment(&mut iter) {
+                    Some(name) => {
+                        if let Some((_, other)) = sources.iter().find(|(x, _)| x == &name) {
+                            if other != &ident {
+                                abort!(
+                                    ident.span(),
+                                    "`{}` is imported from both `{}` and `{}`.",
+                                    name,
+                                    other,
+                                    ident
+                                )
+                            }
+                        }
+                        sources.push((name.clone(), ident));
+                        name
+                    }
+                    None => ident,
+                };
+                if !pastes.contains(&ident) {
+                    pastes.push(ident.clone());
+                }
+                result.push(TokenTree::Ident(ident));
+            }
+            // Recursively look for `$`s.
+            TokenTree::Group(g) => {
+                last_sigil = None;
+                let stream = sanitize_group(g.stream(), oil, pastes, sources);
+                let mut group = Group::new(g.delimiter(), stream);
+                group.set_span(g.span());
+                result.push(TokenTree::Group(group));
+            }
+            // `#![option]`
+            TokenTree::Punct(p) if last_sigil == Some('#') && p.as_char() == '!' => {
+                last_sigil = None;
+                result.push(TokenTree::Punct(p))
+            }
+            tt if last_sigil == Some('#') && !oil => {
+                abort!(
+                    tt.span(),
+                    "Unexpected token after `#`, expected an import or `#![option]`."
+                )
+            }
+            tt => {
+                last_sigil = None;
+                result.push(tt)
+            }
+        }
+    }
+    TokenStream::from_iter(result)
+}
+
+/// Take `::ident` after the crate of `$krate::ident`.
+fn path_segment(iter: &mut Peekable<IntoIter>) -> Option<Ident> {
+    let mut lookahead = iter.clone();
+    match (lookahead.next(), lookahead.next(), lookahead.next()) {
+        (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)), Some(TokenTree::Ident(name)))
+            if a.as_char() == ':' && a.spacing() == Spacing::Joint && b.as_char() == ':' =>
+        {
+            *iter = lookahead;
+            Some(name)
+        }
+        _ => None,
+    }
+}
+
+/// Import sigils, `$ident` or `#ident`.
+fn is_sigil(c: char) -> bool {
+    c == '$' || c == '#'
+}
+
+pub fn is_naga_oil_name(name: &Ident) -> bool {
+    name == "define_import_path"
+        || name == "define"
+        || name == "import"
+        || name == "if"
+        || name == "ifdef"
+        || name == "ifndef"
+        || name == "else"
+        || name == "endif"
+}
+
+/// Replace `$$` and `##` with [`DOLLAR`] and [`HASH`], exported items are escaped
+/// before they are put in a `macro_rules!`, where `$$` is unstable.
+pub fn escape_sigils(stream: TokenStream) -> TokenStream {
+    let mut result = Vec::new();
+    let mut iter = stream.into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        match tt {
+            TokenTree::Punct(p)
+                if is_sigil(p.as_char())
+                    && matches!(iter.peek(), Some(TokenTree::Punct(q)) if q.as_char() == p.as_char()) =>
+            {
+                iter.next();
+                let name = match p.as_char() {
+                    '$' => DOLLAR,
+                    _ => HASH,
+                };
+                result.push(TokenTree::Ident(Ident::new(name, p.span())));
+            }
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), escape_sigils(g.stream()));
+                group.set_span(g.span());
+                result.push(TokenTree::Group(group));
+            }
+            tt => result.push(tt),
+        }
+    }
+    TokenStream::from_iter(result)
+}
+
+/// Remove literal `$`s and `#`s from the stream, they are not part of the validated source.
+///
+/// Returns `true` if any were removed.
+pub fn strip_escaped(stream: TokenStream) -> (TokenStream, bool) {
+    let mut found = false;
+    let stream = stream
+        .into_iter()
+        .filter_map(|tt| match tt {
+            TokenTree::Ident(i) if i == DOLLAR || i == HASH => {
+                found = true;
+                None
+            }
+            TokenTree::Group(g) => {
+                let (stream, escaped) = strip_escaped(g.stream());
+                found |= escaped;
+                let mut group = Group::new(g.delimiter(), stream);
+                group.set_span(g.span());
+                Some(TokenTree::Group(group))
+            }
+            tt => Some(tt),
+        })
+        .collect();
+    (stream, found)
+}
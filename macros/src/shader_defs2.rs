@@ -0,0 +1,75 @@
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Parse `$(#[attr])* NAME $(: type = value)?` separated by commas.
+pub fn shader_defs2(stream: TokenStream) -> TokenStream {
+    let mut inserts = Vec::new();
+    let mut segment = Vec::new();
+    for tt in stream {
+        match &tt {
+            TokenTree::Punct(p) if p.as_char() == ',' => {
+                inserts.push(shader_def(std::mem::take(&mut segment)));
+            }
+            _ => segment.push(tt),
+        }
+    }
+    if !segment.is_empty() {
+        inserts.push(shader_def(segment));
+    }
+    quote! {{
+        let mut defs = ::std::collections::HashMap::<
+            ::std::string::String,
+            ::naga_oil::compose::ShaderDefValue,
+        >::new();
+        #(#inserts)*
+        defs
+    }}
+}
+
+fn shader_def(tokens: Vec<TokenTree>) -> TokenStream {
+    let mut attrs = Vec::new();
+    let mut iter = tokens.into_iter();
+    let name = loop {
+        match iter.next() {
+            Some(TokenTree::Punct(p)) if p.as_char() == '#' => {
+                attrs.push(TokenTree::Punct(p));
+                match iter.next() {
+                    Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Bracket => {
+                        attrs.push(TokenTree::Group(g))
+                    }
+                    _ => abort!(Span::call_site(), "Expected `#[..]`."),
+                }
+            }
+            Some(TokenTree::Ident(name)) => break name,
+            Some(tt) => abort!(tt.span(), "Expected a shader def name."),
+            None => abort!(Span::call_site(), "Expected a shader def name."),
+        }
+    };
+    let name_str = name.to_string();
+    let value = match iter.next() {
+        None => quote! {::naga_oil::compose::ShaderDefValue::Bool(true)},
+        Some(TokenTree::Punct(p)) if p.as_char() == ':' => {
+            let ty = match iter.next() {
+                Some(TokenTree::Ident(ty)) => ty,
+                _ => abort!(p.span(), "Expected `bool`, `i32` or `u32`."),
+            };
+            match iter.next() {
+                Some(TokenTree::Punct(p)) if p.as_char() == '=' => (),
+                _ => abort!(ty.span(), "Expected `= value`."),
+            }
+            let expr: TokenStream = iter.collect();
+            match ty.to_string().as_str() {
+                "bool" => quote! {::naga_oil::compose::ShaderDefValue::Bool(#expr)},
+                "i32" => quote! {::naga_oil::compose::ShaderDefValue::Int(#expr)},
+                "u32" => quote! {::naga_oil::compose::ShaderDefValue::UInt(#expr)},
+                _ => abort!(ty.span(), "Expected `bool`, `i32` or `u32`."),
+            }
+        }
+        Some(tt) => abort!(tt.span(), "Expected `: type = value`."),
+    };
+    quote! {
+        #(#attrs)*
+        defs.insert(::std::string::String::from(#name_str), #value);
+    }
+}
@@ -0,0 +1,111 @@
+use std::{error::Error, path::PathBuf};
+
+use naga::{
+    back::wgsl::WriterFlags,
+    front::spv::{parse_u8_slice, Options},
+    valid::{Capabilities, ValidationFlags, Validator},
+};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote;
+
+use crate::{level::Level, options::string_literal, wgsl2::validate};
+
+/// Validate a SPIR-V binary with naga's SPIR-V frontend,
+/// `wgsl_from_spirv!(include_bytes!("path"))`.
+///
+/// Emits the bytes, or the decompiled WGSL with `#![wgsl]`.
+pub fn wgsl_from_spirv2(stream: TokenStream) -> TokenStream {
+    let mut wgsl = false;
+    let mut tokens = stream.into_iter().peekable();
+    while matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '#') {
+        let hash = tokens.next().unwrap();
+        match (tokens.next(), tokens.next()) {
+            (Some(TokenTree::Punct(bang)), Some(TokenTree::Group(group)))
+                if bang.as_char() == '!' && group.stream().to_string() == "wgsl" =>
+            {
+                wgsl = true
+            }
+            _ => abort!(hash.span(), "Expected `#![wgsl]`."),
+        }
+    }
+    let error = || -> ! {
+        abort!(
+            Span::call_site(),
+            "Expected `wgsl_from_spirv!(include_bytes!(\"path\"))`."
+        )
+    };
+    let lit = match (tokens.next(), tokens.next(), tokens.next()) {
+        (
+            Some(TokenTree::Ident(name)),
+            Some(TokenTree::Punct(bang)),
+            Some(TokenTree::Group(args)),
+        ) if name == "include_bytes" && bang.as_char() == '!' => {
+            match args.stream().into_iter().next() {
+                Some(TokenTree::Literal(lit)) => lit,
+                _ => error(),
+            }
+        }
+        _ => error(),
+    };
+    let Some(path) = string_literal(&lit) else {
+        error()
+    };
+    // Relative to the calling file like `include_bytes!`.
+    let dir = match lit.span().unwrap().local_file() {
+        Some(file) => file.parent().map(PathBuf::from).unwrap_or_default(),
+        None => PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default()),
+    };
+    let bytes = match std::fs::read(dir.join(&path)) {
+        Ok(bytes) => bytes,
+        Err(e) => abort!(lit.span(), "Unable to read \"{}\": {}", path, e),
+    };
+    // `include_bytes!` rebuilds the crate if the binary changes.
+    let include = quote! {::core::include_bytes!(#lit)};
+    let level = Level::get(None);
+    if level == Level::Off && !wgsl {
+        return quote! {#include};
+    }
+    let module = match parse_u8_slice(&bytes, &Options::default()) {
+        Ok(module) => module,
+        Err(e) => abort!(lit.span(), "Spir-V Error: {}", e),
+    };
+    if level == Level::Parse && !wgsl {
+        return quote! {#include};
+    }
+    let info = match validate(&module) {
+        Ok(info) => info,
+        Err(e) => abort!(lit.span(), "Spir-V Error: {}", message(e.as_inner())),
+    };
+    if level == Level::Strict {
+        let mut strict = Validator::new(ValidationFlags::all(), Capabilities::default());
+        if let Err(e) = strict.validate(&module) {
+            abort!(
+                lit.span(),
+                "Spir-V Error: {}, `WGSL_LN_VALIDATE=strict` only allows the default capabilities.",
+                message(e.as_inner())
+            )
+        }
+    }
+    if !wgsl {
+        return quote! {#include};
+    }
+    match naga::back::wgsl::write_string(&module, &info, WriterFlags::empty()) {
+        Ok(output) => quote! {{
+            const _: &[u8] = #include;
+            #output
+        }},
+        Err(e) => abort!(lit.span(), "Unable to decompile to WGSL: {}", e),
+    }
+}
+
+/// An error with its sources, i.e. the missing capability.
+fn message(mut error: &dyn Error) -> String {
+    let mut message = error.to_string();
+    while let Some(source) = error.source() {
+        message.push_str(": ");
+        message.push_str(&source.to_string());
+        error = source;
+    }
+    message
+}
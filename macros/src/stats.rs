@@ -0,0 +1,48 @@
+use naga::{Block, Function, Module, Statement};
+
+/// Function, global and approximate instruction counts of a shader for `#![stats]`.
+///
+/// Instructions are counted as expressions and statements other than `Emit` in the naga IR.
+pub fn stats(module: &Module) -> String {
+    let functions = module
+        .functions
+        .iter()
+        .map(|(_, function)| function)
+        .chain(module.entry_points.iter().map(|entry| &entry.function));
+    let instructions: usize = functions.map(instructions).sum();
+    format!(
+        "functions: {}, entry points: {}, globals: {}, constants: {}, overrides: {}, types: {}, instructions: ~{}",
+        module.functions.len(),
+        module.entry_points.len(),
+        module.global_variables.len(),
+        module.constants.len(),
+        module.overrides.len(),
+        module.types.len(),
+        instructions,
+    )
+}
+
+fn instructions(function: &Function) -> usize {
+    function.expressions.len() + statements(&function.body)
+}
+
+fn statements(block: &Block) -> usize {
+    block
+        .iter()
+        .map(|statement| match statement {
+            Statement::Emit(_) => 0,
+            Statement::Block(block) => statements(block),
+            Statement::If { accept, reject, .. } => 1 + statements(accept) + statements(reject),
+            Statement::Switch { cases, .. } => {
+                1 + cases
+                    .iter()
+                    .map(|case| statements(&case.body))
+                    .sum::<usize>()
+            }
+            Statement::Loop {
+                body, continuing, ..
+            } => 1 + statements(body) + statements(continuing),
+            _ => 1,
+        })
+        .sum()
+}
@@ -0,0 +1,128 @@
+use naga::{ImageClass, Module, StorageAccess, StorageFormat, TypeInner};
+use proc_macro2::Span;
+
+use crate::{level::Level, wgsl2::span_at};
+
+/// Storage texture formats and access modes WebGPU does not support.
+///
+/// [`Level::Full`] allows the formats of WebGPU's optional storage format features,
+/// [`Level::Strict`] only the formats of core WebGPU.
+pub fn storage_format_errors(
+    module: &Module,
+    source: &str,
+    spans: &[(usize, Span)],
+    level: Level,
+) -> Vec<(Span, String)> {
+    let mut errors = Vec::new();
+    for (handle, var) in module.global_variables.iter() {
+        let ty = match module.types[var.ty].inner {
+            TypeInner::BindingArray { base, .. } => base,
+            _ => var.ty,
+        };
+        let TypeInner::Image {
+            class: ImageClass::Storage { format, access },
+            ..
+        } = module.types[ty].inner
+        else {
+            continue;
+        };
+        let (name, core, read_write) = storage_format(format);
+        let error = if access == StorageAccess::LOAD | StorageAccess::STORE {
+            match read_write {
+                ReadWrite::Core => None,
+                ReadWrite::Optional if level < Level::Strict => None,
+                ReadWrite::Optional => Some(format!(
+                    "`read_write` storage textures of `{}` are not supported by core WebGPU, only `r32float`, `r32sint` and `r32uint`.",
+                    name
+                )),
+                ReadWrite::No => Some(format!(
+                    "`read_write` storage textures of `{}` are not supported by WebGPU.",
+                    name
+                )),
+            }
+        } else if format == StorageFormat::Bgra8Unorm && access.contains(StorageAccess::LOAD) {
+            Some("`bgra8unorm` storage textures only support `write` access.".to_owned())
+        } else if !core && level == Level::Strict {
+            Some(format!(
+                "`{}` storage textures are not supported by core WebGPU.",
+                name
+            ))
+        } else {
+            None
+        };
+        let Some(error) = error else {
+            continue;
+        };
+        // Spanned at the format token of the declaration.
+        let span = match module.global_variables.get_span(handle).to_range() {
+            Some(range) if range.end <= source.len() => {
+                let declaration = &source[range.clone()];
+                let offset = declaration
+                    .find("texture_storage")
+                    .and_then(|start| Some(start + declaration[start..].find(name)?))
+                    .unwrap_or(0);
+                span_at(spans, range.start + offset)
+            }
+            _ => Span::call_site(),
+        };
+        errors.push((span, error));
+    }
+    errors
+}
+
+/// Formats supporting `read_write` access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReadWrite {
+    Core,
+    Optional,
+    No,
+}
+
+/// WGSL name of a format, if core WebGPU supports it as a storage texture
+/// and whether it supports `read_write` access.
+fn storage_format(format: StorageFormat) -> (&'static str, bool, ReadWrite) {
+    use ReadWrite::*;
+    use StorageFormat as F;
+    match format {
+        F::R8Unorm => ("r8unorm", false, Optional),
+        F::R8Snorm => ("r8snorm", false, No),
+        F::R8Uint => ("r8uint", false, Optional),
+        F::R8Sint => ("r8sint", false, Optional),
+        F::R16Unorm => ("r16unorm", false, No),
+        F::R16Snorm => ("r16snorm", false, No),
+        F::R16Uint => ("r16uint", false, Optional),
+        F::R16Sint => ("r16sint", false, Optional),
+        F::R16Float => ("r16float", false, Optional),
+        F::Rg8Unorm => ("rg8unorm", false, No),
+        F::Rg8Snorm => ("rg8snorm", false, No),
+        F::Rg8Uint => ("rg8uint", false, No),
+        F::Rg8Sint => ("rg8sint", false, No),
+        F::R32Uint => ("r32uint", true, Core),
+        F::R32Sint => ("r32sint", true, Core),
+        F::R32Float => ("r32float", true, Core),
+        F::Rg16Unorm => ("rg16unorm", false, No),
+        F::Rg16Snorm => ("rg16snorm", false, No),
+        F::Rg16Uint => ("rg16uint", false, No),
+        F::Rg16Sint => ("rg16sint", false, No),
+        F::Rg16Float => ("rg16float", false, No),
+        F::Rgba8Unorm => ("rgba8unorm", true, Optional),
+        F::Rgba8Snorm => ("rgba8snorm", true, No),
+        F::Rgba8Uint => ("rgba8uint", true, Optional),
+        F::Rgba8Sint => ("rgba8sint", true, Optional),
+        F::Bgra8Unorm => ("bgra8unorm", false, No),
+        F::Rgb10a2Uint => ("rgb10a2uint", false, No),
+        F::Rgb10a2Unorm => ("rgb10a2unorm", false, No),
+        F::Rg11b10Float => ("rg11b10float", false, No),
+        F::Rg32Uint => ("rg32uint", true, No),
+        F::Rg32Sint => ("rg32sint", true, No),
+        F::Rg32Float => ("rg32float", true, No),
+        F::Rgba16Unorm => ("rgba16unorm", false, No),
+        F::Rgba16Snorm => ("rgba16snorm", false, No),
+        F::Rgba16Uint => ("rgba16uint", true, Optional),
+        F::Rgba16Sint => ("rgba16sint", true, Optional),
+        F::Rgba16Float => ("rgba16float", true, Optional),
+        F::Rgba32Uint => ("rgba32uint", true, Optional),
+        F::Rgba32Sint => ("rgba32sint", true, Optional),
+        F::Rgba32Float => ("rgba32float", true, Optional),
+    }
+}
@@ -0,0 +1,295 @@
+use std::fmt::Write;
+
+use proc_macro2::{Delimiter, Group, Spacing, Span, TokenStream, TokenTree};
+
+use proc_macro_error::abort;
+
+use crate::{
+    open_close::{close, open},
+    options::{string_literal, string_value},
+    sanitize::{DOLLAR, HASH},
+};
+
+/// State of a `naga_oil` directive, directives must be on their own line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Directive {
+    None,
+    /// After `#`.
+    Start,
+    /// After `#else`, optionally followed by `ifdef`, `ifndef` or `if`.
+    Else,
+    /// Number of remaining words on the line, i.e. `#if DEF == 2` has 3 words after `if`.
+    Words(usize),
+    /// Path of `#define_import_path`, `#import` or `import`, number of `:` since the last segment.
+    Path(usize),
+    /// After `as` in `#import a::b as c`.
+    Alias,
+    /// End of an `#import`, the line ends unless followed by `;`.
+    End,
+}
+
+impl Directive {
+    /// Update the state before writing a token, ending the line if the token is not
+    /// part of the directive.
+    fn before(&mut self, token: &TokenTree, ident: Option<&str>, string: &mut String) {
+        let is_colon = matches!(token, TokenTree::Punct(p) if p.as_char() == ':');
+        let is_semi = matches!(token, TokenTree::Punct(p) if p.as_char() == ';');
+        *self = match (*self, ident) {
+            (Directive::Start | Directive::Else, Some("ifdef" | "ifndef")) => Directive::Words(1),
+            (Directive::Start | Directive::Else, Some("if")) => Directive::Words(3),
+            (Directive::Start, Some("else")) => Directive::Else,
+            (Directive::Start, Some("endif")) => Directive::Words(0),
+            (Directive::Start, Some("define_import_path" | "import")) => Directive::Path(2),
+            (Directive::Start, _) => Directive::None,
+            (Directive::Words(n), _) => match token {
+                // Operators like `==` are one word.
+                TokenTree::Punct(p) if p.spacing() == Spacing::Joint => Directive::Words(n),
+                _ => Directive::Words(n.saturating_sub(1)),
+            },
+            (Directive::Path(0), Some("as")) => Directive::Alias,
+            (Directive::Path(2), Some(_)) => Directive::Path(0),
+            // `#import "embedded://shader.wgsl"::item`
+            (Directive::Path(2), None) if matches!(token, TokenTree::Literal(_)) => {
+                Directive::Path(0)
+            }
+            (Directive::Path(n), None) if is_colon => Directive::Path(n + 1),
+            // `#import a::{b, c}`
+            (Directive::Path(2), None) if matches!(token, TokenTree::Group(_)) => Directive::End,
+            (Directive::Alias, Some(_)) => Directive::End,
+            (Directive::Path(_) | Directive::End, None) if is_semi => Directive::None,
+            (Directive::Else | Directive::Path(_) | Directive::Alias | Directive::End, _) => {
+                string.push('\n');
+                Directive::None
+            }
+            // WESL `import a::{b, c};`
+            (Directive::None, Some("import")) => Directive::Path(2),
+            (Directive::None, _) => Directive::None,
+        };
+    }
+
+    /// End the line after the last word of a directive.
+    fn after(&mut self, string: &mut String) {
+        if *self == Directive::Words(0) {
+            string.push('\n');
+            *self = Directive::None;
+        }
+    }
+}
+
+/// Convert to `wgsl`.
+/// This has to format in a certain way to make `naga_oil` work:
+///
+/// * Linebreaks after `;` and `}`.
+/// * Linebreaks before `#`.
+/// * Linebreaks after directives like `#ifdef DEF`, `#else ifdef DEF`, `#if DEF == 2`,
+///   `#import a::b` and `#endif`.
+/// * Import lists like `#import a::{b, c as d}` on a single line.
+/// * No space after `#`.
+/// * No spaces before and after `:`.
+pub fn to_wgsl_string(stream: TokenStream, spans: &mut Vec<(usize, Span)>, string: &mut String) {
+    let mut directive = Directive::None;
+    // Rust lexes some WGSL literals as several tokens, `.5` as `.` and `5`, `0x1p-2` as `0x1p`,
+    // `-` and `2`. The space after `.`, `p` or the sign is deferred until the next token.
+    let mut pending = None;
+    let mut tokens = stream.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        // Converted once, comparing a compiler `Ident` to a `&str` converts it every time.
+        let ident = match &token {
+            TokenTree::Ident(i) => Some(i.to_string()),
+            _ => None,
+        };
+        let ident = ident.as_deref();
+        directive.before(&token, ident, string);
+        // `ident!("name")` or `note!("text")`, consumed here so `!` and the arguments are not written.
+        let args = match ident {
+            Some("ident" | "note") => macro_args(tokens.clone()),
+            _ => None,
+        };
+        if args.is_some() {
+            tokens.next();
+            tokens.next();
+        }
+        let joined = match (pending.take(), &token) {
+            (Some('.'), TokenTree::Literal(l)) => {
+                l.to_string().starts_with(|c: char| c.is_ascii_digit())
+            }
+            (Some('p'), TokenTree::Punct(p)) => matches!(p.as_char(), '+' | '-'),
+            (Some('-'), TokenTree::Literal(_)) => true,
+            (Some(_), _) => {
+                string.push(' ');
+                false
+            }
+            (None, _) => false,
+        };
+        match token {
+            TokenTree::Ident(i) if ident == Some("ident") && args.is_some() => {
+                spans.push((string.len(), i.span()));
+                let name = args.and_then(|g| match Vec::from_iter(g.stream()).as_slice() {
+                    [TokenTree::Literal(lit)] => string_literal(lit),
+                    _ => None,
+                });
+                match name {
+                    Some(name) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                        string.push_str(&name);
+                        string.push(' ');
+                    }
+                    _ => abort!(i.span(), "Expected `ident!(\"name\")`."),
+                }
+            }
+            TokenTree::Ident(i) if ident == Some("note") && args.is_some() => {
+                let text = args.and_then(|g| match Vec::from_iter(g.stream()).as_slice() {
+                    [TokenTree::Literal(lit)] => string_value(lit),
+                    _ => None,
+                });
+                let Some(text) = text else {
+                    abort!(i.span(), "Expected `note!(\"comment\")`.")
+                };
+                // A comment ends its line, so it starts on a new one.
+                if !string.is_empty() && !string.ends_with('\n') {
+                    string.push('\n');
+                }
+                spans.push((string.len(), i.span()));
+                for line in text.lines() {
+                    string.push_str("// ");
+                    string.push_str(line);
+                    string.push('\n');
+                }
+            }
+            // `$$` or `##`, usually followed by a placeholder name like `$value`.
+            TokenTree::Ident(i) if ident == Some(DOLLAR) || ident == Some(HASH) => {
+                spans.push((string.len(), i.span()));
+                string.push(if ident == Some(DOLLAR) { '$' } else { '#' });
+            }
+            TokenTree::Ident(i) => {
+                spans.push((string.len(), i.span()));
+                let name = ident.unwrap_or_default();
+                // Raw identifiers like `r#type` name WGSL identifiers that are rust keywords.
+                string.push_str(name.strip_prefix("r#").unwrap_or(name));
+                string.push(' ');
+            }
+            TokenTree::Punct(p) => {
+                spans.push((string.len(), p.span()));
+                if p.as_char() == ';' {
+                    string.push(p.as_char());
+                    string.push('\n');
+                } else if p.as_char() == '#' {
+                    // new line and no spaces for naga_oil
+                    string.push('\n');
+                    string.push(p.as_char());
+                    directive = Directive::Start;
+                } else if p.as_char() == ':' {
+                    // bend over backwards for `naga_oil` :p
+                    if string.ends_with(' ') {
+                        string.pop();
+                    }
+                    string.push(p.as_char());
+                } else if joined {
+                    // Sign of a hex float exponent.
+                    string.push(p.as_char());
+                    pending = Some('-');
+                } else if p.as_char() == '.' && p.spacing() == Spacing::Alone {
+                    string.push(p.as_char());
+                    pending = Some('.');
+                } else if p.spacing() == Spacing::Alone {
+                    string.push(p.as_char());
+                    string.push(' ');
+                } else {
+                    string.push(p.as_char());
+                }
+            }
+            TokenTree::Literal(l) => {
+                spans.push((string.len(), l.span()));
+                let start = string.len();
+                let _ = write!(string, "{}", l);
+                let literal = &string[start..];
+                match (literal.get(..2), literal.chars().last()) {
+                    (Some("0x" | "0X"), Some('p' | 'P')) => pending = Some('p'),
+                    _ => string.push(' '),
+                }
+            }
+            TokenTree::Group(g) if directive == Directive::End => {
+                spans.push((string.len(), g.delim_span().open()));
+                import_list(g, spans, string);
+            }
+            TokenTree::Group(g) => {
+                spans.push((string.len(), g.delim_span().open()));
+                string.push(open(g.delimiter()));
+                if g.delimiter() == Delimiter::Brace {
+                    string.push('\n')
+                }
+                to_wgsl_string(g.stream(), spans, string);
+                spans.push((string.len(), g.delim_span().close()));
+                string.push(close(g.delimiter()));
+                if g.delimiter() == Delimiter::Brace {
+                    string.push('\n')
+                }
+            }
+        }
+        directive.after(string);
+    }
+    if pending.is_some() {
+        string.push(' ');
+    }
+    if directive != Directive::None {
+        string.push('\n');
+    }
+}
+
+/// The arguments if the tokens after a name are `!(..)`, a call like `ident!("name")`,
+/// not a comparison like `ident != 0`.
+pub fn macro_args(mut after: impl Iterator<Item = TokenTree>) -> Option<Group> {
+    match (after.next(), after.next()) {
+        (Some(TokenTree::Punct(p)), Some(TokenTree::Group(g)))
+            if p.as_char() == '!'
+                && p.spacing() == Spacing::Alone
+                && g.delimiter() == Delimiter::Parenthesis =>
+        {
+            Some(g)
+        }
+        _ => None,
+    }
+}
+
+/// Number of tokens including the tokens of groups, to pre-size the output and its spans.
+pub fn token_count(stream: &TokenStream) -> usize {
+    stream
+        .clone()
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(g) => 1 + token_count(&g.stream()),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Write the braced list of `#import a::{b, c as d}` without linebreaks.
+fn import_list(group: Group, spans: &mut Vec<(usize, Span)>, string: &mut String) {
+    string.push(open(group.delimiter()));
+    for token in group.stream() {
+        spans.push((string.len(), token.span()));
+        match token {
+            TokenTree::Group(g) => import_list(g, spans, string),
+            TokenTree::Punct(p) if p.as_char() == ':' || p.as_char() == ',' => {
+                if string.ends_with(' ') {
+                    string.pop();
+                }
+                string.push(p.as_char());
+                if p.as_char() == ',' {
+                    string.push(' ');
+                }
+            }
+            TokenTree::Ident(i) => {
+                let name = i.to_string();
+                string.push_str(name.strip_prefix("r#").unwrap_or(&name));
+                string.push(' ');
+            }
+            tt => {
+                let _ = write!(string, "{} ", tt);
+            }
+        }
+    }
+    if string.ends_with(' ') {
+        string.pop();
+    }
+    string.push(close(group.delimiter()));
+}
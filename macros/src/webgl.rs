@@ -0,0 +1,112 @@
+use naga::{
+    back::glsl::{Options, PipelineOptions, Version, Writer, WriterFlags},
+    proc::BoundsCheckPolicies,
+    Handle, Module, ShaderStage,
+};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+
+use crate::wgsl2::validate;
+
+/// Translate the vertex and fragment entry points to GLSL ES 3.00 for WebGL2,
+/// as `wgsl_ln_runtime::GlslEntryPoint`s.
+///
+/// Entry points naga cannot translate are skipped with a warning.
+pub fn glsl_es(module: &Module) -> (TokenStream, TokenStream) {
+    let Ok(info) = validate(module) else {
+        return (quote! {&[]}, TokenStream::new());
+    };
+    let options = Options {
+        version: Version::Embedded {
+            version: 300,
+            is_webgl: true,
+        },
+        // WebGL clip space depth is -1 to 1.
+        writer_flags: WriterFlags::ADJUST_COORDINATE_SPACE,
+        ..Default::default()
+    };
+    let mut entry_points = Vec::new();
+    let mut warnings = TokenStream::new();
+    for entry in &module.entry_points {
+        let stage = match entry.stage {
+            ShaderStage::Vertex => quote! {VERTEX},
+            ShaderStage::Fragment => quote! {FRAGMENT},
+            ShaderStage::Compute => continue,
+        };
+        let pipeline = PipelineOptions {
+            shader_stage: entry.stage,
+            entry_point: entry.name.clone(),
+            multiview: None,
+        };
+        let mut source = String::new();
+        let reflection = Writer::new(
+            &mut source,
+            module,
+            &info,
+            &options,
+            &pipeline,
+            BoundsCheckPolicies::default(),
+        )
+        .and_then(|mut writer| writer.write());
+        let reflection = match reflection {
+            Ok(reflection) => reflection,
+            Err(e) => {
+                let message = format!("`{}` has no GLSL ES 3.00 translation: {}", entry.name, e);
+                warnings.extend(quote_spanned! {Span::call_site()=>
+                    const _: () = {
+                        #[deprecated(note = #message)]
+                        const WEBGL_WARNING: () = ();
+                        WEBGL_WARNING
+                    };
+                });
+                continue;
+            }
+        };
+        let binding = |handle: Handle<naga::GlobalVariable>| {
+            let binding = module.global_variables[handle].binding.as_ref();
+            binding.map_or((0, 0), |x| (x.group, x.binding))
+        };
+        let mut uniforms: Vec<_> = reflection
+            .uniforms
+            .iter()
+            .map(|(handle, name)| (binding(*handle), name))
+            .collect();
+        uniforms.sort();
+        let uniforms = uniforms.into_iter().map(|((group, binding), name)| {
+            quote! {
+                ::wgsl_ln::runtime::GlslUniform {
+                    name: #name,
+                    group: #group,
+                    binding: #binding,
+                }
+            }
+        });
+        let mut textures: Vec<_> = reflection.texture_mapping.iter().collect();
+        textures.sort_by_key(|(name, _)| *name);
+        let textures = textures.into_iter().map(|(name, mapping)| {
+            let (texture_group, texture_binding) = binding(mapping.texture);
+            let sampler = match mapping.sampler.map(binding) {
+                Some((group, binding)) => quote! {Some((#group, #binding))},
+                None => quote! {None},
+            };
+            quote! {
+                ::wgsl_ln::runtime::GlslTexture {
+                    name: #name,
+                    texture: (#texture_group, #texture_binding),
+                    sampler: #sampler,
+                }
+            }
+        });
+        let name = &entry.name;
+        entry_points.push(quote! {
+            ::wgsl_ln::runtime::GlslEntryPoint {
+                entry_point: #name,
+                stage: ::wgsl_ln::runtime::wgpu::ShaderStages::#stage,
+                source: #source,
+                uniforms: &[#(#uniforms),*],
+                textures: &[#(#textures),*],
+            }
+        });
+    }
+    (quote! {&[#(#entry_points),*]}, warnings)
+}
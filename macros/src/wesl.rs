@@ -0,0 +1,134 @@
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote_spanned;
+
+/// Find the first top level WESL `import package::item;`, remove it and return `item`.
+///
+/// Items in `package` are pasted like `$item`, other items in a braced list
+/// are kept as separate `import package::..;` statements.
+pub fn package_import(stream: TokenStream) -> (TokenStream, Option<Ident>) {
+    let mut result = Vec::new();
+    let mut iter = stream.into_iter();
+    // `naga_oil`'s `#import`.
+    let mut last_is_hash = false;
+    while let Some(tt) = iter.next() {
+        let is_hash = matches!(&tt, TokenTree::Punct(p) if p.as_char() == '#');
+        match &tt {
+            TokenTree::Ident(i) if i == "import" && !last_is_hash => {
+                let statement = statement(i, &mut iter);
+                if !matches!(statement.first(), Some(TokenTree::Ident(i)) if i == "package") {
+                    let semi = quote_spanned!(i.span()=> ;);
+                    result.push(tt);
+                    result.extend(statement);
+                    result.extend(semi);
+                    continue;
+                }
+                let mut leaves = Vec::new();
+                import_leaves(statement, Vec::new(), &mut leaves);
+                let mut leaves = leaves.into_iter();
+                let Some(first) = leaves.next().and_then(|mut x| x.pop()) else {
+                    abort!(i.span(), "Expected `import package::item;`.")
+                };
+                for leaf in leaves {
+                    let span = i.span();
+                    result.extend(quote_spanned!(span=> import #(#leaf)::*;));
+                }
+                result.extend(iter);
+                return (TokenStream::from_iter(result), Some(first));
+            }
+            _ => result.push(tt),
+        }
+        last_is_hash = is_hash;
+    }
+    (TokenStream::from_iter(result), None)
+}
+
+/// Remove every top level WESL `import package::..;` and return the items in order.
+pub fn package_imports(mut stream: TokenStream) -> (TokenStream, Vec<Ident>) {
+    let mut result = Vec::new();
+    loop {
+        match package_import(stream) {
+            (rest, Some(item)) => {
+                result.push(item);
+                stream = rest;
+            }
+            (rest, None) => return (rest, result),
+        }
+    }
+}
+
+/// Split top level WESL `import ..;` statements out of the stream,
+/// these are passed through to a WESL runtime.
+pub fn extract_imports(stream: TokenStream) -> (TokenStream, TokenStream) {
+    let mut result = Vec::new();
+    let mut imports = Vec::new();
+    let mut iter = stream.into_iter();
+    // `naga_oil`'s `#import`.
+    let mut last_is_hash = false;
+    while let Some(tt) = iter.next() {
+        let is_hash = matches!(&tt, TokenTree::Punct(p) if p.as_char() == '#');
+        match &tt {
+            TokenTree::Ident(i) if i == "import" && !last_is_hash => {
+                let statement = statement(i, &mut iter);
+                imports.push(tt.clone());
+                imports.extend(statement);
+                imports.extend(quote_spanned!(i.span()=> ;));
+            }
+            _ => result.push(tt),
+        }
+        last_is_hash = is_hash;
+    }
+    (
+        TokenStream::from_iter(result),
+        TokenStream::from_iter(imports),
+    )
+}
+
+/// Tokens of an import statement until `;`.
+fn statement(import: &Ident, iter: &mut impl Iterator<Item = TokenTree>) -> Vec<TokenTree> {
+    let mut statement = Vec::new();
+    loop {
+        match iter.next() {
+            Some(TokenTree::Punct(p)) if p.as_char() == ';' => return statement,
+            Some(tt) => statement.push(tt),
+            None => abort!(import.span(), "Expected `;` after `import`."),
+        }
+    }
+}
+
+/// Flatten `a::{b, c::{d}}` into paths `a::b` and `a::c::d`.
+fn import_leaves(tokens: Vec<TokenTree>, mut path: Vec<Ident>, result: &mut Vec<Vec<Ident>>) {
+    for tt in tokens {
+        match tt {
+            TokenTree::Ident(i) if i == "as" => {
+                abort!(i.span(), "Aliases are not supported in `package` imports.")
+            }
+            TokenTree::Ident(i) => path.push(i),
+            TokenTree::Punct(p) if p.as_char() == ':' => (),
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+                let mut item = Vec::new();
+                for tt in g.stream() {
+                    match tt {
+                        TokenTree::Punct(p) if p.as_char() == ',' => {
+                            import_leaves(std::mem::take(&mut item), path.clone(), result);
+                        }
+                        tt => item.push(tt),
+                    }
+                }
+                if !item.is_empty() {
+                    import_leaves(item, path.clone(), result);
+                }
+                return;
+            }
+            tt => abort!(tt.span(), "Expected a module path."),
+        }
+    }
+    if path.len() < 2 {
+        let span = path
+            .first()
+            .map(|x| x.span())
+            .unwrap_or_else(Span::call_site);
+        abort!(span, "Expected `import package::item;`.")
+    }
+    result.push(path);
+}
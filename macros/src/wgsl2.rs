@@ -0,0 +1,720 @@
+use std::path::{Path, PathBuf};
+
+use std::cell::RefCell;
+
+use naga::{
+    valid::{Capabilities, ModuleInfo, ValidationError, ValidationFlags, Validator},
+    GlobalVariable, Handle, Module, WithSpan,
+};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote, quote_spanned};
+
+#[cfg(feature = "runtime")]
+use crate::reflect::{bindings, entry_points, overrides};
+use crate::{
+    __wgsl_paste2::Chain,
+    config::{apply, preferred, Config},
+    consts::{extract_consts, interpolate},
+    externs::{extern_names, extract_externs},
+    items::{
+        canonical_order, dedup_aliases, item_kind, rename_entry_points, set_constants,
+        split_pasted, strip_notes,
+    },
+    level::{validate_strict, Level},
+    library,
+    limits::Limits,
+    options::{
+        extract_options, flatten_options, has_option, is_oil, join_path, paste_from, Options,
+    },
+    passes::run_passes,
+    portability::browser_lints,
+    preprocess::preprocess,
+    profile::Profile,
+    rename::{namespaced, obfuscate},
+    samplers::sampler_lints,
+    sanitize::{escape_sigils, sanitize, strip_escaped},
+    storage::storage_format_errors,
+    to_wgsl_string::{to_wgsl_string, token_count},
+    wesl::{extract_imports, package_imports},
+    wgsl_export2::{export_option, paste_macros, strip_local_options},
+};
+#[cfg(feature = "naga_oil")]
+use crate::{
+    known_imports::check_imports,
+    oil::{check_defs, validate_oil},
+};
+
+pub fn wgsl2(stream: TokenStream) -> TokenStream {
+    expand(apply(&Config::get(), flatten_options(stream)))
+}
+
+fn expand(stream: TokenStream) -> TokenStream {
+    // `#![export(name)]` in a macro-generated call, the shader is exported like `#[wgsl_export(name)]`.
+    if has_option(&stream, "export") {
+        if let Some((name, stream)) = export_option(stream.clone()) {
+            let source = escape_sigils(strip_local_options(stream.clone()));
+            let macros = paste_macros(&name, &source);
+            let in_item = has_option(&stream, "doc_item");
+            let expr = expand(stream);
+            if in_item {
+                return quote! {#macros #expr};
+            }
+            return quote! {{#macros #expr}};
+        }
+    }
+    let mut profile = Profile::new(&stream);
+    // Items pasted in so far are kept apart from the body, see `Chain`.
+    let (mut chain, first) = match Chain::parse(stream.clone()) {
+        Some(chain) => (chain, false),
+        None => (
+            Chain {
+                defined: Vec::new(),
+                pasted: TokenStream::new(),
+                body: stream,
+                pending: Vec::new(),
+            },
+            true,
+        ),
+    };
+    #[cfg(any(feature = "manifest", feature = "analyzer"))]
+    if first {
+        chain.body = crate::options::with_call_site(chain.body);
+    }
+    let oil = is_oil(&chain.body) || is_oil(&chain.pasted);
+    // In item position, paste macros are in scope of the `#[cfg(doc)]` module.
+    let in_item = has_option(&chain.body, "doc_item");
+    #[cfg(not(feature = "naga_oil"))]
+    if oil {
+        abort!(
+            Span::call_site(),
+            "`naga_oil` mode requires the `naga_oil` feature."
+        )
+    }
+    // Imports of the newest pasted items come first, the body is only sanitized once.
+    // WESL `import package::item;` is equivalent to `$item`.
+    let (pasted, mut pastes) = sanitize(std::mem::take(&mut chain.pasted), oil);
+    let (pasted, imports) = package_imports(pasted);
+    chain.pasted = pasted;
+    pastes.extend(imports);
+    if first {
+        let (body, body_pastes) = sanitize(std::mem::take(&mut chain.body), oil);
+        let (body, imports) = package_imports(body);
+        // Imports of items declared by the shader itself, i.e. from `wgsl_prelude!`, are ignored.
+        let declared = |x: &Ident| item_kind(body.clone(), x).is_some();
+        pastes.extend(
+            body_pastes
+                .into_iter()
+                .chain(imports)
+                .filter(|x| !declared(x)),
+        );
+        chain.body = body;
+    }
+    pastes.append(&mut chain.pending);
+    let mut pending = Vec::new();
+    for paste in pastes {
+        if !chain.defined.contains(&paste) && !pending.contains(&paste) {
+            pending.push(paste);
+        }
+    }
+    profile.phase("sanitize");
+    if !pending.is_empty() {
+        let paste = pending.remove(0);
+        chain.pending = pending;
+        let tokens = chain.to_tokens();
+        profile.shader = format!("${}", paste);
+        // `$krate::name` or `prefer` in `wgsl_ln.toml`, otherwise the name in scope.
+        let krate = paste_from(&chain.pasted, &paste)
+            .or_else(|| paste_from(&chain.body, &paste))
+            .or_else(|| preferred(&Config::get(), &paste));
+        // `$wgsl_ln::name` is pasted from the libraries of this crate.
+        if let Some(krate) = krate.as_ref().filter(|x| *x == "wgsl_ln") {
+            let source = library::source(krate, &paste);
+            return quote! {::wgsl_ln::__wgsl_paste!{#paste {#![origin(#paste, #krate)] #source} #tokens}};
+        }
+        // Spanned at the import, so an export found in two glob imports is reported there.
+        let paste = format_ident!("__wgsl_paste_{}", paste, span = paste.span());
+        let paste = match krate {
+            Some(krate) => quote! {::#krate::#paste},
+            None => quote! {#paste},
+        };
+        if in_item {
+            return quote! {#paste!(wgsl!(#tokens));};
+        }
+        return quote! {{use crate::*; #paste!(wgsl!(#tokens))}};
+    }
+    let original = chain.to_tokens();
+    let Chain {
+        defined,
+        pasted,
+        body,
+        ..
+    } = chain;
+    let (pasted, body) = match has_option(&body, "namespaced") {
+        true => namespaced(&defined, pasted, body),
+        false => (pasted, body),
+    };
+    // An alias can be pasted in by its own export and by another export declaring it.
+    let stream = dedup_aliases(quote! {#pasted #body});
+    #[allow(unused_variables)]
+    let (stream, mut options) = extract_options(stream);
+    profile.shader = match (&options.label, &options.call_site) {
+        (Some(label), _) => label.clone(),
+        (None, Some(call_site)) => call_site.clone(),
+        (None, None) => "-".to_owned(),
+    };
+    #[cfg(not(feature = "wgpu"))]
+    if options.wgpu {
+        abort!(Span::call_site(), "`#![wgpu]` requires the `wgpu` feature.")
+    }
+    #[cfg(not(feature = "runtime"))]
+    if options.shader {
+        abort!(
+            Span::call_site(),
+            "`#![shader]` requires the `runtime` feature."
+        )
+    }
+    #[cfg(not(feature = "round_trip"))]
+    if let Some(span) = options.round_trip {
+        abort!(span, "`#![round_trip]` requires the `round_trip` feature.")
+    }
+    #[cfg(not(feature = "naga_ir"))]
+    if let Some(span) = options.naga_ir {
+        abort!(span, "`#![naga_ir]` requires the `naga_ir` feature.")
+    }
+    #[cfg(not(feature = "gpu_test"))]
+    if let Some(span) = options.gpu_test {
+        abort!(span, "`wgsl_test!` requires the `gpu_test` feature.")
+    }
+    if let (true, Some(span)) = (oil || options.wesl, options.obfuscate) {
+        abort!(
+            span,
+            "`#![obfuscate]` does not support `naga_oil` or WESL mode."
+        )
+    }
+    if let (true, Some(span)) = (oil, options.canonical_order) {
+        abort!(
+            span,
+            "`#![canonical_order]` does not support `naga_oil` mode."
+        )
+    }
+    if let (true, Some(span)) = (oil, options.gpu_test) {
+        abort!(span, "`wgsl_test!` does not support `naga_oil` mode.")
+    }
+    if let (true, Some(span)) = (oil, options.eval) {
+        abort!(span, "`wgsl_eval!` does not support `naga_oil` mode.")
+    }
+    // Paste in modules exported with `#define_import_path`.
+    if let Some(path) = options.unresolved_module() {
+        let module = format_ident!("__wgsl_module_{}", join_path(path).replace("::", "__"));
+        if in_item {
+            return quote! {#module!(wgsl!(#original));};
+        }
+        return quote! {{use crate::*; #module!(wgsl!(#original))}};
+    }
+    #[cfg(feature = "naga_oil")]
+    let mut defs = Vec::new();
+    #[cfg(feature = "naga_oil")]
+    check_defs(&options, stream.clone(), &mut defs);
+    // `$MAX_STEPS = 64u;` replaces the value of an exported constant.
+    let stream = set_constants(stream);
+    let (stream, mut externs) = extract_externs(stream);
+    let extern_names = extern_names(&externs);
+    // Entry points are renamed before validation, so reflection sees the new names.
+    let (stream, unused) =
+        rename_entry_points(stream, &options.renames, options.entry_prefix.as_ref());
+    if let Some(name) = unused.first() {
+        abort!(name.span(), "`{}` is not an entry point.", name)
+    }
+    // `${WG_X}` is validated as a placeholder and written by rustc.
+    let (stream, consts) = extract_consts(stream, &mut externs);
+    options.rust_consts = consts;
+    if !options.rust_consts.exprs.is_empty() {
+        let files = [
+            &options.embedded_asset,
+            &options.hot_reload,
+            &options.out_dir,
+        ];
+        if let Some((_, span)) = files.into_iter().flatten().next() {
+            abort!(span, "Shaders with `${..}` cannot be written to files.")
+        }
+    }
+    // Obfuscated shaders ship without `note!(..)` comments.
+    let (stream, externs) = match options.obfuscate_enabled() {
+        true => obfuscate(strip_notes(stream), externs),
+        false => (stream, externs),
+    };
+    // WESL imports are not validated, items they provide can be declared with `$extern`.
+    let (stream, imports) = extract_imports(stream);
+    let stream = match options.canonical_order_enabled() {
+        true => canonical_order(stream),
+        false => stream,
+    };
+    // `$$` and `##` are written to the output only, placeholders like `$$value` can be declared with `$extern`.
+    let (validated, escaped) = strip_escaped(stream.clone());
+    // A span per token, tokens average about 5 bytes with their spaces.
+    let tokens = token_count(&validated);
+    let mut spans = Vec::with_capacity(tokens);
+    let mut source = String::with_capacity(tokens * 5);
+    to_wgsl_string(validated.clone(), &mut spans, &mut source);
+    // Without imports the output is the source without `$extern` stubs, skip tokenizing it again.
+    let output = match imports.is_empty() && !options.wesl && !escaped {
+        true => source.clone(),
+        false => {
+            let mut output = String::with_capacity(source.len());
+            // Templates list their externs, `substitute` validates the result if it fills them all.
+            if escaped && !extern_names.is_empty() {
+                let names = Vec::from_iter(extern_names.iter().map(Ident::to_string));
+                output.push_str(&format!("// extern: {}\n", names.join(", ")));
+            }
+            to_wgsl_string(imports, &mut Vec::new(), &mut output);
+            // In WESL mode, pasted items are imported from the package instead.
+            let body = match options.wesl {
+                true => {
+                    let (rest, pasted) = split_pasted(stream.clone(), &defined);
+                    let names = pasted.into_iter().map(|(name, _)| name);
+                    to_wgsl_string(
+                        quote! {#(import package::#names;)*},
+                        &mut Vec::new(),
+                        &mut output,
+                    );
+                    rest
+                }
+                false => stream.clone(),
+            };
+            to_wgsl_string(body, &mut Vec::new(), &mut output);
+            output
+        }
+    };
+    // Preprocessed text has no token spans, errors are shown at the call.
+    let preprocessed = match oil {
+        true => None,
+        false => preprocess_all(&options, &output, &source),
+    };
+    let is_preprocessed = preprocessed.is_some();
+    let (output, mut source) = preprocessed.unwrap_or((output, source));
+    if let Some((path, span)) = &options.embedded_asset {
+        write_file(&out_dir(*span).join(path), *span, &output);
+    }
+    if let Some((path, span)) = &options.hot_reload {
+        if !options.shader {
+            abort!(span, "`#![hot_reload(..)]` requires `#![shader]`.")
+        }
+        write_file(&hot_reload_path(path), *span, &output);
+        // Pasted items are replaced by their live sources at runtime, if found in the file.
+        for (name, item) in split_pasted(stream.clone(), &defined).1 {
+            let mut text = String::new();
+            to_wgsl_string(item, &mut Vec::new(), &mut text);
+            let text = text.trim();
+            if !text.is_empty() && output.contains(text) {
+                options.pasted.push((name.to_string(), text.to_owned()));
+            }
+        }
+    }
+    // Printed before formatting, for comparison with the output in `#![round_trip]`.
+    #[cfg(feature = "round_trip")]
+    let tokens = options
+        .round_trip_enabled()
+        .then(|| format!("{}\n{}", validated, externs));
+    #[cfg(feature = "naga_oil")]
+    let (items, stubs) = (validated, externs.clone());
+    // `$extern` stubs are validated but not emitted.
+    to_wgsl_string(externs, &mut spans, &mut source);
+    if is_preprocessed {
+        spans.clear();
+    }
+    profile.phase("tokenize");
+    #[cfg(feature = "naga_oil")]
+    if oil {
+        let warnings = check_imports(&items, &options);
+        let (includes, module) =
+            validate_oil(&options, &defs, &defined, &items, &stubs, &source, &spans);
+        profile.phase("compose");
+        // Spans of the composed module do not match `source`.
+        let expr = emit(&options, &output, module.as_ref(), "", &[]);
+        let expr = quote! {{#warnings #includes #expr}};
+        let expr = notes(&options, &output, module.as_ref(), expr);
+        profile.phase("emit");
+        return doc_item(&options, &output, expr);
+    }
+    let level = Level::get(options.validate);
+    // A strict build does not trust shaders validated with all capabilities.
+    #[cfg(feature = "cache")]
+    let cached = level == Level::Full
+        && Config::get().limits.is_none()
+        && crate::cache::is_validated(&source);
+    #[cfg(not(feature = "cache"))]
+    let cached = false;
+    if (cached || level == Level::Off) && !options.needs_module() {
+        profile.phase(if cached { "cache" } else { "skip" });
+        let expr = emit(&options, &output, None, &source, &spans);
+        let expr = notes(&options, &output, None, expr);
+        return doc_item(&options, &output, expr);
+    }
+    let parsed = naga::front::wgsl::parse_str(&source);
+    profile.phase("parse");
+    match parsed {
+        Ok(module) => {
+            let validated = match cached || level < Level::Full {
+                true => Ok(None),
+                false => validate(&module).map(Some),
+            };
+            if validated.is_ok() && level == Level::Strict {
+                validate_strict(&module, &source, &spans);
+            }
+            if validated.is_ok() && level >= Level::Full {
+                let errors = storage_format_errors(&module, &source, &spans, level);
+                if let Some((span, message)) = errors.into_iter().next() {
+                    abort!(span, "Wgsl Error: {}", message)
+                }
+                // Not cached with limits, so the module was validated here.
+                if let (Some(path), Ok(Some(info))) = (&Config::get().limits, &validated) {
+                    let exceeded = Limits::read(path).exceeded(&module, info, &source, &spans);
+                    if let Some((span, message)) = exceeded.into_iter().next() {
+                        abort!(span, "Wgsl Error: {}", message)
+                    }
+                }
+            }
+            profile.phase("validate");
+            match validated {
+                Ok(info) => {
+                    #[cfg(feature = "cache")]
+                    if level >= Level::Full {
+                        crate::cache::set_validated(&source);
+                    }
+                    // A shader rewritten by a pass is validated again.
+                    let (rewritten, warnings) =
+                        run_passes(&Config::get().passes, options.label.as_deref(), &source);
+                    let (output, source, spans, module, info) = match rewritten {
+                        Some(rewritten) => {
+                            if output != source {
+                                abort!(
+                                    Span::call_site(),
+                                    "Passes cannot rewrite shaders with `$extern`, imports or `$$`."
+                                )
+                            }
+                            let (module, info) = naga::front::wgsl::parse_str(&rewritten)
+                                .map_err(|e| e.to_string())
+                                .and_then(|module| match validate(&module) {
+                                    Ok(info) => Ok((module, info)),
+                                    Err(e) => Err(e.into_inner().to_string()),
+                                })
+                                .unwrap_or_else(|e| {
+                                    abort!(Span::call_site(), "Invalid shader after passes: {}", e)
+                                });
+                            (rewritten.clone(), rewritten, Vec::new(), module, Some(info))
+                        }
+                        None => (output, source, spans, module, info),
+                    };
+                    if options.eval.is_some() {
+                        return crate::eval::eval(&module);
+                    }
+                    #[cfg(feature = "gpu_test")]
+                    if options.gpu_test.is_some() {
+                        return crate::gpu_test::gpu_test(&options, &output, &module);
+                    }
+                    let mut lints = sampler_lints(&module, &source, &spans);
+                    if options.portability_enabled() {
+                        lints.extend(browser_lints(&module, info.as_ref(), &source, &spans));
+                    }
+                    let expr = emit(&options, &output, Some(&module), &source, &spans);
+                    let expr = quote! {{#warnings #lints #expr}};
+                    let expr = notes(&options, &output, Some(&module), expr);
+                    #[cfg(feature = "round_trip")]
+                    let expr =
+                        crate::round_trip::round_trip(&options, tokens.as_deref(), &module, expr);
+                    profile.phase("emit");
+                    doc_item(&options, &output, expr)
+                }
+                Err(e) => {
+                    if let Some((span, _)) = e.spans().next() {
+                        let location = span.location(&source);
+                        abort!(
+                            span_at(&spans, location.offset as usize),
+                            "Wgsl Error: {}",
+                            e
+                        )
+                    }
+                    let e_str = e.to_string();
+                    quote! {compile_error!(#e_str)}
+                }
+            }
+        }
+        Err(e) => {
+            if let Some((span, _)) = e.labels().next() {
+                let location = span.location(&source);
+                abort!(
+                    span_at(&spans, location.offset as usize),
+                    "Wgsl Error: {}",
+                    e
+                )
+            }
+            let e_str = e.to_string();
+            quote! {compile_error!(#e_str)}
+        }
+    }
+}
+
+/// Preprocess the output and the validated source, `None` if neither changed.
+fn preprocess_all(options: &Options, output: &str, source: &str) -> Option<(String, String)> {
+    let config = Config::get();
+    let label = options.label.as_deref();
+    let source_result = preprocess(&config, label, source);
+    let output_result = match output == source {
+        true => source_result.clone(),
+        false => preprocess(&config, label, output),
+    };
+    if source_result.is_none() && output_result.is_none() {
+        return None;
+    }
+    Some((
+        output_result.unwrap_or_else(|| output.to_owned()),
+        source_result.unwrap_or_else(|| source.to_owned()),
+    ))
+}
+
+/// Emit the output as a string, or a `ShaderModuleDescriptor` or `Shader` if requested.
+#[allow(unused_variables)]
+fn emit(
+    options: &Options,
+    output: &str,
+    module: Option<&Module>,
+    source: &str,
+    spans: &[(usize, Span)],
+) -> TokenStream {
+    #[cfg(feature = "manifest")]
+    crate::manifest::record(options, output);
+    crate::dump::dump(options, output, module);
+    #[cfg(any(feature = "analyzer", wgsl_ln_dump))]
+    crate::analyzer::write_shader(options, output);
+    #[cfg(feature = "json")]
+    if let Some(module) = module {
+        crate::json::write_json(options, output, module);
+    }
+    #[cfg(feature = "naga_ir")]
+    if let (Some(span), Some(module)) = (options.naga_ir, module) {
+        let hash = format!("{:032x}", fnv1a(output.as_bytes()));
+        let name = crate::json::file_stem(options.label.as_deref(), &hash);
+        let file = out_dir(span)
+            .join("wgsl_ln")
+            .join(format!("{}.naga.json", name));
+        write_file(&file, span, &crate::ir::to_json(module));
+    }
+    let label = match &options.label {
+        Some(label) => quote! {Some(#label)},
+        None => quote! {None},
+    };
+    // Large shaders are cheaper to compile as `include_str!`.
+    let string = match &options.out_dir {
+        Some((path, span)) => {
+            let path = match path.is_empty() {
+                true => format!("wgsl_ln/{:032x}.wgsl", fnv1a(output.as_bytes())),
+                false => path.clone(),
+            };
+            write_file(&out_dir(*span).join(&path), *span, output);
+            let file = format!("/{}", path);
+            quote! {::core::include_str!(::core::concat!(::core::env!("OUT_DIR"), #file))}
+        }
+        None if !options.rust_consts.exprs.is_empty() => interpolate(&options.rust_consts, output),
+        None => quote! {#output},
+    };
+    if options.wgpu {
+        return quote! {
+            ::wgpu::ShaderModuleDescriptor {
+                label: #label,
+                source: ::wgpu::ShaderSource::Wgsl(::std::borrow::Cow::Borrowed(#string)),
+            }
+        };
+    }
+    #[cfg(feature = "runtime")]
+    if options.shader {
+        let hash = match options.rust_consts.exprs.is_empty() {
+            true => {
+                let hash = fnv1a(output.as_bytes());
+                quote! {#hash}
+            }
+            false => quote! {{
+                const HASH: u128 = ::wgsl_ln::runtime::fnv1a(#string.as_bytes());
+                HASH
+            }},
+        };
+        // Modules composed from `#import`s provided at runtime are not reflected.
+        let (bindings, entry_points, overrides) = match module {
+            Some(module) => (
+                bindings(module, source, spans),
+                entry_points(module, &options.rust_consts),
+                overrides(module),
+            ),
+            None => (quote! {&[]}, quote! {&[]}, quote! {&[]}),
+        };
+        let path = match &options.hot_reload {
+            Some((path, _)) => {
+                let path = hot_reload_path(path).display().to_string();
+                quote! {Some(#path)}
+            }
+            None => quote! {None},
+        };
+        let pasted_names = options.pasted.iter().map(|(name, _)| name);
+        let pasted_sources = options.pasted.iter().map(|(_, source)| source);
+        #[cfg(feature = "webgl")]
+        let (glsl_es, warnings) = match module {
+            Some(module) => crate::webgl::glsl_es(module),
+            None => (quote! {&[]}, TokenStream::new()),
+        };
+        #[cfg(not(feature = "webgl"))]
+        let (glsl_es, warnings) = (quote! {&[]}, TokenStream::new());
+        return quote! {{
+            #warnings
+            ::wgsl_ln::runtime::Shader {
+                label: #label,
+                source: #string,
+                hash: #hash,
+                path: #path,
+                pasted: &[#(::wgsl_ln::runtime::PastedItem {
+                    name: #pasted_names,
+                    source: #pasted_sources,
+                }),*],
+                bindings: #bindings,
+                entry_points: #entry_points,
+                overrides: #overrides,
+                glsl_es: #glsl_es,
+            }
+        }};
+    }
+    string
+}
+
+/// For `#![dump]` and `#![stats]`, show the output and statistics as warnings.
+///
+/// Statistics of every shader are shown if `WGSL_LN_STATS` is set.
+fn notes(
+    options: &Options,
+    output: &str,
+    module: Option<&Module>,
+    expr: TokenStream,
+) -> TokenStream {
+    let mut notes = Vec::new();
+    if let Some(span) = options.dump {
+        notes.push((span, format!("`#![dump]`\n{}", output.trim())));
+    }
+    let stats = match options.stats {
+        Some(span) => Some(span),
+        None if std::env::var_os("WGSL_LN_STATS").is_some() => Some(Span::call_site()),
+        None => None,
+    };
+    if let (Some(span), Some(module)) = (stats, module) {
+        notes.push((span, format!("`#![stats]` {}", crate::stats::stats(module))));
+    }
+    if notes.is_empty() {
+        return expr;
+    }
+    let notes = notes.into_iter().map(|(span, message)| {
+        quote_spanned! {span=>
+            const _: () = {
+                #[deprecated(note = #message)]
+                const WGSL: () = ();
+                WGSL
+            };
+        }
+    });
+    quote! {{#(#notes)* #expr}}
+}
+
+/// For `#![doc_item(..)]`, emit the item with the output in a collapsed section of its documentation.
+fn doc_item(options: &Options, output: &str, expr: TokenStream) -> TokenStream {
+    let Some(prefix) = &options.doc_item else {
+        return expr;
+    };
+    let doc = format!(
+        "\n\n<details><summary>WGSL</summary>\n\n```wgsl\n{}\n```\n\n</details>",
+        output.trim()
+    );
+    // After the item's own attributes and documentation.
+    let mut prefix: Vec<_> = prefix.clone().into_iter().collect();
+    let mut attrs = 0;
+    while matches!(prefix.get(attrs), Some(TokenTree::Punct(p)) if p.as_char() == '#') {
+        attrs += 2;
+    }
+    let attrs: Vec<_> = prefix.drain(..attrs.min(prefix.len())).collect();
+    quote! {
+        #(#attrs)*
+        #[doc = #doc]
+        #(#prefix)* #expr;
+    }
+}
+
+/// `OUT_DIR` of the crate, which requires a `build.rs`.
+fn out_dir(span: Span) -> PathBuf {
+    match std::env::var("OUT_DIR") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => abort!(
+            span,
+            "Writing to `OUT_DIR` requires a `build.rs` in the crate."
+        ),
+    }
+}
+
+/// Write the output for `#![embedded_asset(..)]`, `#![hot_reload(..)]` and `#![out_dir(..)]`.
+fn write_file(file: &Path, span: Span, output: &str) {
+    // Unchanged files are not written to avoid triggering hot reloading.
+    if std::fs::read_to_string(file).is_ok_and(|x| x == output) {
+        return;
+    }
+    let written = match file.parent() {
+        Some(parent) => std::fs::create_dir_all(parent).and_then(|_| std::fs::write(file, output)),
+        None => std::fs::write(file, output),
+    };
+    if written.is_err() {
+        abort!(span, "Unable to write \"{}\".", file.display())
+    }
+}
+
+/// Path of a `#![hot_reload(..)]` file, relative to `CARGO_MANIFEST_DIR`.
+fn hot_reload_path(path: &str) -> PathBuf {
+    PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default()).join(path)
+}
+
+thread_local! {
+    /// Shared by every invocation, `Validator::validate` resets its state for each module.
+    static VALIDATOR: RefCell<Validator> =
+        RefCell::new(Validator::new(ValidationFlags::all(), Capabilities::all()));
+}
+
+/// Validate a module with all validation flags and capabilities.
+pub fn validate(module: &Module) -> Result<ModuleInfo, WithSpan<ValidationError>> {
+    VALIDATOR.with(|validator| validator.borrow_mut().validate(module))
+}
+
+/// Find the span of the token at a byte offset of the generated source.
+pub fn span_at(spans: &[(usize, Span)], offset: usize) -> Span {
+    let pos = match spans.binary_search_by_key(&offset, |x| x.0) {
+        Ok(x) => x,
+        Err(x) => x.saturating_sub(1),
+    };
+    spans.get(pos).map(|x| x.1).unwrap_or_else(Span::call_site)
+}
+
+/// Span of a global's declaration.
+pub fn global_span(
+    module: &Module,
+    handle: Handle<GlobalVariable>,
+    source: &str,
+    spans: &[(usize, Span)],
+) -> Span {
+    match module.global_variables.get_span(handle).to_range() {
+        Some(range) if range.end <= source.len() => span_at(spans, range.start),
+        _ => Span::call_site(),
+    }
+}
+
+/// 128 bit FNV-1a, stable unlike `DefaultHasher`.
+///
+/// Same as `wgsl_ln_runtime::fnv1a`, which computes `Shader::hash` of shaders with `${..}`
+/// and the hash of edited files, its doctest checks the two agree.
+pub fn fnv1a(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013B;
+    bytes
+        .iter()
+        .fold(OFFSET, |hash, b| (hash ^ *b as u128).wrapping_mul(PRIME))
+}
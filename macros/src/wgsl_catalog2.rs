@@ -0,0 +1,191 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote, quote_spanned};
+
+use crate::{
+    externs::is_extern,
+    items::item_kind,
+    options::is_oil,
+    sanitize::sanitize,
+    wesl::{extract_imports, package_imports},
+};
+
+/// An export registered by `#[wgsl_export]`.
+struct Export {
+    name: String,
+    kind: String,
+    item: String,
+    /// Source of the exported shader, spans are not `Send`.
+    source: String,
+    /// `file:line:column` of the name in `#[wgsl_export(name)]`.
+    location: String,
+}
+
+/// Exports of each crate and whether `wgsl_catalog!()` has been expanded, by crate name.
+///
+/// State kept across expansions, like this, relies on the proc macro being loaded once
+/// per compilation of a crate, with attribute macros expanded in source order before macros
+/// they generate. rust-analyzer expands macros on demand in a long lived process shared by
+/// crates, so what it sees can be incomplete or stale, builds are not affected.
+static CATALOG: Mutex<BTreeMap<String, (Vec<Export>, bool)>> = Mutex::new(BTreeMap::new());
+
+fn crate_name() -> String {
+    std::env::var("CARGO_CRATE_NAME").unwrap_or_default()
+}
+
+/// Register an export for `wgsl_catalog!()`, warns if the catalog has already been generated.
+pub fn register(name: &Ident, item: &Ident, source: &TokenStream) -> TokenStream {
+    let kind = item_kind(source.clone(), name).unwrap_or_else(|| "module".to_owned());
+    let mut catalog = CATALOG.lock().unwrap();
+    let (exports, generated) = catalog.entry(crate_name()).or_default();
+    if *generated {
+        let message = format!(
+            "`{}` is exported after `wgsl_catalog!()` and is not listed, move `wgsl_catalog!()` to the end of the crate root.",
+            name
+        );
+        return quote_spanned! {name.span()=>
+            const _: () = {
+                #[deprecated(note = #message)]
+                const NOT_IN_CATALOG: () = ();
+                NOT_IN_CATALOG
+            };
+        };
+    }
+    exports.retain(|x| *name != x.name);
+    exports.push(Export {
+        name: name.to_string(),
+        kind,
+        item: item.to_string(),
+        source: source.to_string(),
+        location: location(name.span()),
+    });
+    TokenStream::new()
+}
+
+/// The item that first exported `name` in this crate and where, if it is not `item`.
+pub fn exported_by(name: &Ident, item: &Ident) -> Option<(String, String)> {
+    let catalog = CATALOG.lock().unwrap();
+    let (exports, _) = catalog.get(&crate_name())?;
+    exports
+        .iter()
+        .find(|x| *name == x.name && *item != x.item)
+        .map(|x| (x.item.clone(), x.location.clone()))
+}
+
+/// The name an item of this crate is exported as, if it has been expanded.
+pub fn export_of(item: &Ident) -> Option<String> {
+    let catalog = CATALOG.lock().unwrap();
+    let (exports, _) = catalog.get(&crate_name())?;
+    exports
+        .iter()
+        .find(|x| *item == x.item)
+        .map(|x| x.name.clone())
+}
+
+fn location(span: Span) -> String {
+    let span = span.unwrap();
+    format!("{}:{}:{}", span.file(), span.line(), span.column())
+}
+
+/// Generate a module with the exports of the crate, `wgsl_catalog!()` or `wgsl_catalog!(name)`.
+pub fn wgsl_catalog2(stream: TokenStream) -> TokenStream {
+    let module = match stream.into_iter().next() {
+        Some(TokenTree::Ident(name)) => name,
+        None => format_ident!("wgsl_catalog"),
+        Some(tt) => abort!(
+            tt.span(),
+            "Expected `wgsl_catalog!()` or `wgsl_catalog!(name)`."
+        ),
+    };
+    let mut catalog = CATALOG.lock().unwrap();
+    let (exports, generated) = catalog.entry(crate_name()).or_default();
+    *generated = true;
+    exports.sort_by(|a, b| a.name.cmp(&b.name));
+    let entries = exports.iter().map(|export| {
+        let Export {
+            name, kind, item, ..
+        } = export;
+        quote! {(#name, #kind, #item)}
+    });
+    let standalone = exports
+        .iter()
+        .filter(|x| standalone(exports, &x.name, &mut Vec::new()))
+        .map(|export| {
+            let ident = Ident::new(&export.name, Span::call_site());
+            let name = &export.name;
+            quote! {(#name, #ident)}
+        });
+    let consts = exports.iter().map(|export| {
+        let ident = Ident::new(&export.name, Span::call_site());
+        let doc = format!(
+            "`{} {}`, exported from `{}`, paste it into `wgsl!` with `${}`.",
+            export.kind, export.name, export.item, export.name
+        );
+        let source: TokenStream = match export.source.parse() {
+            Ok(source) => source,
+            Err(_) => abort!(Span::call_site(), "Unable to parse `{}`.", export.name),
+        };
+        quote! {
+            #[doc = #doc]
+            #[allow(non_upper_case_globals)]
+            pub const #ident: &str = ::wgsl_ln::wgsl!(#source);
+        }
+    });
+    quote! {
+        /// Shader items exported from this crate with `#[wgsl_export]`.
+        pub mod #module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            /// Every export as `(name, kind, item)`, where `kind` is the keyword
+            /// of the exported item and `item` the `static` or `const` it is exported from.
+            pub const EXPORTS: &[(&str, &str, &str)] = &[#(#entries),*];
+
+            /// Composed source of every export that is valid on its own as `(name, source)`,
+            /// exports with `$extern` declarations, WESL or `naga_oil` imports are not listed.
+            pub const SOURCES: &[(&str, &str)] = &[#(#standalone),*];
+
+            #(#consts)*
+        }
+    }
+}
+
+/// Returns true if an export and its dependencies in the same crate can be validated on their own.
+fn standalone(exports: &[Export], name: &str, visited: &mut Vec<String>) -> bool {
+    let Some(export) = exports.iter().find(|x| x.name == name) else {
+        return true;
+    };
+    if visited.iter().any(|x| x == name) {
+        return true;
+    }
+    visited.push(name.to_owned());
+    let Ok(source) = export.source.parse::<TokenStream>() else {
+        return false;
+    };
+    if is_oil(&source) || has_extern(source.clone()) {
+        return false;
+    }
+    let (source, mut dependencies) = sanitize(source, false);
+    let (source, imports) = package_imports(source);
+    dependencies.extend(imports);
+    extract_imports(source).1.is_empty()
+        && dependencies
+            .iter()
+            .all(|x| standalone(exports, &x.to_string(), visited))
+}
+
+/// Check for `$extern`.
+fn has_extern(stream: TokenStream) -> bool {
+    let mut last_is_dollar = false;
+    for tt in stream {
+        match &tt {
+            TokenTree::Ident(i) if last_is_dollar && is_extern(i) => return true,
+            TokenTree::Group(g) if has_extern(g.stream()) => return true,
+            _ => (),
+        }
+        last_is_dollar = matches!(&tt, TokenTree::Punct(p) if p.as_char() == '$');
+    }
+    false
+}
@@ -0,0 +1,71 @@
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+use crate::{options::string_value, wgsl_catalog2::export_of};
+
+/// Concatenate exported shaders and WGSL strings into one validated shader,
+/// `wgsl_concat!(#![options] ITEM, "wgsl", ..)`.
+///
+/// Shaders are imported like `import package::name;` so shared dependencies are pasted once.
+pub fn wgsl_concat2(stream: TokenStream) -> TokenStream {
+    let mut options = TokenStream::new();
+    let mut tokens = stream.into_iter().peekable();
+    while matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '#') {
+        options.extend(tokens.by_ref().take(3));
+    }
+    let mut imports = Vec::new();
+    let mut body = TokenStream::new();
+    let tokens: Vec<_> = tokens.collect();
+    let args = tokens.split(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ','));
+    for arg in args {
+        // The last segment of a path, `shaders::BLUR`.
+        match arg.last() {
+            Some(TokenTree::Ident(item)) => match export_of(item) {
+                Some(name) => {
+                    let name = format_ident!("{}", name, span = item.span());
+                    imports.push(quote! {import package::#name;});
+                }
+                None => abort!(
+                    item.span(),
+                    "`{}` is not exported with `#[wgsl_export]` before this `wgsl_concat!`.",
+                    item
+                ),
+            },
+            Some(TokenTree::Literal(lit)) if arg.len() == 1 => {
+                let source =
+                    string_value(lit).unwrap_or_else(|| abort!(lit.span(), "Expected a string."));
+                match source.parse::<TokenStream>() {
+                    Ok(stream) => body.extend(respan(stream, lit.span())),
+                    Err(e) => abort!(lit.span(), "Unable to tokenize the string: {}", e),
+                }
+            }
+            Some(tt) => abort!(tt.span(), "Expected an exported item or a string."),
+            // A trailing comma.
+            None => (),
+        }
+    }
+    if imports.is_empty() && body.is_empty() {
+        abort!(
+            Span::call_site(),
+            "Expected `wgsl_concat!(ITEM, \"wgsl\", ..)`."
+        )
+    }
+    quote! {::wgsl_ln::wgsl!{#options #(#imports)* #body}}
+}
+
+/// Errors in a string are reported at the string.
+fn respan(stream: TokenStream, span: Span) -> TokenStream {
+    stream
+        .into_iter()
+        .map(|mut tt| {
+            if let TokenTree::Group(g) = &tt {
+                let mut group = Group::new(g.delimiter(), respan(g.stream(), span));
+                group.set_span(span);
+                return TokenTree::Group(group);
+            }
+            tt.set_span(span);
+            tt
+        })
+        .collect()
+}
@@ -0,0 +1,305 @@
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+use crate::{
+    options::{flatten_options, LOCAL_OPTIONS},
+    sanitize::escape_sigils,
+};
+
+#[cfg(feature = "bevy")]
+use crate::bevy::bevy_items;
+
+pub fn wgsl_export2(attr: TokenStream, stream: TokenStream) -> TokenStream {
+    let Some(TokenTree::Ident(name)) = attr.into_iter().next() else {
+        abort!(Span::call_site(), "Expected #[wgsl_export(name)]");
+    };
+    let mut wgsl_macro_ident = false;
+    let mut oil = false;
+    let mut exclamation_mark = false;
+    let tokens: Vec<_> = stream.clone().into_iter().collect();
+    for (idx, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Ident(i) if i == "wgsl" || i == "wgsl_oil" => {
+                wgsl_macro_ident = true;
+                oil = i == "wgsl_oil";
+                exclamation_mark = false;
+            }
+            TokenTree::Punct(p) if wgsl_macro_ident && p.as_char() == '!' => {
+                exclamation_mark = true;
+            }
+            TokenTree::Group(g) if wgsl_macro_ident && exclamation_mark => {
+                // WESL output, asset files and materials only apply to this shader, not where it is pasted.
+                let source = escape_sigils(strip_local_options(g.stream()));
+                let source = match oil {
+                    true => quote! {#![oil] #source},
+                    false => source,
+                };
+                // Shaders are labeled with the item's name.
+                let (vis, ident) = item_ident(&name, &stream);
+                // Otherwise the paste macros are reported as duplicates at the `wgsl!` calls.
+                if let Some((first, at)) = crate::wgsl_catalog2::exported_by(&name, &ident) {
+                    abort!(
+                        name.span(),
+                        "`{}` is already exported by `{}` at {}.",
+                        name,
+                        first,
+                        at
+                    );
+                }
+                crate::dump::dump_export(&name, &ident, source.clone());
+                let catalog = crate::wgsl_catalog2::register(&name, &ident, &source);
+                let label = ident.to_string();
+                // The macros are emitted by `wgsl!`, so they are in the initializer,
+                // and see what a `wgsl_prelude!` adds to the call.
+                let inner = g.stream();
+                let inner = quote! {#![label(#label)] #inner};
+                let mut group = Group::new(g.delimiter(), quote! {#![export(#name)] #inner});
+                group.set_span(g.span());
+                let mut tokens = tokens.clone();
+                tokens[idx] = TokenTree::Group(group);
+                let stream = TokenStream::from_iter(tokens.clone());
+                let eq = tokens[..idx]
+                    .iter()
+                    .rposition(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == '='));
+                // Consts can be associated items, where modules and `const _` are not allowed.
+                let is_const = tokens[..idx]
+                    .iter()
+                    .any(|x| matches!(x, TokenTree::Ident(i) if i == "const"));
+                if let (true, Some(eq)) = (is_const, eq) {
+                    let prefix = &tokens[..=eq];
+                    let expr = tokens[eq + 1..]
+                        .iter()
+                        .take_while(|x| !matches!(x, TokenTree::Punct(p) if p.as_char() == ';'));
+                    return quote! {
+                        #(#prefix)* {
+                            #catalog
+                            #(#expr)*
+                        };
+                    };
+                }
+                #[cfg(feature = "bevy")]
+                let bevy = bevy_items(&name, &stream, g.stream());
+                #[cfg(not(feature = "bevy"))]
+                let bevy = TokenStream::new();
+                // Public items show the composed shader in their documentation,
+                // the item is emitted by `wgsl!` at the end of the paste chain.
+                let item = match eq {
+                    Some(eq) if !vis.is_empty() => {
+                        let prefix = &tokens[..=eq];
+                        let ty = tokens[..eq]
+                            .iter()
+                            .position(|x| matches!(x, TokenTree::Ident(i) if *i == ident))
+                            .map_or(&[][..], |i| &tokens[i + 2..eq]);
+                        let path = &tokens[eq + 1..idx - 1];
+                        // The documented item imports the export, which has the items added by
+                        // `wgsl_prelude!`, a shadowed `wgsl!` would be ambiguous with `use crate::*`.
+                        let options = inner_options(&inner);
+                        let oil = match oil {
+                            true => quote! {#![oil]},
+                            false => TokenStream::new(),
+                        };
+                        let doc_module = format_ident!("__wgsl_doc_{}", name);
+                        // Macros exported from the documentation module could not be resolved
+                        // through its `use crate::*`, they are exported from a separate item.
+                        quote! {
+                            #[cfg(not(doc))]
+                            #stream
+                            #[cfg(doc)]
+                            const _: #(#ty)* = #(#path)*!(#![export(#name)] #inner);
+                            #[cfg(doc)]
+                            #[allow(non_snake_case)]
+                            #[doc(hidden)]
+                            mod #doc_module {
+                                use super::*;
+                                use crate::*;
+                                ::wgsl_ln::wgsl!(#![doc_item(#(#prefix)*)] #oil #options import package::#name;);
+                            }
+                            #[cfg(doc)]
+                            #(#vis)* use #doc_module::#ident;
+                        }
+                    }
+                    _ => stream,
+                };
+                return quote! {
+                    #item
+                    #bevy
+                    #catalog
+                };
+            }
+            _ => {
+                wgsl_macro_ident = false;
+                exclamation_mark = false;
+            }
+        }
+    }
+    abort!(
+        Span::call_site(),
+        "Expected wgsl! macro, use `wgsl!(#![export(name)] ..)` if it is generated by a macro."
+    );
+}
+
+/// The `__wgsl_paste_name` macro, and `__wgsl_module_a__b` for `#define_import_path a::b`.
+///
+/// The sealed module makes exporting the same name twice an error.
+/// The pasted source records the defining crate with `#![origin(name, crate)]`.
+pub fn paste_macros(name: &Ident, source: &TokenStream) -> TokenStream {
+    let sealed = format_ident!("__sealed_{}", name);
+    let mut paste = format_ident!("__wgsl_paste_{}", name);
+    paste.set_span(name.span());
+    let module = define_import_path(source.clone()).map(|path| {
+        let module = format_ident!(
+            "__wgsl_module_{}",
+            path.iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join("__")
+        );
+        quote! {
+            #[allow(non_snake_case)]
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! #module {
+                (wgsl!(__wgsl_chain [$($defined: tt)*] $($tt: tt)*)) => {
+                    ::wgsl_ln::wgsl!{__wgsl_chain [$($defined)*] #![module_source(#(#path)::* {#source})] $($tt)*}
+                };
+                (wgsl!($($tt: tt)*)) => {
+                    ::wgsl_ln::wgsl!{#![module_source(#(#path)::* {#source})] $($tt)*}
+                };
+            }
+        }
+    });
+    // The defining crate, for `#![namespaced]`.
+    let krate = format_ident!(
+        "{}",
+        std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "crate".to_owned())
+    );
+    quote! {
+        #[allow(non_snake_case)]
+        mod #sealed {
+            #[allow(non_snake_case)]
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! #paste {
+                (wgsl!($($tt: tt)*)) => {
+                    ::wgsl_ln::__wgsl_paste!{#name {#![origin(#name, #krate)] #source} $($tt)*}
+                };
+            }
+            #module
+        }
+    }
+}
+
+/// Find `#![export(name)]` in a macro-generated `wgsl!` call, returns the name and the
+/// stream without the option.
+pub fn export_option(stream: TokenStream) -> Option<(Ident, TokenStream)> {
+    let mut result: Vec<TokenTree> = Vec::new();
+    let mut name = None;
+    for tt in stream {
+        let is_inner = matches!(
+            result.as_slice(),
+            [.., TokenTree::Punct(a), TokenTree::Punct(b)] if a.as_char() == '#' && b.as_char() == '!'
+        );
+        match &tt {
+            TokenTree::Group(g)
+                if is_inner && name.is_none() && g.delimiter() == Delimiter::Bracket =>
+            {
+                let mut iter = g.stream().into_iter();
+                match (iter.next(), iter.next()) {
+                    (Some(TokenTree::Ident(i)), Some(TokenTree::Group(args))) if i == "export" => {
+                        match args.stream().into_iter().next() {
+                            Some(TokenTree::Ident(export)) => name = Some(export),
+                            _ => abort!(args.span(), "Expected `#![export(name)]`."),
+                        }
+                        result.truncate(result.len() - 2);
+                        continue;
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+        result.push(tt);
+    }
+    name.map(|name| (name, TokenStream::from_iter(result)))
+}
+
+/// Top level `#![option]`s of a stream.
+fn inner_options(stream: &TokenStream) -> TokenStream {
+    let tokens: Vec<_> = stream.clone().into_iter().collect();
+    let mut result = Vec::new();
+    for window in tokens.windows(3) {
+        if let [TokenTree::Punct(a), TokenTree::Punct(b), TokenTree::Group(g)] = window {
+            if a.as_char() == '#' && b.as_char() == '!' && g.delimiter() == Delimiter::Bracket {
+                result.extend(window.iter().cloned());
+            }
+        }
+    }
+    TokenStream::from_iter(result)
+}
+
+/// Find the visibility and name of the exported `static` or `const`.
+pub fn item_ident(name: &Ident, item: &TokenStream) -> (Vec<TokenTree>, Ident) {
+    let mut vis = Vec::new();
+    let mut iter = item.clone().into_iter();
+    loop {
+        match iter.next() {
+            Some(TokenTree::Ident(i)) if i == "static" || i == "const" => match iter.next() {
+                Some(TokenTree::Ident(i)) => return (vis, i),
+                _ => abort!(i.span(), "Expected a name."),
+            },
+            // Skip attributes.
+            Some(TokenTree::Punct(p)) if p.as_char() == '#' => {
+                iter.next();
+            }
+            Some(tt) => vis.push(tt),
+            None => abort!(name.span(), "Expected `static` or `const`."),
+        }
+    }
+}
+
+/// Find `#define_import_path a::b` and return `[a, b]`.
+fn define_import_path(stream: TokenStream) -> Option<Vec<Ident>> {
+    let mut iter = stream.into_iter();
+    while let Some(tt) = iter.next() {
+        match tt {
+            TokenTree::Ident(i) if i == "define_import_path" => {
+                let mut path = Vec::new();
+                let mut colons = 2;
+                for tt in iter.by_ref() {
+                    match tt {
+                        TokenTree::Ident(i) if colons == 2 => {
+                            path.push(i);
+                            colons = 0;
+                        }
+                        TokenTree::Punct(p) if p.as_char() == ':' => colons += 1,
+                        _ => break,
+                    }
+                }
+                return Some(path);
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Remove the [`LOCAL_OPTIONS`] from the top level of a stream, they only apply to the exported shader.
+pub fn strip_local_options(stream: TokenStream) -> TokenStream {
+    let mut result: Vec<TokenTree> = Vec::new();
+    for tt in flatten_options(stream) {
+        let is_local = matches!(&tt, TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket
+            && matches!(g.stream().into_iter().next(),
+                Some(TokenTree::Ident(i)) if LOCAL_OPTIONS.iter().any(|x| i == x)));
+        let is_inner = matches!(
+            result.as_slice(),
+            [.., TokenTree::Punct(a), TokenTree::Punct(b)] if a.as_char() == '#' && b.as_char() == '!'
+        );
+        if is_local && is_inner {
+            result.truncate(result.len() - 2);
+        } else {
+            result.push(tt);
+        }
+    }
+    TokenStream::from_iter(result)
+}
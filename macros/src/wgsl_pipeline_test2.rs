@@ -0,0 +1,24 @@
+use proc_macro2::{Span, TokenStream};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+use crate::wgsl_export2::item_ident;
+
+/// Add a test creating every pipeline of a `wgsl_ln_runtime::Shader`, `#[wgsl_pipeline_test]`.
+pub fn wgsl_pipeline_test2(attr: TokenStream, stream: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        abort!(Span::call_site(), "Expected #[wgsl_pipeline_test]")
+    }
+    let (_, ident) = item_ident(&format_ident!("wgsl_pipeline_test"), &stream);
+    let test = format_ident!("wgsl_pipeline_test_{}", ident);
+    quote! {
+        #stream
+
+        #[cfg(test)]
+        #[test]
+        #[allow(non_snake_case)]
+        fn #test() {
+            ::wgsl_ln::runtime::test::create_pipelines(&#ident);
+        }
+    }
+}
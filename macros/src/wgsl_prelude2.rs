@@ -0,0 +1,38 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Shadow `wgsl!` and `wgsl_oil!` in the rest of the module with macros that import the prelude.
+pub fn wgsl_prelude2(stream: TokenStream) -> TokenStream {
+    let mut items = Vec::new();
+    for tt in stream {
+        match tt {
+            TokenTree::Ident(item) => items.push(item),
+            TokenTree::Punct(p) if p.as_char() == ',' => (),
+            tt => abort!(tt.span(), "Expected `wgsl_prelude!(item, ..)`."),
+        }
+    }
+    if items.is_empty() {
+        abort!(Span::call_site(), "Expected `wgsl_prelude!(item, ..)`.")
+    }
+    #[cfg(feature = "naga_oil")]
+    let oil = quote! {
+        #[allow(unused_macros)]
+        macro_rules! wgsl_oil {
+            ($($tt: tt)*) => {
+                ::wgsl_ln::wgsl_oil!{#(import package::#items;)* $($tt)*}
+            };
+        }
+    };
+    #[cfg(not(feature = "naga_oil"))]
+    let oil = TokenStream::new();
+    quote! {
+        #[allow(unused_macros)]
+        macro_rules! wgsl {
+            ($($tt: tt)*) => {
+                ::wgsl_ln::wgsl!{#(import package::#items;)* $($tt)*}
+            };
+        }
+        #oil
+    }
+}
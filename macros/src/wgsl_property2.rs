@@ -0,0 +1,38 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+use crate::options::parse_property;
+
+/// Add a test comparing an exported function to the Rust function the attribute is on,
+/// `#[wgsl_property(function, samples = 256, tolerance = 1e-5, range = -10.0..10.0, seed = 0)]`.
+pub fn wgsl_property2(attr: TokenStream, stream: TokenStream) -> TokenStream {
+    let mut iter = stream.clone().into_iter();
+    let reference = loop {
+        match iter.next() {
+            Some(TokenTree::Ident(i)) if i == "fn" => match iter.next() {
+                Some(TokenTree::Ident(name)) => break name,
+                _ => abort!(Span::call_site(), "Expected a function."),
+            },
+            Some(_) => (),
+            None => abort!(Span::call_site(), "Expected a function."),
+        }
+    };
+    let args = quote! {#attr, reference = #reference};
+    let function = parse_property(args.clone(), Span::call_site()).function;
+    let test = format_ident!("wgsl_property_{}", reference);
+    quote! {
+        #[cfg_attr(not(test), allow(dead_code))]
+        #stream
+
+        #[cfg(test)]
+        #[test]
+        #[allow(non_snake_case)]
+        fn #test() {
+            ::wgsl_ln::wgsl_test!(
+                #![property(#args)]
+                import package::#function;
+            );
+        }
+    }
+}
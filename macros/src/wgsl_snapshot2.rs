@@ -0,0 +1,58 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+use crate::{options::string_literal, wgsl_export2::item_ident};
+
+/// Add a test comparing the item to a checked-in file, `#[wgsl_snapshot("path")]`.
+///
+/// The file is relative to `CARGO_MANIFEST_DIR`, and is written instead
+/// if `WGSL_LN_UPDATE_SNAPSHOTS` is set.
+pub fn wgsl_snapshot2(attr: TokenStream, stream: TokenStream) -> TokenStream {
+    let path = match attr.into_iter().next() {
+        Some(TokenTree::Literal(lit)) => match string_literal(&lit) {
+            Some(path) => path,
+            None => abort!(lit.span(), "Expected #[wgsl_snapshot(\"path\")]"),
+        },
+        _ => abort!(Span::call_site(), "Expected #[wgsl_snapshot(\"path\")]"),
+    };
+    let (_, ident) = item_ident(&format_ident!("wgsl_snapshot"), &stream);
+    let test = format_ident!("wgsl_snapshot_{}", ident);
+    let path = format!("/{}", path);
+    quote! {
+        #stream
+
+        #[cfg(test)]
+        #[test]
+        #[allow(non_snake_case)]
+        fn #test() {
+            let path = ::core::concat!(::core::env!("CARGO_MANIFEST_DIR"), #path);
+            let actual: &str = #ident;
+            if ::std::env::var_os("WGSL_LN_UPDATE_SNAPSHOTS").is_some() {
+                let path = ::std::path::Path::new(path);
+                if let Some(parent) = path.parent() {
+                    ::std::fs::create_dir_all(parent).unwrap();
+                }
+                ::std::fs::write(path, actual).unwrap();
+                return;
+            }
+            let Ok(expected) = ::std::fs::read_to_string(path) else {
+                panic!(
+                    "Snapshot \"{}\" of `{}` not found, run with `WGSL_LN_UPDATE_SNAPSHOTS=1` to create it.",
+                    path,
+                    ::core::stringify!(#ident),
+                )
+            };
+            // Git may check out files with CRLF line endings.
+            assert!(
+                expected.replace("\r\n", "\n") == actual,
+                "`{}` does not match snapshot \"{}\", run with `WGSL_LN_UPDATE_SNAPSHOTS=1` to update it.\n\
+                expected:\n{}\nactual:\n{}",
+                ::core::stringify!(#ident),
+                path,
+                expected,
+                actual,
+            );
+        }
+    }
+}
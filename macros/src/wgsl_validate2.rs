@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote;
+
+use crate::{
+    config::Config,
+    level::{validate_strict, Level},
+    limits::Limits,
+    options::{string_literal, string_value},
+    storage::storage_format_errors,
+    wgsl2::validate,
+};
+
+/// Validate a WGSL string known at compile time, `wgsl_validate!(include_str!("path"))`.
+///
+/// Emits the string expression unchanged, so `include_str!` still rebuilds the crate
+/// when the file changes.
+pub fn wgsl_validate2(stream: TokenStream) -> TokenStream {
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let mut source = String::new();
+    let expr = string_expr(&tokens, &mut source);
+    let level = Level::get(None);
+    if level == Level::Off {
+        return expr;
+    }
+    // The source has no token spans, errors are shown at the argument with naga's report.
+    let span = match tokens.as_slice() {
+        [tt] => tt.span(),
+        _ => Span::call_site(),
+    };
+    let module = match naga::front::wgsl::parse_str(&source) {
+        Ok(module) => module,
+        Err(e) => abort!(span, "Wgsl Error: {}", e.emit_to_string(&source).trim_end()),
+    };
+    if level == Level::Parse {
+        return expr;
+    }
+    let info = match validate(&module) {
+        Ok(info) => info,
+        Err(e) => abort!(span, "Wgsl Error: {}", e.emit_to_string(&source).trim_end()),
+    };
+    let spans = [(0, span)];
+    if level == Level::Strict {
+        validate_strict(&module, &source, &spans);
+    }
+    let errors = storage_format_errors(&module, &source, &spans, level);
+    if let Some((span, message)) = errors.into_iter().next() {
+        abort!(span, "Wgsl Error: {}", message)
+    }
+    if let Some(path) = &Config::get().limits {
+        let exceeded = Limits::read(path).exceeded(&module, &info, &source, &spans);
+        if let Some((span, message)) = exceeded.into_iter().next() {
+            abort!(span, "Wgsl Error: {}", message)
+        }
+    }
+    expr
+}
+
+/// Append the value of a string literal, `include_str!(..)` or `concat!(..)` of them to `source`,
+/// returns the expression with its macros fully qualified.
+fn string_expr(tokens: &[TokenTree], source: &mut String) -> TokenStream {
+    match tokens {
+        [TokenTree::Literal(lit)] => match string_value(lit) {
+            Some(value) => {
+                source.push_str(&value);
+                quote! {#lit}
+            }
+            None => abort!(lit.span(), "Expected a string."),
+        },
+        [TokenTree::Ident(name), TokenTree::Punct(bang), TokenTree::Group(args)]
+            if bang.as_char() == '!' && name == "include_str" =>
+        {
+            let lit = match args.stream().into_iter().collect::<Vec<_>>().as_slice() {
+                [TokenTree::Literal(lit)] => lit.clone(),
+                _ => abort!(args.span(), "Expected `include_str!(\"path\")`."),
+            };
+            let Some(path) = string_literal(&lit) else {
+                abort!(lit.span(), "Expected `include_str!(\"path\")`.")
+            };
+            // Relative to the calling file like `include_str!`.
+            let dir = match lit.span().unwrap().local_file() {
+                Some(file) => file.parent().map(PathBuf::from).unwrap_or_default(),
+                None => PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default()),
+            };
+            match std::fs::read_to_string(dir.join(&path)) {
+                Ok(content) => source.push_str(&content),
+                Err(e) => abort!(lit.span(), "Unable to read \"{}\": {}", path, e),
+            }
+            quote! {::core::include_str!(#lit)}
+        }
+        [TokenTree::Ident(name), TokenTree::Punct(bang), TokenTree::Group(args)]
+            if bang.as_char() == '!' && name == "concat" =>
+        {
+            let args: Vec<_> = args.stream().into_iter().collect();
+            let parts = args
+                .split(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ','))
+                .filter(|x| !x.is_empty())
+                .map(|part| string_expr(part, source))
+                .collect::<Vec<_>>();
+            quote! {::core::concat!(#(#parts),*)}
+        }
+        [tt, ..] => abort!(
+            tt.span(),
+            "Expected a string literal, `include_str!(..)` or `concat!(..)` of them, \
+            the value of a `const` is not known to macros."
+        ),
+        [] => abort!(
+            Span::call_site(),
+            "Expected `wgsl_validate!(include_str!(\"path\"))`."
+        ),
+    }
+}
@@ -0,0 +1,334 @@
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// A shader per variant of a Rust enum, with `#ifdef` directives resolved by the variant's defs.
+///
+/// ```text
+/// wgsl_variants!(
+///     pub fn lighting(quality: Quality) -> &'static str {
+///         Quality::Low => (),
+///         Quality::High => (SHADOWS, SAMPLES = 4),
+///     }
+///     ..
+/// )
+/// ```
+pub fn wgsl_variants2(stream: TokenStream) -> TokenStream {
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let error = || -> ! {
+        abort!(
+            Span::call_site(),
+            "Expected `fn name(variant: Enum) -> &'static str { Enum::A => (DEFS..), .. }` before the shader."
+        )
+    };
+    let Some(fn_idx) = tokens
+        .iter()
+        .position(|x| matches!(x, TokenTree::Ident(i) if i == "fn"))
+    else {
+        error()
+    };
+    let Some(body_idx) = tokens[fn_idx..]
+        .iter()
+        .position(|x| matches!(x, TokenTree::Group(g) if g.delimiter() == Delimiter::Brace))
+        .map(|x| fn_idx + x)
+    else {
+        error()
+    };
+    let signature = TokenStream::from_iter(tokens[..body_idx].iter().cloned());
+    // The variant is the first parameter.
+    let variant = match &tokens[fn_idx..body_idx] {
+        [_, TokenTree::Ident(_), TokenTree::Group(params), ..]
+            if params.delimiter() == Delimiter::Parenthesis =>
+        {
+            match params.stream().into_iter().next() {
+                Some(TokenTree::Ident(variant)) => variant,
+                _ => abort!(params.span(), "Expected `(variant: Enum)`."),
+            }
+        }
+        _ => error(),
+    };
+    let TokenTree::Group(arms) = &tokens[body_idx] else {
+        error()
+    };
+    let arms = parse_arms(arms.stream());
+    if arms.is_empty() {
+        abort!(tokens[body_idx].span(), "Expected at least one variant.")
+    }
+    let shader = TokenStream::from_iter(tokens[body_idx + 1..].iter().cloned());
+    // A def no variant sets is most likely misspelled.
+    let mut used = Vec::new();
+    resolve(shader.clone(), &[], &mut used, None);
+    for name in used {
+        if !arms
+            .iter()
+            .any(|(_, defs)| defs.iter().any(|(x, _)| x == &name))
+        {
+            abort!(
+                name.span(),
+                "Shader def `{}` is not set by any variant.",
+                name
+            )
+        }
+    }
+    let arms = arms.iter().map(|(pattern, defs)| {
+        let variant = pattern.to_string().replace(' ', "");
+        let shader = resolve(shader.clone(), defs, &mut Vec::new(), Some(&variant));
+        quote! {#pattern => ::wgsl_ln::wgsl!(#shader)}
+    });
+    quote! {
+        #signature {
+            match #variant {
+                #(#arms,)*
+            }
+        }
+    }
+}
+
+/// A pattern and the defs of its shader, `NAME` or `NAME = value`.
+type Arm = (TokenStream, Vec<(Ident, Option<String>)>);
+
+/// Parse `Enum::A => (DEFS..),` arms.
+fn parse_arms(stream: TokenStream) -> Vec<Arm> {
+    let mut arms = Vec::new();
+    let mut pattern = Vec::new();
+    let mut iter = stream.into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        match &tt {
+            TokenTree::Punct(p)
+                if p.as_char() == '='
+                    && matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '>') =>
+            {
+                iter.next();
+                let defs = match iter.next() {
+                    Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                        parse_defs(g.stream())
+                    }
+                    Some(tt) => abort!(
+                        tt.span(),
+                        "Expected shader defs, i.e. `(SHADOWS, SAMPLES = 4)`."
+                    ),
+                    None => abort!(
+                        p.span(),
+                        "Expected shader defs, i.e. `(SHADOWS, SAMPLES = 4)`."
+                    ),
+                };
+                if pattern.is_empty() {
+                    abort!(p.span(), "Expected a variant before `=>`.")
+                }
+                arms.push((TokenStream::from_iter(pattern.drain(..)), defs));
+                match iter.next() {
+                    Some(TokenTree::Punct(p)) if p.as_char() == ',' => (),
+                    None => (),
+                    Some(tt) => abort!(tt.span(), "Expected `,`."),
+                }
+            }
+            _ => pattern.push(tt),
+        }
+    }
+    if let Some(tt) = pattern.first() {
+        abort!(tt.span(), "Expected `=> (DEFS..)`.")
+    }
+    arms
+}
+
+/// Parse `NAME` or `NAME = value` separated by commas.
+fn parse_defs(stream: TokenStream) -> Vec<(Ident, Option<String>)> {
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let mut defs: Vec<(Ident, Option<String>)> = Vec::new();
+    for def in tokens.split(|x| matches!(x, TokenTree::Punct(p) if p.as_char() == ',')) {
+        let def = match def {
+            [TokenTree::Ident(name)] => (name.clone(), None),
+            [TokenTree::Ident(name), TokenTree::Punct(eq), value @ ..]
+                if eq.as_char() == '=' && !value.is_empty() =>
+            {
+                (
+                    name.clone(),
+                    Some(value.iter().map(|x| x.to_string()).collect()),
+                )
+            }
+            [] => continue,
+            [tt, ..] => abort!(tt.span(), "Expected `NAME` or `NAME = value`."),
+        };
+        if defs.iter().any(|(x, _)| x == &def.0) {
+            abort!(def.0.span(), "Shader def `{}` is set twice.", def.0)
+        }
+        defs.push(def);
+    }
+    defs
+}
+
+/// A `#ifdef`, `#ifndef` or `#if` and the branches taken so far.
+struct Scope {
+    span: Span,
+    parent: bool,
+    taken: bool,
+    active: bool,
+}
+
+/// Keep the tokens of branches whose condition holds for `defs` and remove the directives.
+///
+/// Names used in conditions are added to `used`, conditions are only checked with a `variant`.
+fn resolve(
+    stream: TokenStream,
+    defs: &[(Ident, Option<String>)],
+    used: &mut Vec<Ident>,
+    variant: Option<&str>,
+) -> TokenStream {
+    let tokens: Vec<_> = stream.into_iter().collect();
+    let mut result = Vec::new();
+    let mut scopes: Vec<Scope> = Vec::new();
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let active = scopes.last().is_none_or(|x| x.active);
+        let directive = match &tokens[idx..] {
+            [TokenTree::Punct(hash), TokenTree::Ident(name), ..] if hash.as_char() == '#' => {
+                match name.to_string().as_str() {
+                    "ifdef" | "ifndef" | "if" | "else" | "endif" => Some(name.clone()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let Some(directive) = directive else {
+            match &tokens[idx] {
+                // Directives in inactive groups are only looked at for their names.
+                TokenTree::Group(g) if active || variant.is_none() => {
+                    let mut group =
+                        Group::new(g.delimiter(), resolve(g.stream(), defs, used, variant));
+                    group.set_span(g.span());
+                    result.push(TokenTree::Group(group));
+                }
+                tt if active => result.push(tt.clone()),
+                _ => (),
+            }
+            idx += 1;
+            continue;
+        };
+        idx += 2;
+        match directive.to_string().as_str() {
+            "endif" => {
+                if scopes.pop().is_none() {
+                    abort!(
+                        directive.span(),
+                        "`#endif` without `#ifdef`, `#ifndef` or `#if`."
+                    )
+                }
+            }
+            "else" => {
+                let Some(parent) = scopes.last().map(|x| x.parent) else {
+                    abort!(
+                        directive.span(),
+                        "`#else` without `#ifdef`, `#ifndef` or `#if`."
+                    )
+                };
+                // `#else ifdef NAME`
+                let condition = match tokens.get(idx) {
+                    Some(TokenTree::Ident(kind))
+                        if kind == "ifdef" || kind == "ifndef" || kind == "if" =>
+                    {
+                        idx += 1;
+                        let variant = variant.filter(|_| parent);
+                        Some(condition(kind, &tokens, &mut idx, defs, used, variant))
+                    }
+                    _ => None,
+                };
+                let scope = scopes.last_mut().unwrap();
+                scope.active = scope.parent && !scope.taken && condition.unwrap_or(true);
+                scope.taken |= scope.active;
+            }
+            _ => {
+                let holds = condition(
+                    &directive,
+                    &tokens,
+                    &mut idx,
+                    defs,
+                    used,
+                    variant.filter(|_| active),
+                );
+                scopes.push(Scope {
+                    span: directive.span(),
+                    parent: active,
+                    taken: active && holds,
+                    active: active && holds,
+                });
+            }
+        }
+    }
+    if let Some(scope) = scopes.last() {
+        abort!(scope.span, "Expected `#endif` in the same block.")
+    }
+    TokenStream::from_iter(result)
+}
+
+/// Evaluate the condition after `#ifdef`, `#ifndef` or `#if`, i.e. `SAMPLES >= 4`.
+fn condition(
+    kind: &Ident,
+    tokens: &[TokenTree],
+    idx: &mut usize,
+    defs: &[(Ident, Option<String>)],
+    used: &mut Vec<Ident>,
+    variant: Option<&str>,
+) -> bool {
+    let Some(TokenTree::Ident(name)) = tokens.get(*idx) else {
+        abort!(kind.span(), "Expected a shader def after `#{}`.", kind)
+    };
+    *idx += 1;
+    if !used.contains(name) {
+        used.push(name.clone());
+    }
+    let def = defs.iter().find(|(x, _)| x == name);
+    if kind != "if" {
+        return def.is_some() == (kind == "ifdef");
+    }
+    // `==`, `!=`, `>=`, `<=`, `>` or `<`.
+    let mut op = String::new();
+    while let Some(TokenTree::Punct(p)) = tokens.get(*idx).filter(|_| op.len() < 2) {
+        if !matches!(p.as_char(), '=' | '!' | '<' | '>') {
+            break;
+        }
+        op.push(p.as_char());
+        *idx += 1;
+    }
+    // A negative value is `-` and a literal.
+    let mut value = String::new();
+    if let Some(TokenTree::Punct(minus)) = tokens.get(*idx).filter(|x| x.to_string() == "-") {
+        value.push(minus.as_char());
+        *idx += 1;
+    }
+    let Some(last) = tokens.get(*idx) else {
+        abort!(name.span(), "Expected `#if {} == value`.", name)
+    };
+    value.push_str(&last.to_string());
+    *idx += 1;
+    let Some(variant) = variant else {
+        return false;
+    };
+    let actual = match def {
+        Some((_, Some(value))) => value.clone(),
+        Some((_, None)) => "true".to_owned(),
+        None => abort!(
+            name.span(),
+            "Shader def `{}` is compared but not set for `{}`.",
+            name,
+            variant
+        ),
+    };
+    let parse = |x: &str| x.trim_end_matches(['i', 'u']).parse::<i64>().ok();
+    let ordering = match (parse(&actual), parse(&value)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => match actual == value {
+            true => std::cmp::Ordering::Equal,
+            false if op == "!=" || op == "==" => std::cmp::Ordering::Less,
+            false => abort!(last.span(), "Only numbers can be compared with `{}`.", op),
+        },
+    };
+    match op.as_str() {
+        "==" => ordering.is_eq(),
+        "!=" => ordering.is_ne(),
+        ">=" => ordering.is_ge(),
+        "<=" => ordering.is_le(),
+        ">" => ordering.is_gt(),
+        "<" => ordering.is_lt(),
+        _ => abort!(last.span(), "Expected `==`, `!=`, `>=`, `<=`, `>` or `<`."),
+    }
+}
@@ -0,0 +1,53 @@
+use wgpu::ShaderStages;
+
+use crate::{layout::str_eq, Shader};
+
+/// A GLSL ES 3.00 translation of a vertex or fragment entry point, for WebGL2.
+///
+/// Emitted by `#![shader]` with the `webgl` feature of `wgsl_ln`. WebGL2 has no binding
+/// locations, resources are found by the names naga gives them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlslEntryPoint {
+    /// Name of the entry point in the WGSL source, `main` in the GLSL source.
+    pub entry_point: &'static str,
+    /// Either `VERTEX` or `FRAGMENT`.
+    pub stage: ShaderStages,
+    /// GLSL ES 3.00 source.
+    pub source: &'static str,
+    /// Uniform blocks, sorted by group and binding.
+    pub uniforms: &'static [GlslUniform],
+    /// Textures combined with their samplers, sorted by name.
+    pub textures: &'static [GlslTexture],
+}
+
+/// A uniform block of a [`GlslEntryPoint`], bound by its block name, i.e. `type_block_0Fragment`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlslUniform {
+    pub name: &'static str,
+    pub group: u32,
+    pub binding: u32,
+}
+
+/// A combined texture and sampler of a [`GlslEntryPoint`], i.e. `_group_1_binding_0_fs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlslTexture {
+    pub name: &'static str,
+    /// Group and binding of the texture.
+    pub texture: (u32, u32),
+    /// Group and binding of the sampler, if the texture is sampled.
+    pub sampler: Option<(u32, u32)>,
+}
+
+impl Shader {
+    /// Find the GLSL ES translation of an entry point by name.
+    pub const fn glsl_entry_point(&self, name: &str) -> Option<&GlslEntryPoint> {
+        let mut i = 0;
+        while i < self.glsl_es.len() {
+            if str_eq(self.glsl_es[i].entry_point, name) {
+                return Some(&self.glsl_es[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::RwLock;
+
+use crate::Shader;
+
+/// An item or module included by a `#![hot_reload(..)]` shader, see [`set_include_resolver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Include<'t> {
+    /// An item pasted with `$name`, by name.
+    Item(&'t str),
+    /// The module path of a `#import`, i.e. `bevy_pbr::mesh_functions`.
+    Import(&'t str),
+}
+
+/// An item pasted into a `#![hot_reload(..)]` shader with `$name`, reflected at compile time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PastedItem {
+    /// Name the item is pasted with, passed to the resolver as [`Include::Item`].
+    pub name: &'static str,
+    /// Source of the item as written in the file.
+    pub source: &'static str,
+}
+
+type Resolver = Box<dyn Fn(Include) -> Option<String> + Send + Sync>;
+
+static RESOLVER: RwLock<Option<Resolver>> = RwLock::new(None);
+
+/// Register a callback giving the live source of what `#![hot_reload(..)]` shaders include,
+/// or `None` to keep the source they were compiled with.
+///
+/// In debug builds, [`Shader::load`] replaces pasted items and `#import` lines of the file
+/// with the sources returned, so edits to libraries take effect along with edits to the shader.
+/// Pasted items are found by the text they were written to the file with, [`PastedItem::source`],
+/// and only their first occurrence is replaced. If a pasted item was edited or reformatted
+/// in the file itself, it cannot be found and the validated source is loaded instead
+/// of a partially reloaded shader.
+///
+/// ```
+/// # use wgsl_ln::{wgsl, wgsl_export};
+/// use wgsl_ln_runtime::{set_include_resolver, Include, Shader};
+///
+/// #[wgsl_export(luma)]
+/// pub static LUMA: &str = wgsl!(
+///     fn luma(color: vec3<f32>) -> f32 {
+///         return dot(color, vec3(0.2126, 0.7152, 0.0722));
+///     }
+/// );
+///
+/// pub static TONEMAP: Shader = wgsl!(
+///     #![shader]
+///     #![hot_reload("target/shaders/tonemap.wgsl")]
+///     fn tonemap(color: vec3<f32>) -> vec3<f32> {
+///         return color / (1.0 + $luma(color));
+///     }
+/// );
+///
+/// // I.e. read from the library's files.
+/// set_include_resolver(|include| match include {
+///     Include::Item("luma") => Some(
+///         "fn luma(color: vec3<f32>) -> f32 { return max(color.r, max(color.g, color.b)); }".into(),
+///     ),
+///     _ => None,
+/// });
+/// # if cfg!(debug_assertions) {
+/// assert!(TONEMAP.load().contains("return max(color.r"));
+/// assert!(TONEMAP.load().contains("fn tonemap"));
+/// # }
+/// ```
+pub fn set_include_resolver(resolver: impl Fn(Include) -> Option<String> + Send + Sync + 'static) {
+    *RESOLVER.write().unwrap() = Some(Box::new(resolver));
+}
+
+/// Replace the includes of a hot reloaded source with their live sources,
+/// `None` if a pasted item is not found in the source as it was written.
+pub(crate) fn resolve_includes(shader: &Shader, file: &str) -> Option<String> {
+    let resolver = RESOLVER.read().unwrap();
+    let Some(resolver) = resolver.as_ref() else {
+        return Some(file.to_owned());
+    };
+    let mut source = file.to_owned();
+    for item in shader.pasted {
+        let Some(live) = resolver(Include::Item(item.name)) else {
+            continue;
+        };
+        if !source.contains(item.source) {
+            return None;
+        }
+        source = source.replacen(item.source, live.trim(), 1);
+    }
+    let mut result = String::with_capacity(source.len());
+    for line in source.split_inclusive('\n') {
+        let live = line
+            .trim_start()
+            .strip_prefix("#import")
+            .and_then(|x| resolver(Include::Import(import_path(x))));
+        match live {
+            Some(live) => {
+                result.push_str(live.trim_end());
+                result.push('\n');
+            }
+            None => result.push_str(line),
+        }
+    }
+    Some(result)
+}
+
+/// The module of `#import path::{a, b}`, `#import path as name` or `#import "file.wgsl"`.
+fn import_path(import: &str) -> &str {
+    let path = import.split_whitespace().next().unwrap_or_default();
+    let path = path.split("::{").next().unwrap_or_default();
+    path.trim_matches('"')
+}
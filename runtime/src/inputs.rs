@@ -0,0 +1,69 @@
+use wgpu::VertexFormat;
+
+/// A `@location` input of a vertex or fragment entry point, reflected at compile time.
+///
+/// ```
+/// # use wgsl_ln::wgsl;
+/// # use wgsl_ln_runtime::{wgpu::VertexFormat, Interpolation, Sampling, Shader, StageInput};
+/// const MESH: Shader = wgsl!(
+///     #![shader]
+///     struct VertexOutput {
+///         @builtin(position) position: vec4<f32>,
+///         @location(0) @interpolate(linear) uv: vec2<f32>,
+///         @location(1) @interpolate(flat) id: u32,
+///     }
+///     @vertex
+///     fn vertex(@location(0) position: vec3<f32>, @location(1) uv: vec2<f32>) -> VertexOutput {
+///         return VertexOutput(vec4(position, 1.0), uv, 0u);
+///     }
+///     @fragment
+///     fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+///         return vec4(in.uv, 0.0, 1.0);
+///     }
+/// );
+/// let vertex = MESH.entry_point("vertex").unwrap();
+/// assert_eq!(vertex.inputs[0].format, VertexFormat::Float32x3);
+/// let fragment = MESH.entry_point("fragment").unwrap();
+/// assert_eq!(
+///     fragment.inputs,
+///     &[
+///         StageInput {
+///             location: 0,
+///             format: VertexFormat::Float32x2,
+///             interpolation: Some((Interpolation::Linear, Sampling::Center)),
+///         },
+///         StageInput {
+///             location: 1,
+///             format: VertexFormat::Uint32,
+///             interpolation: Some((Interpolation::Flat, Sampling::Center)),
+///         },
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StageInput {
+    pub location: u32,
+    /// Format of a vertex attribute of the same type.
+    pub format: VertexFormat,
+    /// `@interpolate(..)` of a fragment input, `None` for vertex inputs.
+    pub interpolation: Option<(Interpolation, Sampling)>,
+}
+
+/// Interpolation type of a fragment input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interpolation {
+    Perspective,
+    Linear,
+    Flat,
+}
+
+/// Interpolation sampling of a fragment input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sampling {
+    Center,
+    Centroid,
+    Sample,
+}
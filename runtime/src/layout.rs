@@ -0,0 +1,124 @@
+use wgpu::{BindGroupLayoutEntry, BindingType, ShaderStages};
+
+use crate::Shader;
+
+/// A resource binding used by an entry point, reflected at compile time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub group: u32,
+    pub binding: u32,
+    /// Stages of entry points using this binding.
+    pub visibility: ShaderStages,
+    pub ty: BindingType,
+    /// Length of a binding array.
+    pub count: Option<std::num::NonZeroU32>,
+    /// `ty` and `count` as a string, for comparing bindings in `const`.
+    pub signature: &'static str,
+}
+
+impl Binding {
+    /// Create a [`BindGroupLayoutEntry`] for this binding.
+    pub const fn entry(&self) -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding: self.binding,
+            visibility: self.visibility,
+            ty: self.ty,
+            count: self.count,
+        }
+    }
+}
+
+/// Panic if shaders disagree about the type of a binding,
+/// a compile time error when used in a `const`.
+///
+/// ```compile_fail
+/// # use wgsl_ln::wgsl;
+/// # use wgsl_ln_runtime::{check_layout, Shader};
+/// const VERTEX: Shader = wgsl!(
+///     #![shader]
+///     @group(0) @binding(0) var<uniform> scale: f32;
+///     @vertex
+///     fn vertex() -> @builtin(position) vec4<f32> {
+///         return vec4(scale);
+///     }
+/// );
+/// const FRAGMENT: Shader = wgsl!(
+///     #![shader]
+///     @group(0) @binding(0) var<uniform> color: vec4<f32>;
+///     @fragment
+///     fn fragment() -> @location(0) vec4<f32> {
+///         return color;
+///     }
+/// );
+/// const _: () = check_layout(&[&VERTEX, &FRAGMENT]);
+/// ```
+pub const fn check_layout(shaders: &[&Shader]) {
+    let mut a = 0;
+    while a < shaders.len() {
+        let mut b = a + 1;
+        while b < shaders.len() {
+            check_pair(shaders[a].bindings, shaders[b].bindings);
+            b += 1;
+        }
+        a += 1;
+    }
+}
+
+const fn check_pair(a: &[Binding], b: &[Binding]) {
+    let mut i = 0;
+    while i < a.len() {
+        let mut j = 0;
+        while j < b.len() {
+            if a[i].group == b[j].group
+                && a[i].binding == b[j].binding
+                && !str_eq(a[i].signature, b[j].signature)
+            {
+                panic!("Shaders disagree about the type of a binding.")
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+pub(crate) const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Merge bindings of shaders into bind group layout entries, indexed by group.
+///
+/// # Panics
+///
+/// If shaders disagree about the type of a binding, see [`check_layout`].
+pub fn bind_group_layout_entries(shaders: &[&Shader]) -> Vec<Vec<BindGroupLayoutEntry>> {
+    check_layout(shaders);
+    let mut groups: Vec<Vec<BindGroupLayoutEntry>> = Vec::new();
+    for binding in shaders.iter().flat_map(|x| x.bindings) {
+        let group = binding.group as usize;
+        if groups.len() <= group {
+            groups.resize_with(group + 1, Vec::new);
+        }
+        match groups[group]
+            .iter_mut()
+            .find(|x| x.binding == binding.binding)
+        {
+            Some(entry) => entry.visibility |= binding.visibility,
+            None => groups[group].push(binding.entry()),
+        }
+    }
+    for group in &mut groups {
+        group.sort_by_key(|x| x.binding);
+    }
+    groups
+}
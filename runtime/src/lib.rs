@@ -0,0 +1,383 @@
+//! Runtime companion of [`wgsl_ln`](https://docs.rs/wgsl_ln).
+//!
+//! # Cached shader modules
+//!
+//! `wgsl!(#![shader] ..)` emits a [`Shader`] with a hash of its source computed
+//! at compile time, which [`DeviceExt::get_or_create_module`] uses to
+//! create each shader module once per device.
+//!
+//! ```no_run
+//! # use wgsl_ln::wgsl;
+//! use wgsl_ln_runtime::{DeviceExt, Shader};
+//!
+//! pub static BLUR: Shader = wgsl!(
+//!     #![shader]
+//!     @compute @workgroup_size(8, 8)
+//!     fn blur() {}
+//! );
+//!
+//! # fn f(device: &wgpu::Device) {
+//! let module = device.get_or_create_module(&BLUR);
+//! # }
+//! ```
+//!
+//! Exported shaders are labeled with the item's name.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! # use wgsl_ln_runtime::Shader;
+//! #[wgsl_export(tonemap)]
+//! pub static TONEMAP: Shader = wgsl!(
+//!     #![shader]
+//!     fn tonemap(color: vec3<f32>) -> vec3<f32> {
+//!         return color / (color + 1.0);
+//!     }
+//! );
+//! assert_eq!(TONEMAP.label, Some("TONEMAP"));
+//! ```
+//!
+//! # Hot reloading
+//!
+//! `#![hot_reload("path.wgsl")]` writes the shader to a path relative to `CARGO_MANIFEST_DIR`.
+//! In debug builds, [`Shader::load`] and [`DeviceExt::get_or_create_module`] read this file,
+//! so edits to it take effect without recompiling, falling back to the validated source.
+//!
+//! ```no_run
+//! # use wgsl_ln::wgsl;
+//! # use wgsl_ln_runtime::Shader;
+//! pub static FOG: Shader = wgsl!(
+//!     #![shader]
+//!     #![hot_reload("target/shaders/fog.wgsl")]
+//!     fn fog(x: f32) -> f32 {
+//!         return x;
+//!     }
+//! );
+//! ```
+//!
+//! Items pasted with `$name` and `#import`s are copies in the file, [`set_include_resolver`]
+//! registers a callback giving their live sources, so a shader and the libraries it includes
+//! are reloaded together.
+//!
+//! # Pipeline layouts
+//!
+//! Bindings used by entry points are reflected into [`Shader::bindings`].
+//! [`DeviceExt::create_pipeline_layout_for`] merges the bindings of a set of shaders,
+//! i.e. a vertex and fragment shader, into a pipeline layout,
+//! [`check_layout`] checks at compile time that they agree about the type of each binding.
+//!
+//! ```no_run
+//! # use wgsl_ln::wgsl;
+//! use wgsl_ln_runtime::{check_layout, DeviceExt, Shader};
+//!
+//! pub const VERTEX: Shader = wgsl!(
+//!     #![shader]
+//!     @group(0) @binding(0) var<uniform> scale: f32;
+//!     @vertex
+//!     fn vertex() -> @builtin(position) vec4<f32> {
+//!         return vec4(scale);
+//!     }
+//! );
+//! pub const FRAGMENT: Shader = wgsl!(
+//!     #![shader]
+//!     @group(0) @binding(0) var<uniform> brightness: f32;
+//!     @group(0) @binding(1) var<uniform> color: vec4<f32>;
+//!     @fragment
+//!     fn fragment() -> @location(0) vec4<f32> {
+//!         return color * brightness;
+//!     }
+//! );
+//! const _: () = check_layout(&[&VERTEX, &FRAGMENT]);
+//!
+//! # fn f(device: &wgpu::Device) {
+//! let (layout, bind_group_layouts) =
+//!     device.create_pipeline_layout_for(Some("pipeline"), &[&VERTEX, &FRAGMENT]);
+//! # }
+//! ```
+//!
+//! # Reflection data
+//!
+//! [`Shader::meta`] collects the entry points and bind groups of a shader into an owned
+//! [`ShaderMeta`], which implements `Serialize` and `Deserialize` with the `serde` feature,
+//! for asset pipelines and tools that would otherwise parse the WGSL source.
+//!
+//! # Pipeline constants
+//!
+//! `override`s are reflected into [`Shader::overrides`], [`Shader::pipeline_constant`]
+//! checks the name and type of an override and gives a [`PipelineConstant`],
+//! whose [`PipelineConstant::value`] is an entry of `PipelineCompilationOptions::constants`.
+//!
+//! # WebGL2
+//!
+//! With the `webgl` feature of `wgsl_ln`, [`Shader::glsl_es`] has a GLSL ES 3.00 translation
+//! of each vertex and fragment entry point, for engines with a WebGL2 fallback,
+//! see [`GlslEntryPoint`].
+//!
+//! # Testing exports
+//!
+//! With the `validate` feature, [`test::validate_all_exports!`] validates every export
+//! listed by `wgsl_catalog!()` on its own under a chosen set of capabilities,
+//! so broken library snippets are caught even if no shader in the crate imports them.
+//!
+//! With the `gpu_test` feature, `wgsl_ln::wgsl_test!` runs shader functions in
+//! compute shaders on a headless device and asserts their results, see [`test`].
+//! `#[wgsl_ln::wgsl_pipeline_test]` creates every pipeline of a [`Shader`] with
+//! [`test::create_pipelines`].
+//!
+//! # Custom passes
+//!
+//! With the `pass` feature, [`pass::Pass`] and [`pass::run`] implement a program that
+//! `passes` in `wgsl_ln.toml` runs on every validated shader, to lint or rewrite it.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use wgpu::{
+    BindGroupLayout, BindGroupLayoutDescriptor, Device, Id, PipelineLayout,
+    PipelineLayoutDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource, ShaderStages,
+};
+
+mod glsl_es;
+mod hot_reload;
+mod inputs;
+mod layout;
+use layout::str_eq;
+mod meta;
+mod overrides;
+#[cfg(feature = "pass")]
+pub mod pass;
+mod targets;
+#[cfg(any(feature = "validate", feature = "gpu_test"))]
+pub mod test;
+pub use glsl_es::{GlslEntryPoint, GlslTexture, GlslUniform};
+pub use hot_reload::{set_include_resolver, Include, PastedItem};
+pub use inputs::{Interpolation, Sampling, StageInput};
+pub use layout::{bind_group_layout_entries, check_layout, Binding};
+pub use meta::{BindGroupMeta, EntryPointMeta, ShaderMeta};
+pub use overrides::{Override, OverrideType, OverrideValue, PipelineConstant};
+pub use targets::{FragmentOutput, OutputKind, TargetError};
+pub use wgpu;
+
+/// A validated WGSL shader, emitted by `wgsl!(#![shader] ..)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shader {
+    /// Debug label, the name of the item for exported shaders.
+    pub label: Option<&'static str>,
+    /// WGSL source.
+    pub source: &'static str,
+    /// Hash of `source`, computed at compile time.
+    pub hash: u128,
+    /// File written by `#![hot_reload(..)]`, loaded in debug builds.
+    pub path: Option<&'static str>,
+    /// Items pasted into a `#![hot_reload(..)]` shader, see [`set_include_resolver`].
+    pub pasted: &'static [PastedItem],
+    /// Bindings used by entry points, sorted by group and binding.
+    ///
+    /// Empty if composed from `naga_oil` modules provided at runtime.
+    pub bindings: &'static [Binding],
+    /// Entry points of the shader.
+    pub entry_points: &'static [EntryPoint],
+    /// `override`s of the shader, see [`Shader::pipeline_constant`].
+    pub overrides: &'static [Override],
+    /// GLSL ES 3.00 translations of the vertex and fragment entry points,
+    /// empty without the `webgl` feature of `wgsl_ln`.
+    pub glsl_es: &'static [GlslEntryPoint],
+}
+
+impl Shader {
+    /// Load the source, in debug builds from the `#![hot_reload(..)]` file if it exists,
+    /// with includes resolved by [`set_include_resolver`], otherwise returns the validated `source`.
+    pub fn load(&self) -> Cow<'static, str> {
+        match self.path {
+            Some(path) if cfg!(debug_assertions) => {
+                let file = std::fs::read_to_string(path).ok();
+                match hot_reload::resolve_includes(self, file.as_deref().unwrap_or(self.source)) {
+                    Some(source) if source != self.source => Cow::Owned(source),
+                    _ => Cow::Borrowed(self.source),
+                }
+            }
+            _ => Cow::Borrowed(self.source),
+        }
+    }
+
+    /// Create a [`ShaderModuleDescriptor`] for this shader, see [`Shader::load`].
+    pub fn descriptor(&self) -> ShaderModuleDescriptor<'static> {
+        ShaderModuleDescriptor {
+            label: self.label,
+            source: ShaderSource::Wgsl(self.load()),
+        }
+    }
+
+    /// Find an entry point by name.
+    pub const fn entry_point(&self, name: &str) -> Option<&EntryPoint> {
+        let mut i = 0;
+        while i < self.entry_points.len() {
+            if str_eq(self.entry_points[i].name, name) {
+                return Some(&self.entry_points[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+}
+
+/// An entry point, reflected at compile time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntryPoint {
+    pub name: &'static str,
+    /// Either `VERTEX`, `FRAGMENT` or `COMPUTE`.
+    pub stage: ShaderStages,
+    /// `@workgroup_size` of a compute entry point, `[0, 0, 0]` otherwise.
+    pub workgroup_size: [u32; 3],
+    /// `@location` inputs of a vertex or fragment entry point, sorted by location.
+    pub inputs: &'static [StageInput],
+    /// `@location` outputs of a fragment entry point, sorted by location.
+    pub outputs: &'static [FragmentOutput],
+}
+
+impl EntryPoint {
+    /// Number of workgroups to dispatch to cover `extent` invocations,
+    /// rounded up in each dimension.
+    ///
+    /// # Panics
+    ///
+    /// If this is not a compute entry point.
+    ///
+    /// ```
+    /// # use wgsl_ln::wgsl;
+    /// # use wgsl_ln_runtime::Shader;
+    /// const BLUR: Shader = wgsl!(
+    ///     #![shader]
+    ///     @compute @workgroup_size(8, 8)
+    ///     fn blur() {}
+    /// );
+    /// let blur = BLUR.entry_point("blur").unwrap();
+    /// assert_eq!(blur.workgroups([1920, 1080, 1]), [240, 135, 1]);
+    /// assert_eq!(blur.workgroups([100, 1, 1]), [13, 1, 1]);
+    /// ```
+    pub const fn workgroups(&self, extent: [u32; 3]) -> [u32; 3] {
+        let [x, y, z] = self.workgroup_size;
+        [
+            extent[0].div_ceil(x),
+            extent[1].div_ceil(y),
+            extent[2].div_ceil(z),
+        ]
+    }
+}
+
+/// 128 bit FNV-1a, the hash of [`Shader::hash`] and the key of cached modules.
+///
+/// ```
+/// # use wgsl_ln::wgsl;
+/// # use wgsl_ln_runtime::{fnv1a, Shader};
+/// const WG_X: u32 = 8;
+/// pub static FILL: Shader = wgsl!(
+///     #![shader]
+///     @compute @workgroup_size(${WG_X})
+///     fn fill() {}
+/// );
+/// pub static CLEAR: Shader = wgsl!(
+///     #![shader]
+///     @compute @workgroup_size(8)
+///     fn clear() {}
+/// );
+/// assert_eq!(FILL.hash, fnv1a(FILL.source.as_bytes()));
+/// assert_eq!(CLEAR.hash, fnv1a(CLEAR.source.as_bytes()));
+/// ```
+pub const fn fnv1a(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013B;
+    let mut hash = OFFSET;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash = (hash ^ bytes[i] as u128).wrapping_mul(PRIME);
+        i += 1;
+    }
+    hash
+}
+
+type ModuleCache = HashMap<(Id<Device>, u128), Arc<ShaderModule>>;
+
+fn module_cache() -> &'static Mutex<ModuleCache> {
+    static CACHE: OnceLock<Mutex<ModuleCache>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// Extension methods for [`Device`].
+pub trait DeviceExt {
+    /// Create a [`ShaderModule`], or return the one previously created
+    /// on this device for a shader with the same hash.
+    ///
+    /// In debug builds, an edited `#![hot_reload(..)]` file creates a new module.
+    fn get_or_create_module(&self, shader: &Shader) -> Arc<ShaderModule>;
+
+    /// Drop cached modules created on this device, i.e. when it is destroyed.
+    fn clear_module_cache(&self);
+
+    /// Create a [`PipelineLayout`] from the merged bindings of shaders,
+    /// and the [`BindGroupLayout`]s it consists of.
+    ///
+    /// # Panics
+    ///
+    /// If shaders disagree about the type of a binding, see [`check_layout`].
+    fn create_pipeline_layout_for(
+        &self,
+        label: Option<&str>,
+        shaders: &[&Shader],
+    ) -> (PipelineLayout, Vec<BindGroupLayout>);
+}
+
+impl DeviceExt for Device {
+    fn get_or_create_module(&self, shader: &Shader) -> Arc<ShaderModule> {
+        let source = shader.load();
+        let hash = match &source {
+            Cow::Borrowed(_) => shader.hash,
+            // Edited `#![hot_reload(..)]` file.
+            Cow::Owned(source) => fnv1a(source.as_bytes()),
+        };
+        let key = (self.global_id(), hash);
+        if let Some(module) = module_cache().lock().unwrap().get(&key) {
+            return module.clone();
+        }
+        // Not holding the lock, `create_shader_module` may take a while.
+        let module = Arc::new(self.create_shader_module(ShaderModuleDescriptor {
+            label: shader.label,
+            source: ShaderSource::Wgsl(source),
+        }));
+        module_cache()
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert(module)
+            .clone()
+    }
+
+    fn clear_module_cache(&self) {
+        let id = self.global_id();
+        module_cache()
+            .lock()
+            .unwrap()
+            .retain(|(device, _), _| *device != id);
+    }
+
+    fn create_pipeline_layout_for(
+        &self,
+        label: Option<&str>,
+        shaders: &[&Shader],
+    ) -> (PipelineLayout, Vec<BindGroupLayout>) {
+        let bind_group_layouts: Vec<_> = bind_group_layout_entries(shaders)
+            .iter()
+            .map(|entries| {
+                self.create_bind_group_layout(&BindGroupLayoutDescriptor { label, entries })
+            })
+            .collect();
+        let layout = self.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label,
+            bind_group_layouts: &bind_group_layouts.iter().collect::<Vec<_>>(),
+            push_constant_ranges: &[],
+        });
+        (layout, bind_group_layouts)
+    }
+}
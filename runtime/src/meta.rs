@@ -0,0 +1,85 @@
+use wgpu::{BindGroupLayoutEntry, ShaderStages};
+
+use crate::{bind_group_layout_entries, FragmentOutput, Shader, StageInput};
+
+/// Owned reflection data of a [`Shader`], serializable with the `serde` feature.
+///
+/// ```
+/// # use wgsl_ln::wgsl;
+/// # use wgsl_ln_runtime::Shader;
+/// const BLUR: Shader = wgsl!(
+///     #![shader("blur")]
+///     @group(0) @binding(0) var<storage, read_write> pixels: array<vec4<f32>>;
+///     @compute @workgroup_size(8, 8)
+///     fn blur() {
+///         pixels[0] = vec4(0.0);
+///     }
+/// );
+/// let meta = BLUR.meta();
+/// assert_eq!(meta.label.as_deref(), Some("blur"));
+/// assert_eq!(meta.entry_points[0].workgroup_size, [8, 8, 1]);
+/// assert_eq!(meta.bind_groups[0].entries[0].binding, 0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShaderMeta {
+    pub label: Option<String>,
+    /// Hash of the source, see [`Shader::hash`].
+    pub hash: u128,
+    pub entry_points: Vec<EntryPointMeta>,
+    /// Bind groups used by entry points, sorted by group.
+    pub bind_groups: Vec<BindGroupMeta>,
+}
+
+/// Owned reflection data of an [`EntryPoint`](crate::EntryPoint).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntryPointMeta {
+    pub name: String,
+    /// Either `VERTEX`, `FRAGMENT` or `COMPUTE`.
+    pub stage: ShaderStages,
+    /// `@workgroup_size` of a compute entry point, `[0, 0, 0]` otherwise.
+    pub workgroup_size: [u32; 3],
+    /// `@location` inputs of a vertex or fragment entry point, sorted by location.
+    pub inputs: Vec<StageInput>,
+    /// `@location` outputs of a fragment entry point, sorted by location.
+    pub outputs: Vec<FragmentOutput>,
+}
+
+/// Bindings of a bind group, sorted by binding.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BindGroupMeta {
+    pub group: u32,
+    pub entries: Vec<BindGroupLayoutEntry>,
+}
+
+impl Shader {
+    /// Owned reflection data of this shader.
+    pub fn meta(&self) -> ShaderMeta {
+        ShaderMeta {
+            label: self.label.map(String::from),
+            hash: self.hash,
+            entry_points: self
+                .entry_points
+                .iter()
+                .map(|entry| EntryPointMeta {
+                    name: entry.name.to_owned(),
+                    stage: entry.stage,
+                    workgroup_size: entry.workgroup_size,
+                    inputs: entry.inputs.to_vec(),
+                    outputs: entry.outputs.to_vec(),
+                })
+                .collect(),
+            bind_groups: bind_group_layout_entries(&[self])
+                .into_iter()
+                .enumerate()
+                .filter(|(_, entries)| !entries.is_empty())
+                .map(|(group, entries)| BindGroupMeta {
+                    group: group as u32,
+                    entries,
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::marker::PhantomData;
+
+use crate::{layout::str_eq, Shader};
+
+/// An `override` of a shader, reflected at compile time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Override {
+    pub name: &'static str,
+    /// `@id` of the override.
+    pub id: Option<u16>,
+    /// Key of the override in `PipelineCompilationOptions::constants`,
+    /// the `@id` as a decimal number if present, otherwise the name.
+    pub key: &'static str,
+    pub ty: OverrideType,
+}
+
+/// Scalar type of an [`Override`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverrideType {
+    Bool,
+    I32,
+    U32,
+    F32,
+}
+
+/// A Rust type of an `override`.
+pub trait OverrideValue: Copy {
+    const TYPE: OverrideType;
+
+    /// The value as it is passed to `wgpu`.
+    fn to_f64(self) -> f64;
+}
+
+impl OverrideValue for bool {
+    const TYPE: OverrideType = OverrideType::Bool;
+
+    fn to_f64(self) -> f64 {
+        self as u8 as f64
+    }
+}
+
+impl OverrideValue for i32 {
+    const TYPE: OverrideType = OverrideType::I32;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl OverrideValue for u32 {
+    const TYPE: OverrideType = OverrideType::U32;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl OverrideValue for f32 {
+    const TYPE: OverrideType = OverrideType::F32;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+/// The key of an `override` of type `T`, see [`Shader::pipeline_constant`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineConstant<T> {
+    /// Key in `PipelineCompilationOptions::constants`.
+    pub key: &'static str,
+    ty: PhantomData<fn(T)>,
+}
+
+impl<T: OverrideValue> PipelineConstant<T> {
+    /// An entry of `PipelineCompilationOptions::constants` setting the override to `value`.
+    pub fn value(self, value: T) -> (String, f64) {
+        (self.key.to_owned(), value.to_f64())
+    }
+}
+
+impl Shader {
+    /// Find an `override` by name.
+    pub const fn find_override(&self, name: &str) -> Option<&Override> {
+        let mut i = 0;
+        while i < self.overrides.len() {
+            if str_eq(self.overrides[i].name, name) {
+                return Some(&self.overrides[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// The key of an `override` of type `T`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no `override` named `name` or its type is not `T`,
+    /// a compile time error when used in a `const`.
+    ///
+    /// ```
+    /// # use wgsl_ln::wgsl;
+    /// # use wgsl_ln_runtime::{PipelineConstant, Shader};
+    /// const BLUR: Shader = wgsl!(
+    ///     #![shader]
+    ///     @id(0) override samples: u32 = 4u;
+    ///     override radius: f32;
+    ///     @compute @workgroup_size(8, 8)
+    ///     fn blur() {
+    ///         _ = f32(samples) * radius;
+    ///     }
+    /// );
+    /// const SAMPLES: PipelineConstant<u32> = BLUR.pipeline_constant("samples");
+    /// const RADIUS: PipelineConstant<f32> = BLUR.pipeline_constant("radius");
+    ///
+    /// let constants = std::collections::HashMap::from([SAMPLES.value(16), RADIUS.value(2.5)]);
+    /// assert_eq!(constants["0"], 16.0);
+    /// assert_eq!(constants["radius"], 2.5);
+    /// ```
+    pub const fn pipeline_constant<T: OverrideValue>(&self, name: &str) -> PipelineConstant<T> {
+        let Some(item) = self.find_override(name) else {
+            panic!("No `override` with this name.")
+        };
+        if item.ty as u8 != T::TYPE as u8 {
+            panic!("The `override` is of a different type.")
+        }
+        PipelineConstant {
+            key: item.key,
+            ty: PhantomData,
+        }
+    }
+}
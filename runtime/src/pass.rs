@@ -0,0 +1,128 @@
+//! Custom compile-time passes, listed in `passes` of `wgsl_ln.toml`.
+//!
+//! A pass is a binary implementing [`Pass`] that calls [`run`] in `main`.
+//! `wgsl!` runs it on every validated shader of the crate, with the shader on stdin.
+//! Errors fail the build at the `wgsl!` call, warnings are shown there,
+//! and a modified module replaces the shader after validating it again.
+//!
+//! ```no_run
+//! use wgsl_ln_runtime::pass::{naga, run, Diagnostics, Pass};
+//!
+//! /// Bindings of the studio are in group 0 to 3.
+//! struct BindGroups;
+//!
+//! impl Pass for BindGroups {
+//!     fn run(&mut self, _: Option<&str>, module: &mut naga::Module, diagnostics: &mut Diagnostics) -> bool {
+//!         for (_, var) in module.global_variables.iter() {
+//!             if let Some(binding) = var.binding.as_ref().filter(|x| x.group > 3) {
+//!                 diagnostics.error(format!(
+//!                     "`{}` is in group {}, use 0 to 3.",
+//!                     var.name.as_deref().unwrap_or_default(),
+//!                     binding.group
+//!                 ));
+//!             }
+//!         }
+//!         false
+//!     }
+//! }
+//!
+//! fn main() {
+//!     run(BindGroups)
+//! }
+//! ```
+
+use std::{
+    fmt::Display,
+    io::{Read, Write},
+};
+
+pub use naga;
+use naga::{
+    back::wgsl::WriterFlags,
+    valid::{Capabilities, ValidationFlags, Validator},
+    Module,
+};
+
+/// Errors and warnings of a pass, reported at the `wgsl!` call.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl Diagnostics {
+    /// Fail the build with a message.
+    pub fn error(&mut self, message: impl Display) {
+        self.errors.push(message.to_string());
+    }
+
+    /// Show a warning.
+    pub fn warning(&mut self, message: impl Display) {
+        self.warnings.push(message.to_string());
+    }
+}
+
+/// A lint or rewrite of validated shaders.
+pub trait Pass {
+    /// Check or modify a shader, `label` is its `#![label(..)]` or the name of the exported item.
+    ///
+    /// Returns true if the module was modified.
+    fn run(
+        &mut self,
+        label: Option<&str>,
+        module: &mut Module,
+        diagnostics: &mut Diagnostics,
+    ) -> bool;
+}
+
+/// Run a pass on the shader on stdin, the `main` of a pass binary.
+///
+/// A modified module is written to stdout as WGSL, diagnostics are written to stderr
+/// as `error: ..` and `warning: ..` lines. Exits with 1 if there are errors.
+pub fn run(mut pass: impl Pass) -> ! {
+    let mut source = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut source) {
+        exit_with(&format!("error: unable to read the shader, {}", e));
+    }
+    let mut module = match naga::front::wgsl::parse_str(&source) {
+        Ok(module) => module,
+        Err(e) => exit_with(&format!("error: {}", e)),
+    };
+    let label = std::env::var("WGSL_LN_LABEL").ok();
+    let mut diagnostics = Diagnostics::default();
+    let modified = pass.run(label.as_deref(), &mut module, &mut diagnostics);
+    let mut stderr = std::io::stderr().lock();
+    for warning in &diagnostics.warnings {
+        let _ = writeln!(stderr, "warning: {}", single_line(warning));
+    }
+    for error in &diagnostics.errors {
+        let _ = writeln!(stderr, "error: {}", single_line(error));
+    }
+    if !diagnostics.errors.is_empty() {
+        std::process::exit(1)
+    }
+    if modified {
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all()).validate(&module);
+        let output = info
+            .map_err(|e| e.into_inner().to_string())
+            .and_then(|info| {
+                naga::back::wgsl::write_string(&module, &info, WriterFlags::empty())
+                    .map_err(|e| e.to_string())
+            });
+        match output {
+            Ok(output) => print!("{}", output),
+            Err(e) => exit_with(&format!("error: the modified module is invalid, {}", e)),
+        }
+    }
+    std::process::exit(0)
+}
+
+/// Diagnostics are read line by line.
+fn single_line(message: &str) -> String {
+    message.replace('\n', " ")
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", single_line(message));
+    std::process::exit(1)
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use wgpu::{TextureFormat, TextureSampleType};
+
+use crate::EntryPoint;
+
+/// A `@location` output of a fragment entry point, reflected at compile time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FragmentOutput {
+    pub location: u32,
+    pub kind: OutputKind,
+    /// Number of components, `1` for scalars.
+    pub components: u8,
+}
+
+/// Scalar type of a [`FragmentOutput`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputKind {
+    Float,
+    Sint,
+    Uint,
+}
+
+impl FragmentOutput {
+    /// Returns true if this output can be written to a color target of `format`.
+    pub fn is_compatible(&self, format: TextureFormat) -> bool {
+        let kind = match format.sample_type(None, None) {
+            Some(TextureSampleType::Float { .. }) => OutputKind::Float,
+            Some(TextureSampleType::Sint) => OutputKind::Sint,
+            Some(TextureSampleType::Uint) => OutputKind::Uint,
+            _ => return false,
+        };
+        kind == self.kind && self.components >= format.components()
+    }
+}
+
+/// Color targets that do not match the outputs of a fragment entry point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetError {
+    /// The number of color targets is not the number of output locations.
+    Count { expected: usize, found: usize },
+    /// The format of a color target is incompatible with the output, or there is no output.
+    Format {
+        location: u32,
+        format: TextureFormat,
+    },
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetError::Count { expected, found } => {
+                write!(f, "Expected {} color targets, found {}.", expected, found)
+            }
+            TargetError::Format { location, format } => write!(
+                f,
+                "Color target {:?} is incompatible with `@location({})`.",
+                format, location
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TargetError {}
+
+impl EntryPoint {
+    /// Check color target formats, indexed by location, against the outputs of a fragment entry point.
+    ///
+    /// ```
+    /// # use wgsl_ln::wgsl;
+    /// # use wgsl_ln_runtime::{wgpu::TextureFormat, Shader, TargetError};
+    /// const DEFERRED: Shader = wgsl!(
+    ///     #![shader]
+    ///     struct GBuffer {
+    ///         @location(0) albedo: vec4<f32>,
+    ///         @location(1) id: u32,
+    ///     }
+    ///     @fragment
+    ///     fn deferred() -> GBuffer {
+    ///         return GBuffer(vec4(1.0), 1u);
+    ///     }
+    /// );
+    /// let deferred = DEFERRED.entry_point("deferred").unwrap();
+    /// let targets = [Some(TextureFormat::Rgba8Unorm), Some(TextureFormat::R32Uint)];
+    /// assert_eq!(deferred.check_targets(&targets), Ok(()));
+    /// assert_eq!(
+    ///     deferred.check_targets(&targets[..1]),
+    ///     Err(TargetError::Count { expected: 2, found: 1 })
+    /// );
+    /// ```
+    pub fn check_targets(&self, formats: &[Option<TextureFormat>]) -> Result<(), TargetError> {
+        let expected = self
+            .outputs
+            .iter()
+            .map(|x| x.location as usize + 1)
+            .max()
+            .unwrap_or(0);
+        if formats.len() != expected {
+            return Err(TargetError::Count {
+                expected,
+                found: formats.len(),
+            });
+        }
+        for (location, format) in formats.iter().enumerate() {
+            let Some(format) = *format else {
+                continue;
+            };
+            let location = location as u32;
+            match self.outputs.iter().find(|x| x.location == location) {
+                Some(output) if output.is_compatible(format) => (),
+                _ => return Err(TargetError::Format { location, format }),
+            }
+        }
+        Ok(())
+    }
+}
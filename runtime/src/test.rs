@@ -0,0 +1,548 @@
+//! Test helpers, enabled by the `validate` and `gpu_test` features.
+//!
+//! With `gpu_test`, `wgsl_ln::wgsl_test!` runs functions in compute shaders
+//! on a shared headless device.
+//!
+//! ```no_run
+//! # #[cfg(feature = "gpu_test")] {
+//! # use wgsl_ln::wgsl_test;
+//! wgsl_test!(
+//!     #![case(lerp(1.0, 3.0, 0.5) == 2.0)]
+//!     #![case(add([1, 2], [3, 4]) == [4, 6])]
+//!     fn lerp(a: f32, b: f32, t: f32) -> f32 {
+//!         return a + (b - a) * t;
+//!     }
+//!     fn add(a: vec2<i32>, b: vec2<i32>) -> vec2<i32> {
+//!         return a + b;
+//!     }
+//! );
+//! # }
+//! ```
+
+#[cfg(feature = "gpu_test")]
+use std::{
+    fmt::Debug,
+    future::Future,
+    ops::Range,
+    pin::pin,
+    sync::{Arc, OnceLock},
+    task::{Context, Poll, Wake},
+    thread::Thread,
+};
+
+#[cfg(feature = "validate")]
+pub use naga::valid::Capabilities;
+#[cfg(feature = "validate")]
+use naga::valid::{ValidationFlags, Validator};
+#[cfg(feature = "gpu_test")]
+use wgpu::{util::DeviceExt, Device, Queue, ShaderStages, TextureFormat, VertexFormat};
+
+#[cfg(feature = "gpu_test")]
+use crate::{
+    bind_group_layout_entries, FragmentOutput, Interpolation, OutputKind, Shader, StageInput,
+};
+
+/// Validate every export of the crate on its own in a test, requires `wgsl_catalog!()`
+/// at the end of the crate root.
+///
+/// Exports are validated with the default [`Capabilities`] unless specified,
+/// `in path` selects a catalog generated with `wgsl_catalog!(name)`.
+///
+/// ```
+/// # use wgsl_ln::{wgsl, wgsl_catalog, wgsl_export};
+/// use wgsl_ln_runtime::test::{validate_all_exports, Capabilities};
+///
+/// #[wgsl_export(manhattan_distance)]
+/// pub static MANHATTAN_DISTANCE: &str = wgsl!(
+///     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+///         return abs(a.x - b.x) + abs(a.y - b.y);
+///     }
+/// );
+///
+/// wgsl_catalog!(shaders);
+///
+/// // In a `#[test]`.
+/// validate_all_exports!(in shaders);
+/// validate_all_exports!(in shaders, Capabilities::FLOAT64);
+/// ```
+#[cfg(feature = "validate")]
+#[macro_export]
+#[doc(hidden)]
+// The catalog is in the crate of the test.
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! __validate_all_exports {
+    (@capabilities) => {
+        $crate::test::Capabilities::default()
+    };
+    (@capabilities $capabilities: expr) => {
+        $capabilities
+    };
+    (in $catalog: path $(, $capabilities: expr)?) => {
+        $crate::test::validate_exports(
+            {
+                use $catalog as catalog;
+                catalog::SOURCES
+            },
+            $crate::__validate_all_exports!(@capabilities $($capabilities)?),
+        )
+    };
+    ($($capabilities: expr)?) => {
+        $crate::__validate_all_exports!(in crate::wgsl_catalog $(, $capabilities)?)
+    };
+}
+
+#[cfg(feature = "validate")]
+#[doc(inline)]
+pub use __validate_all_exports as validate_all_exports;
+
+/// Parse and validate a WGSL source with all validation flags, errors are naga's report.
+#[cfg(feature = "validate")]
+pub fn validate_source(source: &str, capabilities: Capabilities) -> Result<(), String> {
+    let module = naga::front::wgsl::parse_str(source).map_err(|e| e.emit_to_string(source))?;
+    Validator::new(ValidationFlags::all(), capabilities)
+        .validate(&module)
+        .map(|_| ())
+        .map_err(|e| e.emit_to_string(source))
+}
+
+#[cfg(feature = "validate")]
+/// Validate `(name, source)` pairs, panics listing every export that fails.
+#[track_caller]
+pub fn validate_exports(sources: &[(&str, &str)], capabilities: Capabilities) {
+    let mut errors = Vec::new();
+    for (name, source) in sources {
+        if let Err(e) = validate_source(source, capabilities) {
+            errors.push(format!("${}: {}", name, e));
+        }
+    }
+    if !errors.is_empty() {
+        panic!(
+            "{} of {} exports failed validation with {:?}:\n{}",
+            errors.len(),
+            sources.len(),
+            capabilities,
+            errors.join("\n")
+        );
+    }
+}
+
+/// A host-shareable value passed to or returned from a shader by `wgsl_test!`.
+#[cfg(feature = "gpu_test")]
+pub trait GpuValue: Sized + Debug {
+    /// Append the little endian bytes of the value.
+    fn write(&self, bytes: &mut Vec<u8>);
+    /// Read the value from the start of `bytes`.
+    fn read(bytes: &[u8]) -> Self;
+    /// Generate a value with components in `range`, used by `#[wgsl_property]`.
+    fn generate(rng: &mut Rng, range: &Range<f32>) -> Self;
+    /// Compare to a reference, floats may differ by `tolerance` relative to the larger value,
+    /// or absolute below 1.
+    fn approx_eq(&self, reference: &Self, tolerance: f32) -> bool;
+}
+
+#[cfg(feature = "gpu_test")]
+macro_rules! impl_gpu_value {
+    ($($ty: ty),*) => {$(
+        impl GpuValue for $ty {
+            fn write(&self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read(bytes: &[u8]) -> Self {
+                <$ty>::from_le_bytes(bytes[..4].try_into().unwrap())
+            }
+
+            fn generate(rng: &mut Rng, range: &Range<f32>) -> Self {
+                let start = range.start.max(<$ty>::MIN as f32);
+                let end = range.end.min(<$ty>::MAX as f32).max(start);
+                rng.next_f32(start..end).floor() as $ty
+            }
+
+            fn approx_eq(&self, reference: &Self, _: f32) -> bool {
+                self == reference
+            }
+        }
+    )*};
+}
+#[cfg(feature = "gpu_test")]
+impl_gpu_value!(i32, u32);
+
+#[cfg(feature = "gpu_test")]
+impl GpuValue for f32 {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        f32::from_le_bytes(bytes[..4].try_into().unwrap())
+    }
+
+    fn generate(rng: &mut Rng, range: &Range<f32>) -> Self {
+        rng.next_f32(range.clone())
+    }
+
+    fn approx_eq(&self, reference: &Self, tolerance: f32) -> bool {
+        // `NaN` is only equal to `NaN`, infinities only to themselves.
+        (self.is_nan() && reference.is_nan())
+            || self == reference
+            || (self - reference).abs() <= tolerance * self.abs().max(reference.abs()).max(1.0)
+    }
+}
+
+/// `vecN<T>`.
+#[cfg(feature = "gpu_test")]
+impl<T: GpuValue, const N: usize> GpuValue for [T; N] {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        for value in self {
+            value.write(bytes);
+        }
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        std::array::from_fn(|i| T::read(&bytes[i * 4..]))
+    }
+
+    fn generate(rng: &mut Rng, range: &Range<f32>) -> Self {
+        std::array::from_fn(|_| T::generate(rng, range))
+    }
+
+    fn approx_eq(&self, reference: &Self, tolerance: f32) -> bool {
+        self.iter()
+            .zip(reference)
+            .all(|(a, b)| a.approx_eq(b, tolerance))
+    }
+}
+
+/// A small deterministic random number generator (xorshift64) for `#[wgsl_property]`,
+/// so failures reproduce with the same seed.
+#[cfg(feature = "gpu_test")]
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+#[cfg(feature = "gpu_test")]
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The state must not be zero.
+        Rng((seed ^ 0x9E37_79B9_7F4A_7C15).max(1))
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 32) as u32
+    }
+
+    /// A float uniformly distributed in `range`.
+    pub fn next_f32(&mut self, range: Range<f32>) -> f32 {
+        let t = (self.next_u32() >> 8) as f32 / (1 << 24) as f32;
+        range.start + (range.end - range.start) * t
+    }
+}
+
+/// Device shared by every test, `None` if no adapter is available.
+#[cfg(feature = "gpu_test")]
+fn device() -> &'static (Device, Queue) {
+    static DEVICE: OnceLock<Option<(Device, Queue)>> = OnceLock::new();
+    let device = DEVICE.get_or_init(|| {
+        let instance = wgpu::Instance::default();
+        // Software adapters like lavapipe are used if there is no GPU.
+        let adapter = [false, true]
+            .into_iter()
+            .find_map(|force_fallback_adapter| {
+                block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                    force_fallback_adapter,
+                    ..Default::default()
+                }))
+            })?;
+        let descriptor = wgpu::DeviceDescriptor {
+            label: Some("wgsl_test"),
+            required_features: wgpu::Features::empty(),
+            required_limits: adapter.limits(),
+        };
+        block_on(adapter.request_device(&descriptor, None)).ok()
+    });
+    match device {
+        Some(device) => device,
+        None => panic!("`wgsl_test!` requires a GPU or software adapter, none were found."),
+    }
+}
+
+/// Run `workgroups` workgroups of `entry_point` with `inputs` bound to `@binding(0..n)`
+/// of `@group(0)`, and return the contents of the output at `@binding(n)`.
+#[cfg(feature = "gpu_test")]
+pub fn run_compute(
+    source: &str,
+    entry_point: &str,
+    inputs: &[Vec<u8>],
+    output: u64,
+    workgroups: u32,
+) -> Vec<u8> {
+    assert!(
+        (1..=65535).contains(&workgroups),
+        "Expected 1 to 65535 workgroups, found {}.",
+        workgroups
+    );
+    let (device, queue) = device();
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(entry_point),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+    let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(entry_point),
+        layout: None,
+        module: &module,
+        entry_point,
+        compilation_options: Default::default(),
+    });
+    let mut buffers: Vec<_> = inputs
+        .iter()
+        .map(|bytes| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytes,
+                usage: wgpu::BufferUsages::STORAGE,
+            })
+        })
+        .collect();
+    buffers.push(device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: output,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    }));
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: output,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let entries: Vec<_> = buffers
+        .iter()
+        .enumerate()
+        .map(|(i, buffer)| wgpu::BindGroupEntry {
+            binding: i as u32,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect();
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: None,
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &entries,
+    });
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+        let mut pass = encoder.begin_compute_pass(&Default::default());
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.dispatch_workgroups(workgroups, 1, 1);
+    }
+    encoder.copy_buffer_to_buffer(buffers.last().unwrap(), 0, &staging, 0, output);
+    queue.submit([encoder.finish()]);
+    let slice = staging.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+    let bytes = slice.get_mapped_range().to_vec();
+    staging.unmap();
+    bytes
+}
+
+/// Create every pipeline of a shader on the shared headless device with its reflected layout,
+/// and panic with the errors of the pipelines that failed.
+///
+/// Vertex entry points get a vertex buffer per input, fragment entry points a stub vertex
+/// stage providing their inputs and color targets compatible with their outputs.
+#[cfg(feature = "gpu_test")]
+#[track_caller]
+pub fn create_pipelines(shader: &Shader) {
+    let (device, _) = device();
+    let label = shader.label.unwrap_or("shader");
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = device.create_shader_module(shader.descriptor());
+    if let Some(error) = block_on(device.pop_error_scope()) {
+        panic!("Unable to create shader module `{}`: {}", label, error);
+    }
+    let bind_group_layouts: Vec<_> = bind_group_layout_entries(&[shader])
+        .iter()
+        .map(|entries| {
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries,
+            })
+        })
+        .collect();
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &bind_group_layouts.iter().collect::<Vec<_>>(),
+        push_constant_ranges: &[],
+    });
+    let mut errors = Vec::new();
+    for entry in shader.entry_points {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let stub;
+        if entry.stage == ShaderStages::COMPUTE {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry.name),
+                layout: Some(&layout),
+                module: &module,
+                entry_point: entry.name,
+                compilation_options: Default::default(),
+            });
+        } else {
+            let attributes: Vec<_> = entry
+                .inputs
+                .iter()
+                .map(|input| wgpu::VertexAttribute {
+                    format: input.format,
+                    offset: 0,
+                    shader_location: input.location,
+                })
+                .collect();
+            let buffers: Vec<_> = attributes
+                .iter()
+                .map(|attribute| wgpu::VertexBufferLayout {
+                    array_stride: attribute.format.size(),
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: std::slice::from_ref(attribute),
+                })
+                .collect();
+            let targets: Vec<_> = (0..entry
+                .outputs
+                .iter()
+                .map(|x| x.location + 1)
+                .max()
+                .unwrap_or(0))
+                .map(|location| {
+                    let output = entry.outputs.iter().find(|x| x.location == location)?;
+                    Some(wgpu::ColorTargetState::from(color_target(output)))
+                })
+                .collect();
+            let (vertex, fragment) = match entry.stage {
+                ShaderStages::VERTEX => (
+                    wgpu::VertexState {
+                        module: &module,
+                        entry_point: entry.name,
+                        compilation_options: Default::default(),
+                        buffers: &buffers,
+                    },
+                    None,
+                ),
+                _ => {
+                    stub = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                        label: Some("wgsl_ln_stub"),
+                        source: wgpu::ShaderSource::Wgsl(stub_vertex(entry.inputs).into()),
+                    });
+                    (
+                        wgpu::VertexState {
+                            module: &stub,
+                            entry_point: "wgsl_ln_stub",
+                            compilation_options: Default::default(),
+                            buffers: &[],
+                        },
+                        Some(wgpu::FragmentState {
+                            module: &module,
+                            entry_point: entry.name,
+                            compilation_options: Default::default(),
+                            targets: &targets,
+                        }),
+                    )
+                }
+            };
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(entry.name),
+                layout: Some(&layout),
+                vertex,
+                primitive: Default::default(),
+                depth_stencil: None,
+                multisample: Default::default(),
+                fragment,
+                multiview: None,
+            });
+        }
+        if let Some(error) = block_on(device.pop_error_scope()) {
+            errors.push(format!("`{}`: {}", entry.name, error));
+        }
+    }
+    if !errors.is_empty() {
+        panic!(
+            "Unable to create {} of {} pipelines of `{}`:\n{}",
+            errors.len(),
+            shader.entry_points.len(),
+            label,
+            errors.join("\n")
+        );
+    }
+}
+
+/// A renderable format for an output, with at most as many components.
+#[cfg(feature = "gpu_test")]
+fn color_target(output: &FragmentOutput) -> TextureFormat {
+    use TextureFormat::*;
+    let formats = match output.kind {
+        OutputKind::Float => [R8Unorm, Rg8Unorm, Rg8Unorm, Rgba8Unorm],
+        OutputKind::Sint => [R32Sint, Rg32Sint, Rg32Sint, Rgba32Sint],
+        OutputKind::Uint => [R32Uint, Rg32Uint, Rg32Uint, Rgba32Uint],
+    };
+    formats[output.components.clamp(1, 4) as usize - 1]
+}
+
+/// A vertex shader providing the inputs of a fragment entry point.
+#[cfg(feature = "gpu_test")]
+fn stub_vertex(inputs: &[StageInput]) -> String {
+    let mut source = String::from("struct Output {\n@builtin(position) position: vec4<f32>,\n");
+    for input in inputs {
+        let ty = match input.format {
+            VertexFormat::Float32 => "f32",
+            VertexFormat::Float32x2 => "vec2<f32>",
+            VertexFormat::Float32x3 => "vec3<f32>",
+            VertexFormat::Float32x4 => "vec4<f32>",
+            VertexFormat::Sint32 => "i32",
+            VertexFormat::Sint32x2 => "vec2<i32>",
+            VertexFormat::Sint32x3 => "vec3<i32>",
+            VertexFormat::Sint32x4 => "vec4<i32>",
+            VertexFormat::Uint32 => "u32",
+            VertexFormat::Uint32x2 => "vec2<u32>",
+            VertexFormat::Uint32x3 => "vec3<u32>",
+            VertexFormat::Uint32x4 => "vec4<u32>",
+            format => panic!("Unsupported fragment input {:?}.", format),
+        };
+        let interpolation = match input.interpolation {
+            Some((Interpolation::Flat, _)) => "flat".to_owned(),
+            Some((interpolation, sampling)) => {
+                format!("{:?}, {:?}", interpolation, sampling).to_lowercase()
+            }
+            None => "perspective".to_owned(),
+        };
+        source.push_str(&format!(
+            "@location({}) @interpolate({}) input_{}: {},\n",
+            input.location, interpolation, input.location, ty
+        ));
+    }
+    source.push_str(
+        "}\n@vertex\nfn wgsl_ln_stub() -> Output {\nvar output: Output;\nreturn output;\n}\n",
+    );
+    source
+}
+
+/// Wait for a future on the current thread, wgpu futures resolve on native backends
+/// without polling the device.
+#[cfg(feature = "gpu_test")]
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(Unpark(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Const functions joining shaders with the values of `${..}`,
+//! called by the code `wgsl!` emits, and [`substitute`] for placeholders left to runtime.
+
+use std::fmt;
+
+/// The value of a `${..}`, written as a WGSL literal.
+#[derive(Debug, Clone, Copy)]
+pub enum Value {
+    /// An argument of `@workgroup_size`, must be positive.
+    WorkgroupSize(u32),
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+}
+
+/// Longer than any literal, the longest is `(-2147483647i - 1i)`.
+const MAX_LEN: usize = 24;
+
+/// Length of `parts` joined by the `values`.
+pub const fn len(parts: &[&str], values: &[Value]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        len += parts[i].len();
+        i += 1;
+    }
+    let mut i = 0;
+    while i < values.len() {
+        len += literal(values[i]).1;
+        i += 1;
+    }
+    len
+}
+
+/// Join `parts` by the `values`, `parts` has one more element than `values`.
+///
+/// # Panics
+///
+/// If a `@workgroup_size` is `0` or a float is not finite.
+pub const fn join<const N: usize>(parts: &[&str], values: &[Value]) -> [u8; N] {
+    let mut result = [0; N];
+    let mut at = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        at = copy(&mut result, at, parts[i].as_bytes());
+        if i < values.len() {
+            let (bytes, len) = literal(values[i]);
+            at = copy(&mut result, at, bytes.split_at(len).0);
+        }
+        i += 1;
+    }
+    result
+}
+
+/// The bytes of [`join`] as a string.
+pub const fn as_str(bytes: &[u8]) -> &str {
+    match core::str::from_utf8(bytes) {
+        Ok(string) => string,
+        Err(_) => panic!("Invalid UTF-8."),
+    }
+}
+
+const fn copy<const N: usize>(result: &mut [u8; N], mut at: usize, bytes: &[u8]) -> usize {
+    let mut i = 0;
+    while i < bytes.len() {
+        result[at] = bytes[i];
+        at += 1;
+        i += 1;
+    }
+    at
+}
+
+/// Why [`substitute`] failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubstituteError {
+    /// A placeholder without a value.
+    NoValue(String),
+    /// A value without a placeholder.
+    NoPlaceholder(String),
+    /// A value with no WGSL literal, a `@workgroup_size` of `0` or a float that is not finite.
+    InvalidValue(String),
+    /// The result failed validation, only checked in debug builds with the `validate` feature.
+    Invalid(String),
+}
+
+impl fmt::Display for SubstituteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubstituteError::NoValue(name) => write!(f, "Placeholder `${}` has no value.", name),
+            SubstituteError::NoPlaceholder(name) => {
+                write!(f, "Value `{}` has no placeholder `${}`.", name, name)
+            }
+            SubstituteError::InvalidValue(name) => {
+                write!(f, "Value `{}` has no WGSL literal.", name)
+            }
+            SubstituteError::Invalid(e) => write!(f, "Substituted shader failed validation: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SubstituteError {}
+
+/// Replace `$name` placeholders, written as `$$name` in `wgsl!`, with WGSL literals of their values.
+/// A `$` in a comment or not followed by a name is kept.
+///
+/// With the `validate` feature, the result is validated by naga in debug builds,
+/// unless it uses items declared with `$extern` other than the placeholders.
+///
+/// # Errors
+///
+/// If a placeholder has no value, a value has no placeholder, a `@workgroup_size` is `0`,
+/// a float is not finite, or in debug builds with the `validate` feature, if the result
+/// fails validation.
+///
+/// ```
+/// # use wgsl_ln::{wgsl, substitute, SubstituteError, Value};
+/// pub static BLUR: &str = wgsl!(
+///     $extern fn sample(offset: f32) -> vec4<f32>;
+///     $extern override radius: f32;
+///
+///     note!("Costs 2 samples per $radius.");
+///     fn blur() -> vec4<f32> {
+///         return (sample(-$$radius) + sample($$radius)) * 0.5;
+///     }
+/// );
+/// // `sample` is linked in elsewhere, so the result is not validated.
+/// let source = substitute(BLUR, &[("radius", Value::F32(2.0))]).unwrap();
+/// assert!(source.contains("per $radius."));
+/// assert!(source.contains("-0x1.000000p+1f"));
+/// assert_eq!(
+///     substitute(BLUR, &[]),
+///     Err(SubstituteError::NoValue("radius".into()))
+/// );
+/// ```
+pub fn substitute(source: &str, values: &[(&str, Value)]) -> Result<String, SubstituteError> {
+    if let Some((name, _)) = values.iter().find(|(_, value)| match value {
+        Value::WorkgroupSize(size) => *size == 0,
+        Value::F32(value) => !value.is_finite(),
+        _ => false,
+    }) {
+        return Err(SubstituteError::InvalidValue(name.to_string()));
+    }
+    let mut result = String::with_capacity(source.len());
+    let mut used = vec![false; values.len()];
+    let mut rest = source;
+    while let Some(at) = rest.find(['$', '/']) {
+        result.push_str(&rest[..at]);
+        rest = &rest[at..];
+        if rest.starts_with("//") || rest.starts_with("/*") {
+            let len = comment_len(rest);
+            result.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+        let len = match rest.strip_prefix('$') {
+            Some(name) if name.starts_with(|c: char| c.is_alphabetic() || c == '_') => name
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(name.len()),
+            // A `/` or a `$` not followed by a name is kept.
+            _ => {
+                result.push_str(&rest[..1]);
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let name = &rest[1..len + 1];
+        let Some(idx) = values.iter().position(|(x, _)| *x == name) else {
+            return Err(SubstituteError::NoValue(name.to_owned()));
+        };
+        used[idx] = true;
+        let (bytes, len_of) = literal(values[idx].1);
+        result.push_str(as_str(&bytes[..len_of]));
+        rest = &rest[len + 1..];
+    }
+    result.push_str(rest);
+    if let Some(idx) = used.iter().position(|x| !x) {
+        return Err(SubstituteError::NoPlaceholder(values[idx].0.to_owned()));
+    }
+    #[cfg(all(feature = "validate", debug_assertions))]
+    {
+        // `wgsl!` lists the `$extern`s of templates in a comment.
+        let mut externs = source
+            .lines()
+            .filter_map(|line| line.strip_prefix("// extern: "))
+            .flat_map(|names| names.split(", "));
+        if externs.all(|name| values.iter().any(|(x, _)| *x == name)) {
+            use wgsl_ln_runtime::test::{validate_source, Capabilities};
+            validate_source(&result, Capabilities::all()).map_err(SubstituteError::Invalid)?;
+        }
+    }
+    Ok(result)
+}
+
+/// Length of the `//` or nested `/* */` comment at the start of `source`.
+fn comment_len(source: &str) -> usize {
+    if source.starts_with("//") {
+        return source.find('\n').unwrap_or(source.len());
+    }
+    let mut depth = 0;
+    let mut at = 0;
+    while at < source.len() {
+        let rest = &source[at..];
+        if rest.starts_with("/*") {
+            depth += 1;
+            at += 2;
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            at += 2;
+            if depth == 0 {
+                return at;
+            }
+        } else {
+            at += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    source.len()
+}
+
+/// A value as a WGSL literal and its length.
+const fn literal(value: Value) -> ([u8; MAX_LEN], usize) {
+    let mut bytes = [0; MAX_LEN];
+    let len = match value {
+        Value::WorkgroupSize(0) => panic!("`@workgroup_size` must be positive."),
+        Value::WorkgroupSize(value) => decimal(&mut bytes, 0, value as u64),
+        Value::Bool(true) => copy(&mut bytes, 0, b"true"),
+        Value::Bool(false) => copy(&mut bytes, 0, b"false"),
+        // `-2147483648i` is out of range before it is negated.
+        Value::I32(i32::MIN) => copy(&mut bytes, 0, b"(-2147483647i - 1i)"),
+        Value::I32(value) => {
+            let at = match value < 0 {
+                true => copy(&mut bytes, 0, b"-"),
+                false => 0,
+            };
+            let at = decimal(&mut bytes, at, value.unsigned_abs() as u64);
+            copy(&mut bytes, at, b"i")
+        }
+        Value::U32(value) => {
+            let at = decimal(&mut bytes, 0, value as u64);
+            copy(&mut bytes, at, b"u")
+        }
+        Value::F32(value) => hexadecimal_float(&mut bytes, value),
+    };
+    (bytes, len)
+}
+
+const fn decimal(bytes: &mut [u8; MAX_LEN], at: usize, value: u64) -> usize {
+    let mut end = at + 1;
+    let mut rest = value / 10;
+    while rest > 0 {
+        rest /= 10;
+        end += 1;
+    }
+    // Written from the last digit.
+    let mut rest = value;
+    let mut i = end;
+    while i > at {
+        i -= 1;
+        bytes[i] = b'0' + (rest % 10) as u8;
+        rest /= 10;
+    }
+    end
+}
+
+/// Every finite `f32` is exact as a hexadecimal float, i.e. `0x1.800000p+1f` for `3.0`.
+const fn hexadecimal_float(bytes: &mut [u8; MAX_LEN], value: f32) -> usize {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let bits = value.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    // 23 bits of mantissa as 6 hexadecimal digits.
+    let mantissa = (bits & 0x7fffff) << 1;
+    if exponent == 0xff {
+        panic!("`override` defaults must be finite.")
+    }
+    let mut at = match bits >> 31 {
+        1 => copy(bytes, 0, b"-"),
+        _ => 0,
+    };
+    if exponent == 0 && mantissa == 0 {
+        return copy(bytes, at, b"0.0f");
+    }
+    // Subnormals have the exponent of the smallest normal number.
+    let (leading, exponent) = match exponent {
+        0 => (b"0x0.", -126),
+        _ => (b"0x1.", exponent - 127),
+    };
+    at = copy(bytes, at, leading);
+    let mut i = 0;
+    while i < 6 {
+        bytes[at] = HEX[((mantissa >> (20 - 4 * i)) & 0xf) as usize];
+        at += 1;
+        i += 1;
+    }
+    at = match exponent < 0 {
+        true => copy(bytes, at, b"p-"),
+        false => copy(bytes, at, b"p+"),
+    };
+    at = decimal(bytes, at, exponent.unsigned_abs() as u64);
+    copy(bytes, at, b"f")
+}
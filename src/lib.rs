@@ -3,7 +3,7 @@
 //! # The `wgsl!` macro
 //!
 //! The `wgsl!` macro converts normal rust tokens into a wgsl `&'static str`, similar to [`stringify!`].
-//! This also validates the wgsl string using [`naga`]. Errors will be reported with
+//! This also validates the wgsl string using [naga](https://docs.rs/naga). Errors will be reported with
 //! the correct span.
 //!
 //! ```
@@ -27,58 +27,1283 @@
 //! );
 //! ```
 //!
+//! WGSL literals are emitted as written, including suffixes like `1f` or `2u` and hex floats
+//! with an exponent like `0x1p-2`, which rust lexes as several tokens. Hex floats with a fraction
+//! like `0x1.8p0` are rejected by rust's lexer, write `0x18p-4` instead. As in WGSL, the most
+//! negative integers are written as expressions, i.e. `-2147483647i - 1i`.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static LITERALS: &str = wgsl!(
+//!     const QUARTER = 0x1p-2f;
+//!     const HALF = .5;
+//!     const THOUSANDS = 1.5e3f + 1f;
+//!     const MAX = 0xffffffffu;
+//!     const MIN = -2147483647i - 1i;
+//!     const ONE = 9223372036854775807 - 9223372036854775806;
+//! );
+//! assert_eq!(
+//!     LITERALS,
+//!     "const QUARTER = 0x1p-2f ;\nconst HALF = .5 ;\nconst THOUSANDS = 1.5e3f + 1f ;\n\
+//!     const MAX = 0xffffffffu ;\nconst MIN = - 2147483647i - 1i ;\n\
+//!     const ONE = 9223372036854775807 - 9223372036854775806 ;\n"
+//! );
+//! ```
+//!
+//! Rust keywords are passed through as is, raw identifiers like `r#in` are also accepted
+//! and emitted without `r#`. Names reserved by WGSL, like `type` or `ref`, are still rejected.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static LIGHT: &str = wgsl!(
+//!     fn attenuate(r#in: f32) -> f32 {
+//!         return r#in * 0.5;
+//!     }
+//! );
+//! assert!(LIGHT.starts_with("fn attenuate (in:f32 )"));
+//! ```
+//!
+//! `ident!("name")` emits a name exactly as written, for names rust cannot lex or that would
+//! be split by formatting, i.e. mangled names of items from `naga_oil` modules composed elsewhere.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static LIT: &str = wgsl!(
+//!     $extern fn ident!("light_X_naga_oil_mod_XNRUWO2DUNFXGO")(x: f32) -> f32;
+//!     fn lit(x: f32) -> f32 {
+//!         return ident!("light_X_naga_oil_mod_XNRUWO2DUNFXGO")(x);
+//!     }
+//! );
+//! assert!(LIT.contains("return light_X_naga_oil_mod_XNRUWO2DUNFXGO (x );"));
+//! ```
+//!
+//! Without `!(..)` right after it, `ident` is an ordinary name.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static PREVIOUS: &str = wgsl!(
+//!     fn previous(ident: u32) -> u32 {
+//!         if ident != 0u {
+//!             return ident - 1u;
+//!         }
+//!         return 0u;
+//!     }
+//! );
+//! assert!(PREVIOUS.contains("if ident != 0u"));
+//! ```
+//!
+//! `note!("text")` writes `text` into the output as a `//` comment, one per line,
+//! to explain generated code to whoever reads the shader. Notes are stripped from
+//! shaders renamed by `#![obfuscate]`.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static NOTE: &str = wgsl!(
+//!     note!("Approximates `exp(x)` for small `x`.")
+//!     fn exp_small(x: f32) -> f32 {
+//!         return 1.0 + x;
+//!     }
+//! );
+//! assert!(NOTE.starts_with("// Approximates `exp(x)` for small `x`.\nfn exp_small"));
+//! ```
+//!
+//! Like `ident`, `note` without `!(..)` right after it is an ordinary name.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static NOTES: &str = wgsl!(
+//!     fn count(note: u32) -> u32 {
+//!         if note != 0u {
+//!             return note;
+//!         }
+//!         return 1u;
+//!     }
+//! );
+//! assert!(NOTES.contains("if note != 0u"));
+//! ```
+//!
+//! Options are inner attributes at the start of a call, like `#![dump]` below.
+//! `#![options(..)]` groups several of them, `#![options(a, b(..))]` is the same as `#![a] #![b(..)]`.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static SKY: &str = wgsl!(
+//!     #![options(validate(parse), label("sky"))]
+//!     fn sky() -> vec3<f32> { return vec3(0.5, 0.7, 1.0); }
+//! );
+//! ```
+//!
+//! `#![entry_prefix(prefix_)]` prefixes the entry points of a shader and `#![rename(a = b)]` renames
+//! one of them, so variants can be merged into one module without clashes. Other functions and
+//! the calls to them are left as is.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static SPRITE: &str = wgsl!(
+//!     #![options(entry_prefix(sprite_), rename(fs_main = sprite_color))]
+//!     @vertex
+//!     fn vs_main() -> @builtin(position) vec4<f32> { return vec4(0.0); }
+//!     @fragment
+//!     fn fs_main() -> @location(0) vec4<f32> { return vec4(1.0); }
+//! );
+//! assert!(SPRITE.contains("fn sprite_vs_main ()"));
+//! assert!(SPRITE.contains("fn sprite_color ()"));
+//! ```
+//!
 //! # The `#[wgsl_export(name)]` macro
 //!
-//! Export a wgsl item (function, struct, etc)
-//! via `wgsl_export`. Must have the same `name` as the exported item.
+//! Export a wgsl item (function, struct, etc)
+//! via `wgsl_export`. Must have the same `name` as the exported item.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! #[wgsl_export(manhattan_distance)]
+//! pub static MANHATTAN_DISTANCE: &str = wgsl!(
+//!     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//!         return abs(a.x - b.x) + abs(a.y - b.y);
+//!     }
+//! );
+//! ```
+//!
+//! Associated consts in `impl` blocks can be exported as well.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! pub struct Outline;
+//!
+//! impl Outline {
+//!     #[wgsl_export(outline_color)]
+//!     pub const SHADER: &str = wgsl!(
+//!         fn outline_color() -> vec4<f32> {
+//!             return vec4(1.0, 0.5, 0.0, 1.0);
+//!         }
+//!     );
+//! }
+//!
+//! pub static OUTLINED: &str = wgsl!(
+//!     fn outlined(color: vec4<f32>, edge: f32) -> vec4<f32> {
+//!         return mix(color, $outline_color(), edge);
+//!     }
+//! );
+//! assert!(OUTLINED.contains("fn outline_color"));
+//! ```
+//!
+//! # Using an exported item
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! # #[wgsl_export(manhattan_distance)]
+//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
+//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! #     }
+//! # );
+//! pub static MANHATTAN_DISTANCE_TIMES_FIVE: &str = wgsl!(
+//!     fn manhattan_distance_times_five(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//!         return $manhattan_distance(a, b) * 5.0;
+//!     }
+//! );
+//! ```
+//!
+//! `$manhattan_distance` copies the `manhattan_distance` function into the module,
+//! making it usable. You can specify multiple instances of `$manhattan_distance`
+//! or omit the `$` in later usages. `#manhattan_distance` is also accepted
+//! outside of `naga_oil` mode.
+//!
+//! Aliases are exported like other items, sharing type names between shaders.
+//! An alias pasted in more than once, i.e. by `$Color` and by an export that declares
+//! the same alias, is only declared once.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! #[wgsl_export(Color)]
+//! pub static COLOR: &str = wgsl!(
+//!     alias Color = vec4<f32>;
+//! );
+//!
+//! #[wgsl_export(tint)]
+//! pub static TINT: &str = wgsl!(
+//!     alias Color = vec4<f32>;
+//!     fn tint(color: Color) -> Color {
+//!         return color * 0.5;
+//!     }
+//! );
+//!
+//! pub static SHADED: &str = wgsl!(
+//!     fn shaded(color: $Color) -> $Color {
+//!         return $tint(color);
+//!     }
+//! );
+//! assert_eq!(SHADED.matches("alias Color").count(), 1);
+//! ```
+//!
+//! `$NAME = value;` at the top level imports a constant with a new value, replacing
+//! the initializer of the `const` or `override` named `NAME` wherever it is pasted from.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! #[wgsl_export(MAX_STEPS)]
+//! pub static MAX_STEPS: &str = wgsl!(
+//!     const MAX_STEPS: u32 = 32u;
+//! );
+//!
+//! #[wgsl_export(march)]
+//! pub static MARCH: &str = wgsl!(
+//!     fn march() -> u32 {
+//!         var steps = 0u;
+//!         while steps < $MAX_STEPS {
+//!             steps += 1u;
+//!         }
+//!         return steps;
+//!     }
+//! );
+//!
+//! pub static DETAILED: &str = wgsl!(
+//!     $MAX_STEPS = 64u;
+//!     fn detailed() -> u32 {
+//!         return $march();
+//!     }
+//! );
+//! assert!(DETAILED.contains("const MAX_STEPS:u32 = 64u"));
+//! ```
+//!
+//! With `#![namespaced]`, functions, structs, constants and aliases pasted in are prefixed with
+//! the name of the crate that exported them, its `CARGO_CRATE_NAME`, i.e. `my_crate_manhattan_distance`,
+//! so helpers of different libraries don't collide with items of the shader. References are renamed
+//! with them, except where the shader declares an item of the same name itself.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! # #[wgsl_export(manhattan_distance)]
+//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
+//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! #     }
+//! # );
+//! #[wgsl_export(times_five)]
+//! pub static TIMES_FIVE: &str = wgsl!(
+//!     fn times_five(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//!         return $manhattan_distance(a, b) * 5.0;
+//!     }
+//! );
+//!
+//! pub static NAMESPACED: &str = wgsl!(
+//!     #![namespaced]
+//!     fn manhattan_distance(a: f32, b: f32) -> f32 {
+//!         return abs(a - b);
+//!     }
+//!     fn total(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//!         return $times_five(a, b) + manhattan_distance(a.x, b.x);
+//!     }
+//! );
+//! // The crate exporting `manhattan_distance` and `times_five`.
+//! let krate = env!("CARGO_CRATE_NAME");
+//! let distance = format!("return {}_manhattan_distance (a , b )* 5.0 ;", krate);
+//! assert!(NAMESPACED.contains(&distance));
+//! let total = format!("return {}_times_five (a , b )+ manhattan_distance (a . x , b . x );", krate);
+//! assert!(NAMESPACED.contains(&total));
+//! ```
+//!
+//! * Note compile time checks still work.
+//!
+//! ```compile_fail
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! # #[wgsl_export(manhattan_distance)]
+//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
+//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! #     }
+//! # );
+//! pub static MANHATTAN_DISTANCE_TIMES_FIVE: &str = wgsl!(
+//!     fn manhattan_distance_times_five(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//!         // missing comma
+//!         return $manhattan_distance(a, b) * 5.0
+//!     }
+//! );
+//! ```
+//!
+//! Exporting a name twice in one crate is reported at the second `#[wgsl_export]`,
+//! with the location of the first.
+//!
+//! If two dependencies export the same name, `$krate::name` pastes the one from `krate`,
+//! i.e. `$noise_a::noise` or, with the `noise` feature, `$wgsl_ln::value_noise_2d`. Later uses
+//! can omit the crate. `prefer = ["noise_a::noise"]` in `wgsl_ln.toml` does the same for every
+//! shader of a crate. Otherwise an export found in two glob imports is reported at the import,
+//! listing both crates.
+//!
+//! ```
+//! # #[cfg(feature = "noise")] {
+//! # use wgsl_ln::wgsl;
+//! pub static TERRAIN: &str = wgsl!(
+//!     fn height(p: vec2<f32>) -> f32 {
+//!         return $wgsl_ln::value_noise_2d(p) * 0.5 + $value_noise_2d(p * 2.0) * 0.25;
+//!     }
+//! );
+//! assert!(TERRAIN.contains("fn value_noise_2d"));
+//! # }
+//! ```
+//!
+//! # Math library
+//!
+//! With the `math` feature, `$wgsl_ln::name` pastes one of the common helpers below,
+//! `#![namespaced]` prefixes them with `wgsl_ln_`. Quaternions are `vec4<f32>` with `w` last.
+//!
+//! * `PI`, `TAU`
+//! * `saturate(x)`, `inverse_lerp(a, b, x)`, `remap(x, from_min, from_max, to_min, to_max)`
+//! * `rotate2d(angle) -> mat2x2<f32>`, `rotate_x`, `rotate_y` and `rotate_z(angle) -> mat3x3<f32>`
+//! * `quat_mul(a, b)`, `quat_conj(q)`, `quat_rotate(q, v) -> vec3<f32>`, `quat_from_axis_angle(axis, angle)`
+//!
+//! ```
+//! # #[cfg(feature = "math")] {
+//! # use wgsl_ln::wgsl;
+//! pub static ORBIT: &str = wgsl!(
+//!     fn orbit(p: vec3<f32>, t: f32) -> vec3<f32> {
+//!         return $wgsl_ln::rotate_y(t * $wgsl_ln::TAU) * p;
+//!     }
+//! );
+//! assert!(ORBIT.contains("fn rotate_y"));
+//! # }
+//! ```
+//!
+//! # Noise library
+//!
+//! With the `noise` feature, `$wgsl_ln::name` pastes hash functions and noise. Listing
+//! them in `prefer` of `wgsl_ln.toml` pastes them with `$name`, i.e. `$simplex_noise_3d(p)`.
+//!
+//! * `pcg_hash(u32) -> u32`, `pcg3d(vec3<u32>) -> vec3<u32>`
+//! * `hash12(vec2<f32>) -> f32`, `hash13(vec3<f32>) -> f32`, `hash33(vec3<f32>) -> vec3<f32>`
+//! * `value_noise_2d(p)`, `value_noise_3d(p)` in `0..1`
+//! * `simplex_noise_2d(p)`, `simplex_noise_3d(p)` in `-1..1`
+//!
+//! ```toml
+//! prefer = ["wgsl_ln::simplex_noise_3d"]
+//! ```
+//!
+//! # Color library
+//!
+//! With the `color` feature, `$wgsl_ln::name` pastes color space conversions of `vec3<f32>`,
+//! hues of OKLCH and HSV are in `0..1`.
+//!
+//! * `srgb_to_linear`, `linear_to_srgb`
+//! * `linear_to_oklab`, `oklab_to_linear`, `oklab_to_oklch`, `oklch_to_oklab`
+//! * `rgb_to_hsv`, `hsv_to_rgb`
+//!
+//! # PBR library
+//!
+//! With the `pbr` feature, `$wgsl_ln::name` pastes building blocks of a physically based shader,
+//! roughness is perceptual roughness.
+//!
+//! * `fresnel_schlick(cos_theta, f0: vec3<f32>) -> vec3<f32>`
+//! * `distribution_ggx(n_dot_h, roughness)`, `geometry_schlick_ggx(n_dot_v, roughness)`,
+//!   `geometry_smith(n_dot_v, n_dot_l, roughness)`
+//! * `tonemap_reinhard(c)`, `tonemap_reinhard_extended(c, white)`, `tonemap_aces(c)`
+//!
+//! ```
+//! # #[cfg(feature = "pbr")] {
+//! # use wgsl_ln::wgsl;
+//! pub static SPECULAR: &str = wgsl!(
+//!     fn specular(n_dot_v: f32, n_dot_l: f32, n_dot_h: f32, roughness: f32, f0: vec3<f32>) -> vec3<f32> {
+//!         let d = $wgsl_ln::distribution_ggx(n_dot_h, roughness);
+//!         let g = $wgsl_ln::geometry_smith(n_dot_v, n_dot_l, roughness);
+//!         let f = $wgsl_ln::fresnel_schlick(n_dot_v, f0);
+//!         return d * g * f / max(4.0 * n_dot_v * n_dot_l, 0.0001);
+//!     }
+//! );
+//! assert!(SPECULAR.contains("fn distribution_ggx"));
+//! # }
+//! ```
+//!
+//! # Inside `macro_rules!`
+//!
+//! In a `macro_rules!` definition `$name` is a metavariable, use `#name` to import
+//! `name` instead. `$extern` is not a valid metavariable and can be used as is.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! # #[wgsl_export(manhattan_distance)]
+//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
+//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! #     }
+//! # );
+//! macro_rules! scaled_distance {
+//!     ($name: ident, $function: ident, $factor: literal) => {
+//!         pub static $name: &str = wgsl!(
+//!             fn $function(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//!                 return #manhattan_distance(a, b) * $factor;
+//!             }
+//!         );
+//!     };
+//! }
+//!
+//! scaled_distance!(DISTANCE_TIMES_TWO, distance_times_two, 2.0);
+//! assert!(DISTANCE_TIMES_TWO.contains("fn manhattan_distance"));
+//! ```
+//!
+//! `#[wgsl_export(name)]` needs a `wgsl!` call in the item itself. Shaders generated by macros
+//! or `include!` are exported with `#![export(name)]` instead.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! macro_rules! scale {
+//!     ($name: ident, $function: ident, $factor: literal) => {
+//!         pub static $name: &str = wgsl!(
+//!             #![export($function)]
+//!             fn $function(x: f32) -> f32 {
+//!                 return x * $factor;
+//!             }
+//!         );
+//!     };
+//! }
+//!
+//! scale!(DOUBLE, double, 2.0);
+//!
+//! pub static QUADRUPLE: &str = wgsl!(
+//!     fn quadruple(x: f32) -> f32 {
+//!         return $double($double(x));
+//!     }
+//! );
+//! assert!(QUADRUPLE.contains("fn double"));
+//! ```
+//!
+//! # Preludes
+//!
+//! `wgsl_prelude!(a, b)` imports `a` and `b` in every `wgsl!` and `wgsl_oil!` call in the rest
+//! of the module, by shadowing them with `macro_rules!`. Don't import `wgsl` or `wgsl_oil`
+//! in that module. Items a shader declares itself are not imported.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! # #[wgsl_export(Vertex)]
+//! # pub static VERTEX: &str = wgsl!(
+//! #     struct Vertex {
+//! #         @location(0) position: vec2<f32>,
+//! #     }
+//! # );
+//! # #[wgsl_export(manhattan_distance)]
+//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
+//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! #     }
+//! # );
+//! mod shaders {
+//!     use wgsl_ln::wgsl_prelude;
+//!
+//!     wgsl_prelude!(Vertex, manhattan_distance);
+//!
+//!     pub static MAIN: &str = wgsl!(
+//!         @vertex
+//!         fn main(vertex: Vertex) -> @builtin(position) vec4<f32> {
+//!             return vec4(vertex.position, manhattan_distance(vertex.position, vec2(0.0)), 1.0);
+//!         }
+//!     );
+//! }
+//! # assert!(shaders::MAIN.contains("struct Vertex"));
+//! ```
+//!
+//! # Shaders in the same file
+//!
+//! Items of another shader in the same file are shared like any other, by exporting them
+//! and using them with `$`. Macros can't see each other's input without relying on the order
+//! rustc expands them in, which is not guaranteed.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! #[wgsl_export(Light)]
+//! pub static LIGHT: &str = wgsl!(
+//!     struct Light {
+//!         color: vec3<f32>,
+//!         intensity: f32,
+//!     }
+//! );
+//!
+//! #[wgsl_export(radiance)]
+//! pub static RADIANCE: &str = wgsl!(
+//!     fn radiance(light: $Light) -> vec3<f32> {
+//!         return light.color * light.intensity;
+//!     }
+//! );
+//!
+//! pub static SHADING: &str = wgsl!(
+//!     @group(0) @binding(0) var<uniform> light: $Light;
+//!     @fragment
+//!     fn main() -> @location(0) vec4<f32> {
+//!         return vec4($radiance(light), 1.0);
+//!     }
+//! );
+//! assert_eq!(SHADING.matches("struct Light").count(), 1);
+//! ```
+//!
+//! # Concatenating shaders
+//!
+//! Joining shader strings at runtime skips validation. `wgsl_concat!` joins exported statics
+//! and WGSL strings into a new shader at compile time and validates it like `wgsl!`.
+//! Exports are imported like `import package::name;`, so dependencies they share are pasted once.
+//! Items must be exported before the `wgsl_concat!` call.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_concat, wgsl_export};
+//! # #[wgsl_export(manhattan_distance)]
+//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
+//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! #     }
+//! # );
+//! pub static DISTANCES: &str = wgsl_concat!(
+//!     MANHATTAN_DISTANCE,
+//!     "fn chebyshev_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//!         return max(abs(a.x - b.x), abs(a.y - b.y));
+//!     }",
+//! );
+//! # assert!(DISTANCES.contains("fn chebyshev_distance"));
+//! ```
+//!
+//! # Shader variants
+//!
+//! `wgsl_variants!` generates a function returning one validated shader per variant of an enum.
+//! Its body maps each variant to shader defs, `NAME` or `NAME = value`, and the shader after it
+//! uses `#ifdef`, `#ifndef`, `#if NAME >= value`, `#else` and `#endif`, resolved per variant
+//! at compile time. The `match` has no wildcard, so adding a variant to the enum is an error
+//! until it is mapped, and a def no variant sets is an error at its use.
+//!
+//! ```
+//! # use wgsl_ln::wgsl_variants;
+//! # pub enum Quality {
+//! #     Low,
+//! #     Medium,
+//! #     High,
+//! # }
+//! wgsl_variants!(
+//!     pub fn lighting(quality: Quality) -> &'static str {
+//!         Quality::Low => (),
+//!         Quality::Medium => (SHADOWS),
+//!         Quality::High => (SHADOWS, SAMPLES = 4),
+//!     }
+//!     @fragment
+//!     fn fragment() -> @location(0) vec4<f32> {
+//!         var light = 1.0;
+//!         #ifdef SHADOWS
+//!         light = 0.5;
+//!         #endif
+//!         return vec4(light);
+//!     }
+//! );
+//! # assert!(!lighting(Quality::Low).contains("0.5"));
+//! # assert!(lighting(Quality::High).contains("0.5"));
+//! ```
+//!
+//! Directives must be closed in the block they are opened in, items pasted with `$name`
+//! are not affected.
+//!
+//! # Showing the output
+//!
+//! `#![dump]` shows the final WGSL, after pasting, as a warning at compile time.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export};
+//! # #[wgsl_export(hello)]
+//! # pub static HELLO: &str = wgsl!(
+//! #     fn hello(v: vec2<f32>) -> f32 {
+//! #         return v.x + v.y;
+//! #     }
+//! # );
+//! pub static WORLD: &str = wgsl!(
+//!     #![dump]
+//!     fn world() -> f32 { return $hello(vec2(1.0)); }
+//! );
+//! // warning: use of deprecated constant `WORLD::_::WGSL`: `#![dump]`
+//! //          fn hello (v:vec2 < f32 > )-> f32 {
+//! //          ...
+//! ```
+//!
+//! `#![stats]` shows the number of functions, globals, types and an approximate instruction
+//! count from the naga IR in the same way, or for every shader if `WGSL_LN_STATS` is set.
+//!
+//! ```text
+//! warning: use of deprecated constant `WORLD::_::WGSL`: `#![stats]` functions: 2, entry points: 0, globals: 0, constants: 0, overrides: 0, types: 2, instructions: ~12
+//! ```
+//!
+//! With the `round_trip` feature, `#![round_trip]` writes the validated shader with naga's WGSL
+//! backend and compares it to the same done for a plain printing of its tokens, or for every
+//! shader if `WGSL_LN_ROUND_TRIP` is set. Both are written from the naga IR, so a difference
+//! means formatting changed what ships, i.e. a literal or an operator, and is shown as a warning.
+//!
+//! ```text
+//! warning: use of deprecated constant `K::_::WGSL`: `#![round_trip]` the output differs from its tokens after naga's WGSL writer, line 1:
+//!          output: const K: f32 = 0.12f;
+//!          tokens: const K: f32 = 0.12345679f;
+//! ```
+//!
+//! # Texture and sampler lints
+//!
+//! Mistakes in pairing textures with samplers are shown as warnings on the binding declarations,
+//! instead of surfacing as backend errors at pipeline creation:
+//!
+//! * A `texture_2d<f32>` or depth texture that is never used with a sampler,
+//!   while a sampler of the same bind group is never used with a texture.
+//! * A `sampler_comparison` used with a texture that is not a depth texture,
+//!   which naga only rejects when validating.
+//!
+//! Textures and samplers passed to a function are assumed to be sampled.
+//!
+//! ```text
+//! warning: use of deprecated constant `BLIT::_::SAMPLER_WARNING`: `color` is never used with a sampler and `linear` of the same group is never used with a texture.
+//!  --> src/lib.rs:4:27
+//!   |
+//! 4 |     @group(0) @binding(0) var color: texture_2d<f32>;
+//!   |                           ^^^
+//! ```
+//!
+//! Like other warnings, they can be silenced with `#[allow(deprecated)]` on the item.
+//!
+//! # Browser portability
+//!
+//! `#![portability(browser)]`, or `portability = "browser"` in `wgsl_ln.toml`, warns about what
+//! native `wgpu` supports but browsers do not, so web builds are not a surprise:
+//!
+//! * Capabilities outside of core WebGPU, i.e. push constants, `f64` or `@builtin(primitive_index)`,
+//!   the first one found is shown.
+//! * Storage texture formats outside of core WebGPU, like with `WGSL_LN_VALIDATE=strict`.
+//! * Bindings, buffer sizes and workgroup sizes over the default limits of WebGPU, see device limits.
+//!
+//! ```text
+//! warning: use of deprecated constant `PC::_::PORTABILITY_WARNING`: Not portable to browsers: `u` is in `@group(5)`, exceeding `max_bind_groups = 4` of WebGPU.
+//!  --> src/lib.rs:9:27
+//!   |
+//! 9 |     @group(5) @binding(0) var<uniform> u: vec4<f32>;
+//!   |                           ^^^
+//! ```
+//!
+//! # Profiling
+//!
+//! If `WGSL_LN_PROFILE` is set to a file, each `wgsl!` invocation appends its timing in milliseconds,
+//! with the number of items pasted in so far as `depth`. Invocations that continue the paste chain
+//! are listed with the pasted item.
+//!
+//! ```text
+//! WGSL_LN_PROFILE=$PWD/profile.txt cargo build
+//!
+//! my_crate $hello depth=0 sanitize=0.028 total=0.039
+//! my_crate WORLD depth=1 sanitize=0.050 tokenize=0.061 parse=0.205 validate=0.037 emit=0.035 total=0.399
+//! ```
+//!
+//! With the `cache` feature, shaders that passed validation are recorded in `target/wgsl_ln/cache`
+//! by a hash of their source and the naga version, unchanged shaders are not parsed or validated
+//! again on rebuilds unless reflection data is needed.
+//!
+//! With the `debug_parse_only` feature, shaders are only parsed in debug builds and validated
+//! in release builds, trading some checks for faster edit-compile cycles.
+//!
+//! The validation level can also be set with `WGSL_LN_VALIDATE`, overriding `debug_parse_only`,
+//! so local builds can be fast while CI runs every check without changing source code:
+//!
+//! * `off`: shaders are not parsed unless reflection data is needed.
+//! * `parse`: shaders are parsed but not validated.
+//! * `full`: shaders are validated, the default.
+//! * `strict`: shaders are also validated with naga's default capabilities,
+//!   rejecting optional features like `f64` or push constants.
+//!
+//! ```text
+//! WGSL_LN_VALIDATE=strict cargo build
+//! ```
+//!
+//! Storage texture declarations are checked against the formats and access modes of WebGPU,
+//! i.e. `bgra8unorm` only supports `write` and `rg32float` does not support `read_write`.
+//! `strict` only allows the storage formats of core WebGPU, where `read_write` is limited
+//! to `r32float`, `r32sint` and `r32uint`.
+//!
+//! Cargo does not rebuild crates when the variable changes, run `cargo clean` first.
+//! Neither applies to `naga_oil` mode, where modules are composed by `naga_oil`.
+//!
+//! `#![validate(level)]` sets the level of a single shader, i.e. `parse` for a shader
+//! that needs a capability naga cannot validate yet. `WGSL_LN_VALIDATE` still takes precedence.
+//!
+//! # Configuration file
+//!
+//! Defaults for every shader of a crate can be set in `wgsl_ln.toml`, next to its `Cargo.toml`:
+//!
+//! ```toml
+//! # `off`, `parse`, `full` or `strict`, like `WGSL_LN_VALIDATE`.
+//! validate = "strict"
+//! # `wgsl!` in `naga_oil` mode, like `wgsl_oil!`.
+//! oil = false
+//! # Compare every shader to its tokens, like `WGSL_LN_ROUND_TRIP`.
+//! round_trip = false
+//! # Rename identifiers in release builds, like `#![obfuscate]`.
+//! obfuscate = false
+//! # Order every shader as directives, types, globals and functions, like `#![canonical_order]`.
+//! canonical_order = false
+//! # Imported in every shader, like `wgsl_prelude!`.
+//! prelude = ["Vertex", "VertexOutput"]
+//! # Crates of exports found in more than one dependency, like `$noise_a::noise`.
+//! prefer = ["noise_a::noise"]
+//! # Programs run on every validated shader, see custom passes.
+//! passes = []
+//! # Text replaced before validation, see preprocessing.
+//! substitute = []
+//! # Programs rewriting shaders before validation, see preprocessing.
+//! preprocess = []
+//! # `wgpu::Limits` of a target adapter as JSON or TOML, see device limits.
+//! limits = "limits/mobile.json"
+//! # Warn about what browsers do not support on every shader, see browser portability.
+//! portability = "browser"
+//! ```
+//!
+//! Every key is optional, options and environment variables take precedence. Unknown keys are errors.
+//! Like the environment variables, changes apply after `cargo clean`.
+//!
+//! # Device limits
+//!
+//! `limits` checks shaders against the limits of a target adapter, i.e. a mobile GPU, at compile time
+//! instead of at pipeline creation. The file is `wgpu::Adapter::limits()` serialized as JSON or TOML,
+//! relative to `Cargo.toml`, limits missing from it are not checked.
+//!
+//! ```text
+//! error: Wgsl Error: `main` has 256 invocations per workgroup, exceeding `max_compute_invocations_per_workgroup = 128` of "limits/mobile.json".
+//! ```
+//!
+//! Bind group and binding indices, uniform, storage and push constant buffer sizes, the number of
+//! bindings of each kind used by an entry point, workgroup sizes and workgroup storage are checked,
+//! for shaders validated with `full` or `strict`.
+//!
+//! # Obfuscation
+//!
+//! `#![obfuscate]` renames identifiers to short names like `_0` or `_1a` in release builds,
+//! so shipped shaders are smaller and don't reveal how they were written. Pasted items are renamed
+//! with the rest of the shader. Names the host can see are kept: entry points, `override`s,
+//! resources with `@group` or `@binding` and struct members. Debug builds are not affected,
+//! so errors and `#![dump]` show the original names.
+//!
+//! ```text
+//! fn _4 (_6:_3 , _7:f32 )-> vec3 < f32 > {
+//! let _8 = distance (vec2 (_7 ), vec2 (0.0 ));
+//! ```
+//!
+//! `obfuscate = true` in `wgsl_ln.toml` obfuscates every shader, except in `naga_oil` or WESL mode
+//! where names are imported by other modules.
+//!
+//! # Item order
+//!
+//! Items are written in the order they are pasted in, dependencies first. `#![canonical_order]`
+//! writes directives first, then `struct`s and `alias`es, then `const`, `override` and `var`
+//! declarations, then functions, for tools and reviews that expect this structure.
+//! Declarations at module scope can be used before they are declared in WGSL, so the meaning is unchanged.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static ORDERED: &str = wgsl!(
+//!     #![canonical_order]
+//!     fn scaled(x: f32) -> f32 { return x * SCALE; }
+//!     const SCALE: f32 = 2.0;
+//!     struct Particle { position: vec2<f32> }
+//! );
+//! assert!(ORDERED.find("struct").unwrap() < ORDERED.find("const").unwrap());
+//! assert!(ORDERED.find("const").unwrap() < ORDERED.find("fn").unwrap());
+//! ```
+//!
+//! `canonical_order = true` in `wgsl_ln.toml` reorders every shader, `naga_oil` mode is not supported.
+//!
+//! # Preprocessing
+//!
+//! For in-house directives, `substitute` and `preprocess` in `wgsl_ln.toml` rewrite the source
+//! of every shader before naga sees it, in that order. Substitutions replace whole tokens and are
+//! matched as `wgsl_ln` writes shaders, so `@wg64` also matches `@ wg64`. A preprocessor is a program
+//! reading the shader on stdin and writing the result to stdout, a non-zero exit status fails the build
+//! with its stderr. Paths are relative to `CARGO_MANIFEST_DIR`, names without a directory are found
+//! in `PATH`, and `WGSL_LN_LABEL` is set to the label of the shader. The preprocessed shader is
+//! validated and emitted, errors in it are shown at the `wgsl!` call. Shaders in `naga_oil` mode
+//! are not preprocessed.
+//!
+//! ```toml
+//! substitute = ["@wg64 => @workgroup_size(64, 1, 1)", "@unroll =>"]
+//! preprocess = ["tools/dialect"]
+//! ```
+//!
+//! # Custom passes
+//!
+//! Programs listed in `passes` of `wgsl_ln.toml` run on every validated shader of the crate,
+//! for lints or rewrites specific to a project. A pass is a binary built with the `pass` feature
+//! of `wgsl_ln_runtime`, implementing `wgsl_ln_runtime::pass::Pass` over the `naga::Module`.
+//! Its errors fail the build at the `wgsl!` call and its warnings are shown there. A modified
+//! module replaces the shader and is validated again. Paths are relative to `CARGO_MANIFEST_DIR`,
+//! names without a directory are found in `PATH`. Passes are not run in `naga_oil` mode,
+//! and cannot rewrite shaders with `$extern`, imports or `$$`.
+//!
+//! ```toml
+//! passes = ["target/release/binding-lints"]
+//! ```
+//!
+//! # Catalog of exports
+//!
+//! `wgsl_catalog!()` at the end of the crate root generates a `wgsl_catalog` module
+//! with a constant holding the composed source of each export, documented with the kind
+//! of the exported item, and `EXPORTS` listing them as `(name, kind, item)`,
+//! so users of a library can discover what is importable from its documentation.
+//! Exports are collected while the crate is compiled, rust-analyzer expands macros on demand
+//! and may show an incomplete catalog.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_catalog, wgsl_export};
+//! # #[wgsl_export(manhattan_distance)]
+//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
+//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! #     }
+//! # );
+//! wgsl_catalog!();
+//!
+//! assert_eq!(wgsl_catalog::EXPORTS, &[("manhattan_distance", "fn", "MANHATTAN_DISTANCE")]);
+//! ```
+//!
+//! `wgsl_catalog::SOURCES` lists the exports that are valid on their own. With the `validate`
+//! feature of `wgsl_ln_runtime`, `validate_all_exports!()` validates them in a test under a chosen
+//! set of capabilities, catching broken exports that no shader imports.
+//!
+//! ```
+//! # /*
+//! #[test]
+//! fn exports() {
+//!     use wgsl_ln_runtime::test::{validate_all_exports, Capabilities};
+//!     validate_all_exports!(Capabilities::FLOAT64);
+//! }
+//! # */
+//! ```
+//!
+//! # Snapshot tests
+//!
+//! `#[wgsl_snapshot("path")]` adds a test comparing the composed output of a shader
+//! to a checked-in file relative to `CARGO_MANIFEST_DIR`, catching unintended changes
+//! to formatting or composition of shipped shader text.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_export, wgsl_snapshot};
+//! # #[wgsl_export(hello)]
+//! # pub static HELLO: &str = wgsl!(
+//! #     fn hello(v: vec2<f32>) -> f32 {
+//! #         return v.x + v.y;
+//! #     }
+//! # );
+//! #[wgsl_snapshot("snapshots/world.wgsl")]
+//! pub static WORLD: &str = wgsl!(
+//!     fn world() -> f32 {
+//!         return $hello(vec2(1.0, 2.0));
+//!     }
+//! );
+//! ```
+//!
+//! Run the tests with `WGSL_LN_UPDATE_SNAPSHOTS=1` to write the files instead.
+//!
+//! ```text
+//! WGSL_LN_UPDATE_SNAPSHOTS=1 cargo test
+//! ```
+//!
+//! # GPU tests
+//!
+//! With the `gpu_test` feature, `wgsl_test!` runs shader functions in a test on a headless GPU
+//! or software adapter, through a generated compute shader and `wgpu` harness from
+//! `wgsl_ln::runtime`. Arguments and results are 32 bit scalars
+//! or vectors written as Rust values, i.e. `[f32; 2]` for `vec2<f32>`.
+//!
+//! ```no_run
+//! # #[cfg(feature = "gpu_test")] {
+//! # use wgsl_ln::{wgsl, wgsl_export, wgsl_test};
+//! # #[wgsl_export(manhattan_distance)]
+//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
+//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! #     }
+//! # );
+//! #[test]
+//! fn manhattan_distance() {
+//!     wgsl_test!(
+//!         #![case(manhattan_distance([0.0, 0.0], [1.0, 2.0]) == 3.0)]
+//!         #![case(manhattan_distance([1.0, 1.0], [1.0, 1.0]) == 0.0)]
+//!         import package::manhattan_distance;
+//!     );
+//! }
+//! # }
+//! ```
+//!
+//! `#[wgsl_property]` compares an exported function to a Rust reference over generated
+//! arguments, floats within a relative tolerance.
+//!
+//! ```no_run
+//! # #[cfg(feature = "gpu_test")] {
+//! # use wgsl_ln::{wgsl, wgsl_export, wgsl_property};
+//! # #[wgsl_export(manhattan_distance)]
+//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
+//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
+//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! #     }
+//! # );
+//! #[wgsl_property(manhattan_distance, samples = 1000, tolerance = 1e-6)]
+//! fn manhattan_distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+//!     (a[0] - b[0]).abs() + (a[1] - b[1]).abs()
+//! }
+//! # }
+//! ```
+//!
+//! `#[wgsl_pipeline_test]` on a `#![shader]` adds a test creating its render and compute
+//! pipelines with the reflected layouts, catching backend specific errors `naga` validation
+//! misses, i.e. workgroup sizes above the device limits.
+//!
+//! ```no_run
+//! # #[cfg(feature = "gpu_test")] {
+//! # use wgsl_ln::{runtime::Shader, wgsl, wgsl_pipeline_test};
+//! #[wgsl_pipeline_test]
+//! pub static BLUR: Shader = wgsl!(
+//!     #![shader]
+//!     @group(0) @binding(0) var<storage, read_write> pixels: array<vec4<f32>>;
+//!     @compute @workgroup_size(8, 8)
+//!     fn blur() {
+//!         pixels[0] = vec4(0.0);
+//!     }
+//! );
+//! # }
+//! ```
+//!
+//! # Extern declarations
+//!
+//! Items linked in at runtime (i.e. by `naga_oil` or manual concatenation) can be declared
+//! with `$extern`. They are stubbed for validation and omitted from the output.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static LIT: &str = wgsl!(
+//!     $extern fn engine_lighting(p: vec3<f32>) -> vec3<f32>;
+//!
+//!     fn lit(p: vec3<f32>) -> vec3<f32> {
+//!         return engine_lighting(p) * 0.5;
+//!     }
+//! );
+//! assert!(!LIT.contains("fn engine_lighting"));
+//! ```
+//!
+//! Exports keep their externs, a shader using an export can declare the same extern again,
+//! it is stubbed once.
 //!
 //! ```
 //! # use wgsl_ln::{wgsl, wgsl_export};
-//! #[wgsl_export(manhattan_distance)]
-//! pub static MANHATTAN_DISTANCE: &str = wgsl!(
-//!     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
-//!         return abs(a.x - b.x) + abs(a.y - b.y);
+//! #[wgsl_export(lit)]
+//! pub static LIT: &str = wgsl!(
+//!     $extern fn engine_lighting(p: vec3<f32>) -> vec3<f32>;
+//!
+//!     fn lit(p: vec3<f32>) -> vec3<f32> {
+//!         return engine_lighting(p) * 0.5;
 //!     }
 //! );
+//!
+//! pub static SHADED: &str = wgsl!(
+//!     $extern fn engine_lighting(p: vec3<f32>) -> vec3<f32>;
+//!
+//!     fn shaded(p: vec3<f32>) -> vec3<f32> {
+//!         return $lit(p) + engine_lighting(p);
+//!     }
+//! );
+//! assert!(!SHADED.contains("fn engine_lighting"));
 //! ```
 //!
-//! # Using an exported item
+//! `$$` and `##` are a literal `$` and `#` in the output, i.e. for placeholders substituted
+//! by a template engine. They are left out of the validated source, so `$$value` is validated as `value`.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static TEMPLATE: &str = wgsl!(
+//!     $extern override scale: f32;
+//!
+//!     fn scaled(x: f32) -> f32 {
+//!         return x * $$scale;
+//!     }
+//! );
+//! assert!(TEMPLATE.contains("x * $scale"));
+//! ```
+//!
+//! `wgsl_ln::substitute` fills these placeholders at runtime with `Value`s written as WGSL literals,
+//! i.e. for values only known after startup. `${..}` is filled in at compile time, so placeholders
+//! left to runtime are written `$$name` and emitted as `$name`, a `$` in a comment is kept.
+//! A template lists its `$extern`s in a `// extern: ..` comment on its first line, with the `validate`
+//! feature the result is validated by naga in debug builds if the values fill in all of them.
+//! A missing or unused value, or a failed validation, is a `SubstituteError`.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, substitute, Value};
+//! pub static FOG: &str = wgsl!(
+//!     $extern override density: f32;
+//!
+//!     fn fog(distance: f32) -> f32 {
+//!         return exp(-$$density * distance);
+//!     }
+//! );
+//! let source = substitute(FOG, &[("density", Value::F32(0.5))]).unwrap();
+//! assert!(source.contains("0x1.000000p-1f * distance"));
+//! ```
+//!
+//! # Rust constants
+//!
+//! `${..}` in `@workgroup_size` is a Rust expression of type `u32`, so the declared
+//! workgroup size and the dispatch math on the CPU come from the same constant.
+//! The shader is validated with a size of `1` in its place, the source is
+//! written at compile time with the value.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub const WG_X: u32 = 16;
+//! pub const WG_Y: u32 = 4;
+//!
+//! pub static BLUR: &str = wgsl!(
+//!     @compute @workgroup_size(${WG_X}, ${WG_Y}, 1)
+//!     fn blur() {}
+//! );
+//! assert!(BLUR.contains("workgroup_size (16 , 4 , 1 )"));
+//! ```
+//!
+//! With `#![shader]`, `EntryPoint::workgroup_size` is reflected from the constants as well.
+//!
+//! `${..}` is also the default of an `override` of type `bool`, `i32`, `u32` or `f32`,
+//! a Rust expression of the same type. Floats are written exactly as hexadecimal literals.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub const SAMPLES: u32 = 16;
+//!
+//! pub static BLUR: &str = wgsl!(
+//!     override samples: u32 = ${SAMPLES};
+//!     override radius: f32 = ${1.5};
+//!
+//!     @compute @workgroup_size(8, 8)
+//!     fn blur() {
+//!         _ = f32(samples) * radius;
+//!     }
+//! );
+//! assert!(BLUR.contains("samples:u32 = 16u"));
+//! ```
+//!
+//! With `#![shader]`, `override`s are reflected into `Shader::overrides`, and
+//! `Shader::pipeline_constant` checks the name and type of an override in a `const`,
+//! giving the key to set it with in `PipelineCompilationOptions::constants`.
+//!
+//! ```
+//! # /*
+//! const SAMPLES: PipelineConstant<u32> = BLUR.pipeline_constant("samples");
+//!
+//! let constants = HashMap::from([SAMPLES.value(32)]);
+//! # */
+//! ```
+//!
+//! Shaders written to files, i.e. by `#![out_dir]`, cannot use `${..}`.
+//!
+//! # Constant evaluation
+//!
+//! `wgsl_eval!` evaluates a constant expression with naga's constant evaluator and
+//! gives the result as a Rust value, the other direction of `${..}`. Scalars keep their type,
+//! vectors, matrices and arrays become Rust arrays, i.e. `[f32; 3]` for `vec3<f32>`.
+//! With `$name`, Rust uses the exported constant the shaders use, so CPU and GPU agree
+//! on derived constants.
+//!
+//! ```
+//! # use wgsl_ln::{wgsl, wgsl_eval, wgsl_export};
+//! #[wgsl_export(lut_size)]
+//! pub static LUT: &str = wgsl!(
+//!     const lut_size = 4u * 16u + 3u;
+//! );
+//!
+//! pub const LUT_SIZE: u32 = wgsl_eval!($lut_size);
+//! pub const LUT_BYTES: u32 = wgsl_eval!($lut_size * 4u);
+//! pub const CORNER: [[f32; 2]; 2] = wgsl_eval!(mat2x2(1.0, 0.0, 0.0, 1.0) * 0.5);
+//! assert_eq!(LUT_SIZE, 67);
+//! assert_eq!(LUT_BYTES, 268);
+//! assert_eq!(CORNER, [[0.5, 0.0], [0.0, 0.5]]);
+//! ```
+//!
+//! Built-in functions naga cannot evaluate yet, like `normalize`, are errors.
+//!
+//! # WESL
+//!
+//! WESL `import` statements are recognized. `import package::item;` imports an exported
+//! item like `$item`, other imports are emitted as is for a WESL runtime. Items they
+//! provide can be declared with `$extern` for validation.
 //!
 //! ```
 //! # use wgsl_ln::{wgsl, wgsl_export};
-//! # #[wgsl_export(manhattan_distance)]
-//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
-//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
-//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! # #[wgsl_export(double)]
+//! # pub static DOUBLE: &str = wgsl!(
+//! #     fn double(x: f32) -> f32 {
+//! #         return x * 2.0;
 //! #     }
 //! # );
-//! pub static MANHATTAN_DISTANCE_TIMES_FIVE: &str = wgsl!(
-//!     fn manhattan_distance_times_five(a: vec2<f32>, b: vec2<f32>) -> f32 {
-//!         return #manhattan_distance(a, b) * 5.0;
+//! pub static LIT: &str = wgsl!(
+//!     import package::double;
+//!     import engine::lighting::light;
+//!     $extern fn light(x: f32) -> f32;
+//!
+//!     fn lit(x: f32) -> f32 {
+//!         return double(light(x));
 //!     }
 //! );
+//! assert!(LIT.starts_with("import engine::lighting::light"));
 //! ```
 //!
-//! `#manhattan_distance` copies the `manhattan_distance` function into the module,
-//! making it usable. You can specify multiple instances of `#manhattan_distance`
-//! or omit the `#` in later usages.
-//!
-//! * Note compile time checks still work.
+//! With `#![wesl]` the shader is emitted as a WESL module for WESL toolchains.
+//! Items imported with `$item` are validated as usual, but emitted as
+//! `import package::item;` instead of being inlined, exported items are expected
+//! to be served in the root module of the package.
 //!
-//! ```compile_fail
+//! ```
 //! # use wgsl_ln::{wgsl, wgsl_export};
-//! # #[wgsl_export(manhattan_distance)]
-//! # pub static MANHATTAN_DISTANCE: &str = wgsl!(
-//! #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
-//! #         return abs(a.x - b.x) + abs(a.y - b.y);
+//! # #[wgsl_export(double)]
+//! # pub static DOUBLE: &str = wgsl!(
+//! #     fn double(x: f32) -> f32 {
+//! #         return x * 2.0;
 //! #     }
 //! # );
-//! pub static MANHATTAN_DISTANCE_TIMES_FIVE: &str = wgsl!(
-//!     fn manhattan_distance_times_five(a: vec2<f32>, b: vec2<f32>) -> f32 {
-//!         // missing comma
-//!         return #manhattan_distance(a, b) * 5.0
+//! pub static QUAD: &str = wgsl!(
+//!     #![wesl]
+//!     fn quad(x: f32) -> f32 {
+//!         return $double(double(x));
+//!     }
+//! );
+//! assert!(QUAD.starts_with("import package::double"));
+//! assert!(!QUAD.contains("fn double"));
+//! ```
+//!
+//! # Validating strings
+//!
+//! `wgsl_validate!` validates WGSL that is already a string, so shaders kept in `.wgsl` files
+//! are checked like `wgsl!` without rewriting them as tokens. It accepts a string literal,
+//! `include_str!("path")` relative to the calling file or `concat!(..)` of them,
+//! and emits the same `&'static str`. Errors are shown at the argument with naga's report,
+//! which has the line and column in the string.
+//!
+//! ```
+//! # /*
+//! pub static LEGACY: &str = wgsl_validate!(include_str!("shaders/legacy.wgsl"));
+//! # */
+//! ```
+//!
+//! Macros cannot read the value of a `const`, so strings built in `const`s, i.e. with
+//! `const_format`, cannot be validated. `include_str!` them from a file instead.
+//!
+//! # GLSL
+//!
+//! With the `glsl` feature, `glsl!` checks GLSL at compile time with naga's GLSL frontend,
+//! for codebases migrating to WGSL. The shader stage is required, `#![wgsl]` emits the WGSL
+//! naga translates the shader to instead of the GLSL. GLSL with `#` directives like `#version`
+//! must be a string literal, other GLSL can be written as tokens like in `wgsl!`.
+//!
+//! ```
+//! # /*
+//! pub static TINT: &str = glsl!(
+//!     #![stage(fragment)]
+//!     #![wgsl]
+//!     layout(location = 0) in vec4 v_color;
+//!     layout(location = 0) out vec4 color;
+//!     void main() {
+//!         color = v_color * 0.5;
 //!     }
 //! );
+//! assert!(TINT.contains("@fragment"));
+//! # */
+//! ```
+//!
+//! # SPIR-V
+//!
+//! With the `spirv` feature, `wgsl_from_spirv!` checks a precompiled SPIR-V binary at compile
+//! time with naga's SPIR-V frontend, so third party kernels get the same validation as `wgsl!`.
+//! The path is relative to the calling file like in `include_bytes!`. The bytes are emitted as is,
+//! or as the WGSL naga decompiles them to with `#![wgsl]`.
+//!
+//! ```
+//! # /*
+//! pub static BLUR_SPV: &[u8] = wgsl_from_spirv!(include_bytes!("kernels/blur.spv"));
+//!
+//! pub static BLUR: &str = wgsl_from_spirv!(
+//!     #![wgsl]
+//!     include_bytes!("kernels/blur.spv")
+//! );
+//! # */
+//! ```
+//!
+//! # Writing to `OUT_DIR`
+//!
+//! For very large shaders, `#![out_dir]` writes the output to `OUT_DIR` and expands to
+//! `include_str!` instead of a string literal, which requires a `build.rs`. The file is named
+//! after the content hash, or can be named with `#![out_dir("lighting.wgsl")]`.
+//!
+//! ```
+//! # /*
+//! pub static TERRAIN: &str = wgsl!(
+//!     #![out_dir("terrain.wgsl")]
+//!     ...
+//! );
+//! // expands to:
+//! // include_str!(concat!(env!("OUT_DIR"), "/terrain.wgsl"))
+//! # */
+//! ```
+//!
+//! # Reflection files
+//!
+//! With the `json` feature, each validated shader also writes its entry points, bindings,
+//! structs with member offsets and overrides to `OUT_DIR/wgsl_ln/<label>.json`, for tools
+//! that would otherwise reflect over the strings. Exported shaders are labeled with the
+//! item's name, others can be labeled with `#![label("name")]` or are named after the content hash.
+//!
+//! ```
+//! # /*
+//! pub static TERRAIN: &str = wgsl!(
+//!     #![label("terrain")]
+//!     ...
+//! );
+//! // writes:
+//! // $OUT_DIR/wgsl_ln/terrain.json
+//! # */
+//! ```
+//!
+//! With the `naga_ir` feature, `#![naga_ir]` also writes the validated `naga::Module` as JSON
+//! to `OUT_DIR/wgsl_ln/<label>.naga.json`, named like the reflection file. Build tools can
+//! load it with `serde_json` and naga's `deserialize` feature of the same naga version,
+//! instead of parsing the WGSL again. This requires a `build.rs`.
+//!
+//! # Shader manifest
+//!
+//! With the `manifest` feature, every `wgsl!` call site in the crate is listed in
+//! `OUT_DIR/wgsl_ln/manifest.txt` with the content hash of its output and its label,
+//! for asset pipelines that invalidate cached GPU pipelines when shaders change.
+//!
+//! ```text
+//! 6f1c0e5e1b2a9d4c7a8e3f0b5d2c9e1a src/terrain.rs:12:27 terrain
+//! 0b9e4f2d7c1a8e5b3f6d0c9a2e7b4f18 src/lib.rs:40:5 -
+//! ```
+//!
+//! # wgsl-analyzer
+//!
+//! With the `analyzer` feature, each shader is also written to
+//! `target/wgsl_ln/<package>/<crate>/<label>.wgsl`, so wgsl-analyzer can provide hover,
+//! go-to-definition and diagnostics for the composed shader. `mapping.txt` in the same
+//! directory lists the call site of each file.
+//!
+//! ```text
+//! HELLO.wgsl examples/hello_world.rs:6:26
+//! d0fbb6d603439523a95623bf589e2977.wgsl examples/hello_world.rs:12:26
+//! ```
+//!
+//! Building with `RUSTFLAGS="--cfg wgsl_ln_dump"` writes the same files without the feature,
+//! to inspect what composition produced without printing each shader or expanding each call site.
+//! Changing `RUSTFLAGS` rebuilds every crate, so every shader is written. `mapping.txt` requires
+//! the `analyzer` feature, and `--cfg` does not reach proc macros when `--target` is passed.
+//!
+//! # `cargo wgsl-ln`
+//!
+//! The `cargo-wgsl-ln` subcommand in this repository runs `cargo check` on a workspace
+//! and collects every validated shader, so CI can validate shaders without building every target.
+//!
+//! ```text
+//! cargo install --path cargo-wgsl-ln
+//! cargo wgsl-ln check              # validate every `wgsl!`
+//! cargo wgsl-ln dump shaders/      # write the composed shaders to `shaders/`
+//! cargo wgsl-ln exports            # list `#[wgsl_export]`s and their items
+//! cargo wgsl-ln graph --mermaid    # print the export dependency graph, Graphviz by default
+//! cargo wgsl-ln reflect TERRAIN    # print reflection data as JSON
+//! cargo wgsl-ln check -- --features fog   # pass arguments to `cargo check`
 //! ```
 //!
 //! # Ok what's actually going on?
@@ -97,7 +1322,7 @@
 //!
 //!     pub static MAGIC: &str = wgsl!(
 //!         fn magic() -> f32 {
-//!             return #magic_number();
+//!             return $magic_number();
 //!         }
 //!     )
 //! }
@@ -105,9 +1330,38 @@
 //! # */
 //! ```
 //!
+//! # Reproducible output
+//!
+//! The same shader always produces the same output string, on every platform and in every build.
+//! The output depends only on its tokens and the items pasted into it, not on spans, whitespace,
+//! file paths or the order of hash maps, so embedded shaders and `Shader::hash` are stable
+//! for reproducible builds.
+//!
+//! ```
+//! # use wgsl_ln::wgsl;
+//! pub static ONE_LINE: &str = wgsl!(fn double(x: f32) -> f32 { return x * 2.0; });
+//! pub static SPREAD: &str = wgsl!(
+//!     fn double(x: f32) -> f32 {
+//!         return x * 2.0;
+//!     }
+//! );
+//! assert_eq!(ONE_LINE, SPREAD);
+//! assert_eq!(ONE_LINE, "fn double (x:f32 )-> f32 {\nreturn x * 2.0 ;\n}\n");
+//! ```
+//!
+//! What can change the output is set in `wgsl_ln.toml` and the shader's options: `substitute`,
+//! the programs of `preprocess` and `passes`, which must be deterministic themselves,
+//! `#![canonical_order]`, and `#![obfuscate]`, which only applies to release builds.
+//! `#![hot_reload(..)]` embeds the absolute path of its file in the `Shader`.
+//! Call sites in the shader manifest and the wgsl-analyzer configuration use `/` on every platform.
+//!
 //! # `naga_oil` support
 //!
-//! Enable the `naga_oil` feature for limited `naga_oil` support:
+//! Enable the `naga_oil` feature for limited `naga_oil` support.
+//! `naga_oil` mode must be enabled explicitly with `wgsl_oil!` or `#![oil]`,
+//! otherwise `naga_oil` directives and stray `#`s are errors, use `##` for a literal `#`.
+//! If an `#import` cannot be resolved, pasted items are validated on their own unless they
+//! contain one of the directives below.
 //!
 //! * Treat `#preprocessor_macro_name` as tokens instead of imports.
 //!     * `#define_import_path`
@@ -117,85 +1371,285 @@
 //!     * `#ifndef`
 //!     * `#else`
 //!     * `#endif`
+//!     * `#define`
 //!
 //! These values can no longer be imported.
 //!
-//! * Checks will be disabled when naga_oil preprocessor macros are detected.
+//! ```compile_fail
+//! # use wgsl_ln::wgsl;
+//! pub static VERTEX_SHADER: &str = wgsl!(
+//!     // not in `naga_oil` mode
+//!     #import awesome_game_engine::Vertex;
+//! );
+//! ```
 //!
+//! * In `naga_oil` mode, the shader is validated with `naga_oil`'s `Composer`.
+//!   Modules available to `#import` can be registered with `#![modules(..)]`,
+//!   paths are relative to `CARGO_MANIFEST_DIR`.
+//!
+//! ```
+//! # /*
+//! pub static VERTEX_SHADER: &str = wgsl_oil!(
+//!     #![modules("shaders/awesome_game_engine.wgsl")]
+//!     #import awesome_game_engine::Vertex;
+//!     ...
+//! );
+//! # */
+//! ```
+//!
+//! * Exports starting with `#define_import_path` can be registered as modules
+//!   by their import path.
+//!
+//! ```
+//! # /*
+//! #[wgsl_export(lighting)]
+//! pub static LIGHTING: &str = wgsl_oil!(
+//!     #define_import_path my_game::lighting
+//!     ...
+//! );
+//!
+//! pub static LIT_SHADER: &str = wgsl_oil!(
+//!     #![modules(my_game::lighting)]
+//!     #import my_game::lighting::half_lambert;
+//!     ...
+//! );
+//! # */
+//! ```
+//!
+//! * Directives `#ifdef`, `#ifndef`, `#if DEF == 2`, `#else`, `#else ifdef` and `#endif`
+//!   are emitted on their own lines.
+//! * Shader defs can be declared with `#![defs(..)]`, using an undeclared def in
+//!   `#ifdef`, `#ifndef` or `#if` is an error. Defs may have a default value used
+//!   in validation, i.e. `#![defs(SHADOWS, MSAA_SAMPLES = 4u)]`.
+//!
+//! ```
+//! # /*
+//! pub static SHADOWED: &str = wgsl_oil!(
+//!     #![defs(SHADOWS, MSAA_SAMPLES)]
+//!     ...
+//!     #ifdef SHADOWS
+//!     ...
+//! );
+//! # */
+//! ```
+//!
+//! * `#![permutations]` validates every combination of shader defs used in
+//!   `#ifdef` and `#ifndef` instead of only the default one.
+//!
+//! * Module paths can be string literals, i.e. `#import "shaders/lighting.wgsl"::light`.
+//!   `naga_oil` reads `//` in paths like `"embedded://lighting.wgsl"` as a comment,
+//!   these imports are treated as provided at runtime.
+//!
+//! * Modules provided at runtime can be listed in a file, one per line, named by
+//!   the `WGSL_LN_IMPORTS` environment variable relative to `CARGO_MANIFEST_DIR`.
+//!   Unlisted imports sharing a root with a listed module, i.e. a typo like
+//!   `#import bevy_pbr::pbr_functionz`, emit a warning.
+//!
+//! ```toml
+//! # .cargo/config.toml
+//! [env]
+//! WGSL_LN_IMPORTS = "wgsl_imports.txt"
+//! ```
+//!
+//! * Items provided at runtime can be stubbed with `#![stub(..)]` instead,
+//!   as `fn(..) -> T`, `struct {..}`, `var: T` or `const: T`.
+//!
+//! ```
+//! # /*
+//! pub static FRAGMENT_SHADER: &str = wgsl_oil!(
+//!     #![stub(
+//!         bevy_pbr::pbr_types::PbrInput as struct { flags: u32 },
+//!         bevy_pbr::pbr_functions::pbr_input_new as fn() -> PbrInput,
+//!     )]
+//!     #import bevy_pbr::pbr_functions::pbr_input_new;
+//!     ...
+//! );
+//! # */
+//! ```
+//!
+//! * If an `#import` cannot be resolved, i.e. it is provided at runtime, only items
+//!   imported with `$item` are validated. Names they use from `#import`s must be
+//!   declared with `#![stub(..)]`.
+//!
+//! ```
+//! # /*
+//! pub static MIXED: &str = wgsl_oil!(
+//!     #import bevy_pbr::forward_io::VertexOutput
+//!     @fragment
+//!     fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+//!         return vec4($saturate_half(in.position.x));
+//!     }
+//! );
+//! # */
+//! ```
+//!
+//! * `shader_defs!` creates the shader defs passed to the `Composer` at runtime from rust
+//!   values, so they cannot diverge from the values used in rust.
+//!
+//! ```
+//! # /*
+//! let defs = shader_defs! {
+//!     MSAA_SAMPLES: u32 = MSAA_SAMPLES,
+//!     SHADOWS: bool = cfg!(feature = "shadows"),
+//!     #[cfg(feature = "fog")]
+//!     FOG,
+//! };
+//! # */
+//! ```
+//!
+//! # `wgpu` integration
+//!
+//! Enable the `wgpu` feature to use `#![wgpu]`, which emits a `wgpu::ShaderModuleDescriptor`
+//! instead of a string. The label can be set with `#![wgpu("label")]`, exported
+//! shaders are labeled with the item's name. Requires `wgpu` 0.20.
+//!
+//! ```
+//! # /*
+//! #[wgsl_export(blur)]
+//! pub const BLUR: ShaderModuleDescriptor<'static> = wgsl!(
+//!     #![wgpu]
+//!     ...
+//! );
+//!
+//! let module = device.create_shader_module(BLUR);
+//! # */
+//! ```
+//!
+//! With the `runtime` feature, `#![shader]` emits a `wgsl_ln::runtime::Shader` instead,
+//! which carries a hash of the source computed at compile time, `wgsl_ln::runtime` re-exports
+//! the `wgsl_ln_runtime` crate. `wgsl_ln::runtime::DeviceExt::get_or_create_module` creates
+//! each module once per device.
+//! Bindings used by entry points are reflected, `DeviceExt::create_pipeline_layout_for`
+//! merges them into a pipeline layout for a set of shaders, `check_layout` checks at
+//! compile time that they agree about the type of each binding.
+//! Entry points are reflected as well, `EntryPoint::workgroups` computes the number of
+//! workgroups to dispatch from the `@workgroup_size` of a compute entry point.
+//! `EntryPoint::check_targets` checks color target formats against the `@location`
+//! outputs of a fragment entry point before creating a render pipeline.
+//!
+//! `#![hot_reload("path.wgsl")]` writes the shader to a path relative to `CARGO_MANIFEST_DIR`,
+//! in debug builds `Shader::load` and `get_or_create_module` read this file instead,
+//! so edits take effect without recompiling.
+//! `runtime::set_include_resolver` re-resolves items pasted with `$name` and `#import`s
+//! against live files as well, so libraries are reloaded with the shaders including them.
+//!
+//! ```
+//! # /*
+//! pub static BLUR: Shader = wgsl!(
+//!     #![shader]
+//!     ...
+//! );
+//!
+//! let module = device.get_or_create_module(&BLUR);
+//! let (layout, bind_group_layouts) = device.create_pipeline_layout_for(None, &[&BLUR]);
+//! let workgroups = BLUR.entry_point("blur").unwrap().workgroups([width, height, 1]);
+//! # */
+//! ```
+//!
+//! # WebGL2
+//!
+//! With the `webgl` feature, every `#![shader]` also has a GLSL ES 3.00 translation of each
+//! vertex and fragment entry point in `Shader::glsl_es`, for engines with a WebGL2 fallback.
+//! WebGL2 binds resources by name, each translation lists the uniform blocks and combined
+//! texture samplers naga named with their group and binding. Entry points naga cannot translate,
+//! i.e. in shaders with `override`s, are skipped with a warning.
+//!
+//! ```
+//! # /*
+//! let fragment = QUAD.glsl_entry_point("fragment").unwrap();
+//! assert!(fragment.source.starts_with("#version 300 es"));
+//! for uniform in fragment.uniforms {
+//!     let index = gl.get_uniform_block_index(program, uniform.name);
+//! }
+//! # */
+//! ```
+//!
+//! # Bevy integration
+//!
+//! Enable the `bevy` feature to generate the boilerplate of `load_internal_asset!`
+//! for each exported shader, a `Handle<Shader>` derived from the crate and exported
+//! name and a function adding the shader to `Assets<Shader>`. Requires `bevy` 0.14.
+//!
+//! ```
+//! # /*
+//! #[wgsl_export(lighting)]
+//! pub static LIGHTING: &str = wgsl!(...);
+//!
+//! // generated:
+//! // pub const LIGHTING_HANDLE: Handle<Shader> = Handle::weak_from_u128(..);
+//! // pub fn load_lighting(app: &mut App) { .. }
+//!
+//! impl Plugin for MyPlugin {
+//!     fn build(&self, app: &mut App) {
+//!         load_lighting(app);
+//!     }
+//! }
+//! # */
+//! ```
+//!
+//! With `#![embedded_asset("path.wgsl")]`, the output is also written to `OUT_DIR`,
+//! which requires a `build.rs`. Exports instead generate an asset path constant and
+//! a function adding the file to the `embedded` asset source, like `embedded_asset!`,
+//! so the asset processor and hot reloading work.
+//!
+//! ```
+//! # /*
+//! #[wgsl_export(fog)]
+//! pub static FOG: &str = wgsl!(
+//!     #![embedded_asset("shaders/fog.wgsl")]
+//!     ...
+//! );
+//!
+//! // generated:
+//! // pub const FOG_PATH: &str = "embedded://my_crate/shaders/fog.wgsl";
+//! // pub fn embed_fog(app: &mut App) { .. }
+//! # */
+//! ```
+//!
+//! `#![material(Name)]` generates an `AsBindGroup` struct from the bindings in `@group(2)`,
+//! with `ShaderType` structs for uniform and storage buffers of structs declared in the shader.
+//! Samplers are attached to the texture before them.
+//!
+//! ```
+//! # /*
+//! #[wgsl_export(fog_material)]
+//! pub static FOG_MATERIAL: &str = wgsl!(
+//!     #![material(FogMaterial)]
+//!     struct FogSettings {
+//!         color: vec4<f32>,
+//!         density: f32,
+//!     }
+//!     @group(2) @binding(0) var<uniform> settings: FogSettings;
+//!     @group(2) @binding(1) var noise: texture_3d<f32>;
+//!     @group(2) @binding(2) var noise_sampler: sampler;
+//!     ...
+//! );
+//!
+//! // generated:
+//! // #[derive(AsBindGroup, Asset, TypePath, Clone, Debug)]
+//! // pub struct FogMaterial {
+//! //     #[uniform(0)]
+//! //     pub settings: FogSettings,
+//! //     #[texture(1, dimension = "3d")]
+//! //     #[sampler(2)]
+//! //     pub noise: Option<Handle<Image>>,
+//! // }
+//! //
+//! // #[derive(ShaderType, Clone, Debug)]
+//! // pub struct FogSettings {
+//! //     pub color: Vec4,
+//! //     pub density: f32,
+//! // }
+//! # */
+//! ```
 
-use proc_macro::TokenStream as TokenStream1;
-use proc_macro_error::{proc_macro_error, set_dummy};
-use quote::quote;
-mod __wgsl_paste2;
-mod open_close;
-mod sanitize;
-mod to_wgsl_string;
-mod wgsl2;
-mod wgsl_export2;
-
-/// Converts normal rust tokens into a wgsl `&'static str`, similar to [`stringify!`].
-/// This also validates the wgsl string using [`naga`]. Errors will be reported with
-/// the correct span.
-///
-/// ```
-/// # use wgsl_ln::wgsl;
-/// pub static MANHATTAN_DISTANCE: &str = wgsl!(
-///     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
-///         return abs(a.x - b.x) + abs(a.y - b.y);
-///     }
-/// );
-/// ```
-///
-/// To import an exported item, use the `#name` syntax. See crate level documentation for details.
-///
-/// ```
-/// # use wgsl_ln::{wgsl, wgsl_export};
-/// # #[wgsl_export(manhattan_distance)]
-/// # pub static MANHATTAN_DISTANCE: &str = wgsl!(
-/// #     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
-/// #         return abs(a.x - b.x) + abs(a.y - b.y);
-/// #     }
-/// # );
-/// pub static MANHATTAN_DISTANCE_SQUARED: &str = wgsl!(
-///     fn manhattan_distance_squared(a: vec2<f32>, b: vec2<f32>) -> f32 {
-///         return #manhattan_distance(a, b) * manhattan_distance(a, b);
-///     }
-/// );
-/// ```
-#[proc_macro]
-#[proc_macro_error]
-pub fn wgsl(stream: TokenStream1) -> TokenStream1 {
-    set_dummy(quote! {""});
-    wgsl2::wgsl2(stream.into()).into()
-}
-
-/// Export a wgsl item (function, struct, etc).
-///
-/// Must have the same `name` as the exported item.
-///
-/// ```
-/// # use wgsl_ln::{wgsl, wgsl_export};
-/// #[wgsl_export(manhattan_distance)]
-/// pub static MANHATTAN_DISTANCE: &str = wgsl!(
-///     fn manhattan_distance(a: vec2<f32>, b: vec2<f32>) -> f32 {
-///         return abs(a.x - b.x) + abs(a.y - b.y);
-///     }
-/// );
-/// ```
-#[proc_macro_attribute]
-#[proc_macro_error]
-pub fn wgsl_export(attr: TokenStream1, stream: TokenStream1) -> TokenStream1 {
-    set_dummy(quote! {""});
-
-    wgsl_export2::wgsl_export2(attr.into(), stream.into()).into()
-}
+pub use wgsl_ln_macros::*;
 
-/// Paste and avoid duplicates.
 #[doc(hidden)]
-#[proc_macro]
-#[proc_macro_error]
-pub fn __wgsl_paste(stream: TokenStream1) -> TokenStream1 {
-    set_dummy(quote! {""});
+pub mod interpolate;
+
+pub use interpolate::{substitute, SubstituteError, Value};
 
-    __wgsl_paste2::__wgsl_paste2(stream.into()).into()
-}
+/// [`wgsl_ln_runtime`], the types emitted by `#![shader]`, reflection and the GPU test harness.
+#[cfg(feature = "runtime")]
+pub use wgsl_ln_runtime as runtime;
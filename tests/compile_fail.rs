@@ -0,0 +1,55 @@
+//! Errors that must point at a specific place, checked by building a scratch crate
+//! in `target/compile_fail` and reading its diagnostics.
+
+use std::{path::Path, process::Command};
+
+/// Check a scratch crate with `source` as its `src/lib.rs`, returns the lines of its errors
+/// as `file:line:column: error: message`.
+fn errors(name: &str, source: &str) -> Vec<String> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let target = root.join("target").join("compile_fail");
+    let dir = target.join(name);
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    let manifest = format!(
+        "[package]\nname = \"{}\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+         [dependencies]\nwgsl_ln = {{ path = {:?} }}\n\n[workspace]\n",
+        name, root
+    );
+    std::fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    std::fs::write(dir.join("src").join("lib.rs"), source).unwrap();
+    // The versions this crate was built with, so no registry is needed.
+    if let Ok(lock) = std::fs::read(root.join("Cargo.lock")) {
+        std::fs::write(dir.join("Cargo.lock"), lock).unwrap();
+    }
+    let output = Command::new(env!("CARGO"))
+        .args(["check", "--quiet", "--message-format", "short"])
+        .current_dir(&dir)
+        .env("CARGO_TARGET_DIR", target.join("target"))
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter(|x| x.contains(": error: "))
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn duplicate_export() {
+    let source = r#"use wgsl_ln::{wgsl, wgsl_export};
+
+#[wgsl_export(helper)]
+pub static FIRST: &str = wgsl!(fn helper() -> f32 { return 1.0; });
+
+mod inner {
+    use wgsl_ln::{wgsl, wgsl_export};
+
+    #[wgsl_export(helper)]
+    pub static SECOND: &str = wgsl!(fn helper() -> f32 { return 2.0; });
+}
+"#;
+    assert_eq!(
+        errors("duplicate_export", source),
+        ["src/lib.rs:9:19: error: `helper` is already exported by `FIRST` at src/lib.rs:3:15."]
+    );
+}